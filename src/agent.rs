@@ -2,16 +2,23 @@ use anyhow::{Context, Result};
 use crossterm::style::Color;
 use serde_json::Value;
 use std::io::{BufRead, BufReader, IsTerminal, Write};
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+use crate::backend::{AgentBackend, AgentEvent};
+use crate::config::{ModelPrice, PermissionsConfig, PhaseLimits};
+use crate::failure::{self, FailureClass};
+use crate::process::ActivityMonitor;
 use crate::terminal;
+use crate::transcript::{Transcript, TranscriptSummary};
 
-/// Distinguishes idle-timeout kills from other agent failures.
+/// How often a silent agent's process tree is checked for CPU and I/O.
+const ACTIVITY_SAMPLE: Duration = Duration::from_secs(10);
+
+/// Distinguishes idle-timeout kills, limit stops and interrupts from other agent failures.
 #[derive(Debug)]
 pub enum AgentError {
     /// Agent was killed because it produced no output for too long.
@@ -20,8 +27,21 @@ pub enum AgentError {
         elapsed_secs: u64,
         idle_limit: u64,
     },
-    /// Any other failure (non-zero exit, spawn error, etc.).
-    Other(anyhow::Error),
+    /// Stopped on reaching a `[limits.phase]` ceiling.
+    LimitExceeded {
+        label: String,
+        breach: LimitBreach,
+        elapsed_secs: u64,
+        /// What the agent used up to that point, with an estimated cost.
+        usage: UsageInfo,
+    },
+    /// Exited unsuccessfully; `class` decides whether it is worth retrying.
+    Failed {
+        class: FailureClass,
+        message: String,
+    },
+    /// Stopped by Ctrl-C.
+    Interrupted { label: String },
 }
 
 impl std::fmt::Display for AgentError {
@@ -36,13 +56,51 @@ impl std::fmt::Display for AgentError {
                 "Agent '{}' killed after {}s idle (limit: {}s)",
                 label, elapsed_secs, idle_limit
             ),
-            AgentError::Other(e) => write!(f, "{}", e),
+            AgentError::LimitExceeded {
+                label,
+                breach,
+                elapsed_secs,
+                ..
+            } => write!(
+                f,
+                "Agent '{}' stopped after {}s: {} reached",
+                label, elapsed_secs, breach
+            ),
+            AgentError::Failed { message, .. } => write!(f, "{}", message),
+            AgentError::Interrupted { label } => {
+                write!(f, "Agent '{}' stopped by Ctrl-C", label)
+            }
         }
     }
 }
 
 impl std::error::Error for AgentError {}
 
+/// Which invocation ceiling an agent reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitBreach {
+    WallTime { limit_secs: u64 },
+    Cost { limit_usd: f64, spent_usd: f64 },
+}
+
+impl std::fmt::Display for LimitBreach {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitBreach::WallTime { limit_secs } => {
+                write!(f, "wall-clock limit of {}s", limit_secs)
+            }
+            LimitBreach::Cost {
+                limit_usd,
+                spent_usd,
+            } => write!(
+                f,
+                "cost limit of ${:.2} (~${:.2} spent)",
+                limit_usd, spent_usd
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UsageInfo {
     pub input_tokens: u64,
@@ -67,19 +125,40 @@ pub struct AgentResult {
     pub elapsed_secs: u64,
     pub tool_log: Vec<ToolCall>,
     pub usage: UsageInfo,
+    /// Markdown transcript of the invocation, if one was requested.
+    pub transcript: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum ToolCall {
-    Read { path: String },
-    Write { path: String },
-    Edit { path: String },
-    Bash { command: String },
-    Glob { pattern: String },
-    Grep { pattern: String },
-    Task { description: String },
-    Other { name: String },
+    Read {
+        path: String,
+    },
+    Write {
+        path: String,
+    },
+    Edit {
+        path: String,
+    },
+    Bash {
+        command: String,
+    },
+    /// `path` is the search root; empty means the working directory.
+    Glob {
+        pattern: String,
+        path: String,
+    },
+    Grep {
+        pattern: String,
+        path: String,
+    },
+    Task {
+        description: String,
+    },
+    Other {
+        name: String,
+    },
 }
 
 /// Shared state between NDJSON loop and ticker thread for collapsed-mode display.
@@ -89,22 +168,127 @@ struct LiveStatus {
     latest_tool: String,
 }
 
-pub fn run_agent(
-    input: &str,
-    model: &str,
-    label: &str,
-    collapse_output: bool,
-    error_log_path: Option<&Path>,
-    extra_args: &[String],
-    idle_timeout_secs: u64,
-) -> Result<AgentResult> {
+/// Everything needed to run one agent invocation.
+#[derive(Debug, Clone, Copy)]
+pub struct AgentRequest<'a> {
+    pub input: &'a str,
+    pub model: &'a str,
+    pub label: &'a str,
+    pub collapse_output: bool,
+    pub error_log_path: Option<&'a Path>,
+    pub idle_timeout_secs: u64,
+    /// Where to write the transcript, without extension (`.jsonl` and `.md` are added).
+    pub transcript_path: Option<&'a Path>,
+    /// Directory the agent runs in; the project root (lisa's cwd) when `None`.
+    pub working_dir: Option<&'a Path>,
+    /// Tool permissions for this phase; unrestricted when `None`.
+    pub permissions: Option<&'a PermissionsConfig>,
+    /// Wall-clock and cost ceilings, enforced while the agent runs.
+    pub limits: PhaseLimits,
+    /// Price of `model`, to estimate the cost while the agent runs.
+    pub price: Option<ModelPrice>,
+    /// Backend session to continue instead of starting fresh; `input` is then
+    /// only a note to the continued agent.
+    pub resume_session: Option<&'a str>,
+    /// Receives the id of the session the agent runs in.
+    pub session: Option<&'a SessionCapture>,
+}
+
+/// Session id reported by the agent, kept as soon as it is streamed so a
+/// failed or interrupted invocation can still be continued.
+#[derive(Debug, Default)]
+pub struct SessionCapture(Mutex<Option<String>>);
+
+impl SessionCapture {
+    pub fn id(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// Token usage summed over the responses an agent has streamed so far.
+#[derive(Debug, Default)]
+struct StreamedUsage {
+    responses: Vec<(Option<String>, UsageInfo)>,
+}
+
+impl StreamedUsage {
+    /// A response split over several lines reports its usage on each; keep the latest.
+    fn add(&mut self, message_id: Option<String>, usage: UsageInfo) {
+        if message_id.is_some() {
+            if let Some(entry) = self.responses.iter_mut().find(|(id, _)| *id == message_id) {
+                entry.1 = usage;
+                return;
+            }
+        }
+        self.responses.push((message_id, usage));
+    }
+
+    fn total(&self) -> UsageInfo {
+        let mut total = UsageInfo::default();
+        for (_, u) in &self.responses {
+            total.input_tokens += u.input_tokens;
+            total.output_tokens += u.output_tokens;
+            total.cache_creation_input_tokens += u.cache_creation_input_tokens;
+            total.cache_read_input_tokens += u.cache_read_input_tokens;
+        }
+        total
+    }
+}
+
+/// The first ceiling in `limits` that `elapsed` or the estimated `spent` has reached.
+fn check_limits(
+    limits: &PhaseLimits,
+    elapsed: Duration,
+    spent: Option<f64>,
+) -> Option<LimitBreach> {
+    if limits.max_wall_secs > 0 && elapsed.as_secs() >= limits.max_wall_secs {
+        return Some(LimitBreach::WallTime {
+            limit_secs: limits.max_wall_secs,
+        });
+    }
+    match spent {
+        Some(spent) if limits.max_cost_usd > 0.0 && spent >= limits.max_cost_usd => {
+            Some(LimitBreach::Cost {
+                limit_usd: limits.max_cost_usd,
+                spent_usd: spent,
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn run_agent(backend: &dyn AgentBackend, request: &AgentRequest) -> Result<AgentResult> {
+    let AgentRequest {
+        input,
+        model,
+        label,
+        collapse_output,
+        error_log_path,
+        idle_timeout_secs,
+        transcript_path,
+        limits,
+        price,
+        session,
+        ..
+    } = *request;
     let start = Instant::now();
     let mut stats = AgentStats::default();
     let mut tool_log = Vec::new();
     let mut result_text = String::new();
+    let mut result_subtype = None;
+    let mut result_is_error = false;
     let mut usage = UsageInfo::default();
 
-    terminal::log_info(&format!("Calling agent: {} (model: {})", label, model));
+    if backend.name() == crate::config::DEFAULT_BACKEND {
+        terminal::log_info(&format!("Calling agent: {} (model: {})", label, model));
+    } else {
+        terminal::log_info(&format!(
+            "Calling agent: {} (model: {}, backend: {})",
+            label,
+            model,
+            backend.name()
+        ));
+    }
 
     let is_tty = std::io::stdout().is_terminal();
     let collapsed = collapse_output && is_tty;
@@ -155,29 +339,12 @@ pub fn run_agent(
         })
     };
 
-    let mut cmd = Command::new("claude");
-    cmd.args([
-        "-p",
-        "--dangerously-skip-permissions",
-        "--verbose",
-        "--model",
-        model,
-        "--output-format",
-        "stream-json",
-    ]);
-    if !extra_args.is_empty() {
-        cmd.args(extra_args);
-    }
-    let mut child = cmd
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .context("Failed to spawn claude CLI. Is it installed and on PATH?")?;
+    let mut transcript = transcript_path.map(Transcript::create);
+    let mut process = backend.spawn(request)?;
 
     // Collect stderr in a background thread
     let stderr_handle = {
-        let stderr = child.stderr.take();
+        let stderr = process.take_stderr();
         std::thread::spawn(move || -> String {
             match stderr {
                 Some(pipe) => {
@@ -197,7 +364,7 @@ pub fn run_agent(
     };
 
     // Write input to stdin
-    if let Some(mut stdin) = child.stdin.take() {
+    if let Some(mut stdin) = process.take_stdin() {
         stdin.write_all(input.as_bytes())?;
         // stdin is dropped here, closing it
     }
@@ -207,7 +374,7 @@ pub fn run_agent(
     let (tx, rx) = mpsc::channel::<String>();
 
     let reader_handle = {
-        let stdout = child.stdout.take();
+        let stdout = process.take_stdout();
         std::thread::spawn(move || {
             if let Some(pipe) = stdout {
                 let reader = BufReader::new(pipe);
@@ -220,168 +387,218 @@ pub fn run_agent(
         })
     };
 
+    // Silence only counts as idle if the agent's process tree is not working either
     let idle_timeout = Duration::from_secs(idle_timeout_secs);
+    let sample_every = idle_timeout.min(ACTIVITY_SAMPLE);
+    let mut monitor = process.pid().map(ActivityMonitor::new);
+    let mut last_output = Instant::now();
+    let mut last_alive = last_output;
+    let mut next_report = idle_timeout;
     let mut timed_out = false;
+    let mut streamed = StreamedUsage::default();
+    let mut breach = None;
 
     loop {
-        match rx.recv_timeout(idle_timeout) {
+        match rx.recv_timeout(sample_every) {
             Ok(line) => {
-                let parsed: Value = match serde_json::from_str(&line) {
-                    Ok(v) => v,
+                last_output = Instant::now();
+                last_alive = last_output;
+                next_report = idle_timeout;
+                if let Some(t) = transcript.as_mut() {
+                    t.record_line(&line);
+                }
+                let events = match backend.parse_line(&line) {
+                    Ok(events) => events,
                     Err(e) => {
-                        if !line.trim().is_empty() {
-                            eprintln!("    [warn] NDJSON parse error: {}", e);
-                        }
+                        eprintln!("    [warn] NDJSON parse error: {}", e);
                         continue;
                     }
                 };
 
-                match parsed.get("type").and_then(|t| t.as_str()) {
-                    Some("assistant") => {
-                        if let Some(contents) = parsed
-                            .get("message")
-                            .and_then(|m| m.get("content"))
-                            .and_then(|c| c.as_array())
-                        {
-                            for item in contents {
-                                match item.get("type").and_then(|t| t.as_str()) {
-                                    Some("thinking") => {
-                                        if !collapse_output {
-                                            if let Some(thought) =
-                                                item.get("thinking").and_then(|t| t.as_str())
-                                            {
-                                                terminal::print_dim(&format!(
-                                                    "    [💭 {}] {}\n",
-                                                    terminal::ts(),
-                                                    thought
-                                                ));
-                                            }
-                                        }
-                                    }
-                                    Some("tool_use") => {
-                                        stats.tool_count += 1;
-                                        let name =
-                                            item.get("name").and_then(|n| n.as_str()).unwrap_or("");
-                                        let input_val =
-                                            item.get("input").cloned().unwrap_or(Value::Null);
-
-                                        let detail = format_tool_detail(name, &input_val);
-                                        let call = parse_tool_call(name, &input_val);
-                                        tool_log.push(call);
-
-                                        if name == "Write" || name == "Edit" {
-                                            stats.file_writes += 1;
-                                        }
-                                        if name == "Bash" {
-                                            if let Some(cmd) =
-                                                input_val.get("command").and_then(|c| c.as_str())
-                                            {
-                                                if cmd.contains("test") || cmd.contains("pytest") {
-                                                    stats.test_runs += 1;
-                                                }
-                                            }
-                                        }
-
-                                        if collapsed {
-                                            {
-                                                let mut status = live_status.lock().unwrap();
-                                                status.tool_count = stats.tool_count;
-                                                status.latest_tool = detail.clone();
-                                            }
-                                            let elapsed = start.elapsed().as_secs();
-                                            let mins = elapsed / 60;
-                                            let secs = elapsed % 60;
-                                            let line = format_collapsed_line(
-                                                label,
-                                                mins,
-                                                secs,
-                                                stats.tool_count,
-                                                &detail,
-                                            );
-                                            print!("\x1b[1A\x1b[2K  ");
-                                            terminal::print_colored(&line, Color::Cyan);
-                                            println!();
-                                        } else {
-                                            print!("    ");
-                                            terminal::print_colored(
-                                                &format!("[🔧 {}]", terminal::ts()),
-                                                Color::Magenta,
-                                            );
-                                            println!(" {}", detail);
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
+                for event in events {
+                    if let Some(t) = transcript.as_mut() {
+                        t.record_event(&event);
                     }
-                    Some("result") => {
-                        if let Some(text) = parsed.get("result").and_then(|r| r.as_str()) {
-                            result_text = text.to_string();
-                        }
-                        if let Some(cost) = parsed.get("total_cost_usd").and_then(|c| c.as_f64()) {
-                            usage.cost_usd = cost;
+                    match event {
+                        AgentEvent::Thinking(thought) => {
+                            if !collapse_output {
+                                terminal::print_dim(&format!(
+                                    "    [💭 {}] {}\n",
+                                    terminal::ts(),
+                                    thought
+                                ));
+                            }
                         }
-                        if let Some(u) = parsed.get("usage") {
-                            if let Some(v) = u.get("input_tokens").and_then(|t| t.as_u64()) {
-                                usage.input_tokens = v;
+                        AgentEvent::ToolUse {
+                            name,
+                            input: input_val,
+                        } => {
+                            stats.tool_count += 1;
+                            let detail = format_tool_detail(&name, &input_val);
+                            let call = parse_tool_call(&name, &input_val);
+                            tool_log.push(call);
+
+                            if name == "Write" || name == "Edit" {
+                                stats.file_writes += 1;
                             }
-                            if let Some(v) = u.get("output_tokens").and_then(|t| t.as_u64()) {
-                                usage.output_tokens = v;
+                            if name == "Bash" {
+                                if let Some(cmd) = input_val.get("command").and_then(|c| c.as_str())
+                                {
+                                    if cmd.contains("test") || cmd.contains("pytest") {
+                                        stats.test_runs += 1;
+                                    }
+                                }
                             }
-                            if let Some(v) = u
-                                .get("cache_creation_input_tokens")
-                                .and_then(|t| t.as_u64())
-                            {
-                                usage.cache_creation_input_tokens = v;
+
+                            if collapsed {
+                                {
+                                    let mut status = live_status.lock().unwrap();
+                                    status.tool_count = stats.tool_count;
+                                    status.latest_tool = detail.clone();
+                                }
+                                let elapsed = start.elapsed().as_secs();
+                                let mins = elapsed / 60;
+                                let secs = elapsed % 60;
+                                let line = format_collapsed_line(
+                                    label,
+                                    mins,
+                                    secs,
+                                    stats.tool_count,
+                                    &detail,
+                                );
+                                print!("\x1b[1A\x1b[2K  ");
+                                terminal::print_colored(&line, Color::Cyan);
+                                println!();
+                            } else {
+                                print!("    ");
+                                terminal::print_colored(
+                                    &format!("[🔧 {}]", terminal::ts()),
+                                    Color::Magenta,
+                                );
+                                println!(" {}", detail);
                             }
-                            if let Some(v) =
-                                u.get("cache_read_input_tokens").and_then(|t| t.as_u64())
-                            {
-                                usage.cache_read_input_tokens = v;
+                        }
+                        AgentEvent::Usage {
+                            message_id,
+                            usage: u,
+                        } => streamed.add(message_id, u),
+                        AgentEvent::Session(id) => {
+                            if let Some(session) = session {
+                                *session.0.lock().unwrap() = Some(id);
                             }
                         }
+                        AgentEvent::Result {
+                            text,
+                            usage: u,
+                            subtype,
+                            is_error,
+                        } => {
+                            if let Some(text) = text {
+                                result_text = text;
+                            }
+                            usage = u;
+                            result_subtype = subtype;
+                            result_is_error = is_error;
+                        }
                     }
-                    _ => {}
                 }
             }
             Err(mpsc::RecvTimeoutError::Timeout) => {
-                // No output for idle_timeout_secs — kill the agent
-                terminal::log_warn(&format!(
-                    "Agent '{}' idle for {}s — killing process.",
-                    label, idle_timeout_secs
-                ));
-                if let Err(e) = child.kill() {
-                    terminal::log_warn(&format!("Failed to kill agent process: {}", e));
+                if let Some(activity) = monitor.as_mut().and_then(|m| m.sample()) {
+                    last_alive = Instant::now();
+                    let silent = last_output.elapsed();
+                    if silent >= next_report {
+                        terminal::log_info(&format!(
+                            "Agent '{}' silent for {}s but still working ({} since the last check) — not idle.",
+                            label,
+                            silent.as_secs(),
+                            activity
+                        ));
+                        next_report += idle_timeout;
+                    }
+                }
+                if last_alive.elapsed() >= idle_timeout {
+                    // No output and no CPU or I/O for idle_timeout_secs — kill the agent
+                    terminal::log_warn(&format!(
+                        "Agent '{}' idle for {}s (no output, CPU or I/O) — stopping it and its child processes.",
+                        label, idle_timeout_secs
+                    ));
+                    if let Err(e) = process.kill() {
+                        terminal::log_warn(&format!("Failed to kill agent process: {}", e));
+                    }
+                    timed_out = true;
+                    break;
                 }
-                timed_out = true;
-                break;
             }
             Err(mpsc::RecvTimeoutError::Disconnected) => {
                 // Reader thread finished (stdout closed) — agent is done
                 break;
             }
         }
+
+        let spent = price.map(|p| crate::usage::estimate_cost(&p, &streamed.total()));
+        if let Some(hit) = check_limits(&limits, start.elapsed(), spent) {
+            terminal::log_warn(&format!(
+                "Agent '{}' reached its {} — stopping it and its child processes.",
+                label, hit
+            ));
+            if let Err(e) = process.kill() {
+                terminal::log_warn(&format!("Failed to kill agent process: {}", e));
+            }
+            breach = Some(hit);
+            break;
+        }
     }
 
-    let _ = reader_handle.join();
+    // After stopping the agent an escaped descendant may still hold stdout open
+    if !timed_out && breach.is_none() {
+        let _ = reader_handle.join();
+    }
 
-    let status = child.wait().context("Failed to wait for claude process")?;
+    let exit_code = process
+        .wait()
+        .with_context(|| format!("Failed to wait for {} process", backend.name()))?;
     let stderr_output = stderr_handle.join().unwrap_or_default();
 
     // Stop ticker thread
     ticker_running.store(false, Ordering::Relaxed);
     let _ = ticker_handle.join();
 
-    if timed_out || !status.success() {
-        let code = status.code().unwrap_or(-1);
+    let interrupted = crate::interrupt::requested();
+    if timed_out || interrupted || breach.is_some() || result_is_error || exit_code != Some(0) {
+        let code = exit_code.unwrap_or(-1);
         let elapsed = start.elapsed().as_secs();
 
-        let failure_reason = if timed_out {
+        let class = (!interrupted && breach.is_none() && !timed_out).then(|| {
+            failure::classify(
+                result_subtype.as_deref(),
+                &result_text,
+                &stderr_output,
+                chrono::Local::now(),
+            )
+        });
+        let failure_reason = if interrupted {
+            "INTERRUPTED (Ctrl-C)".to_string()
+        } else if let Some(hit) = breach {
+            format!("LIMIT REACHED ({})", hit)
+        } else if timed_out {
             format!("IDLE TIMEOUT ({}s no output)", idle_timeout_secs)
+        } else if let Some(class) = &class {
+            format!("FAILED exit {} ({})", code, class)
         } else {
             format!("FAILED exit {}", code)
         };
+        let transcript = transcript.map(|t| {
+            t.finish(&TranscriptSummary {
+                label,
+                model,
+                backend: backend.name(),
+                outcome: &failure_reason,
+                elapsed_secs: elapsed,
+                input,
+                stderr: &stderr_output,
+            })
+        });
 
         // Show stderr if present
         if !stderr_output.trim().is_empty() {
@@ -421,6 +638,9 @@ pub fn run_agent(
             content.push_str(&format!("- **Exit code:** {}\n", code));
             content.push_str(&format!("- **Elapsed:** {}s\n", elapsed));
             content.push_str(&format!("- **Tool count:** {}\n", stats.tool_count));
+            if let Some(t) = &transcript {
+                content.push_str(&format!("- **Transcript:** {}\n", t.display()));
+            }
             content.push_str("\n## Last 10 Tool Calls\n\n");
             let last_n: Vec<&ToolCall> = tool_log.iter().rev().take(10).collect();
             for (i, call) in last_n.iter().rev().enumerate() {
@@ -440,7 +660,22 @@ pub fn run_agent(
             let _ = std::fs::write(path, &content);
         }
 
-        if timed_out {
+        if interrupted {
+            return Err(AgentError::Interrupted {
+                label: label.to_string(),
+            }
+            .into());
+        } else if let Some(breach) = breach {
+            let mut usage = streamed.total();
+            usage.cost_usd = price.map_or(0.0, |p| crate::usage::estimate_cost(&p, &usage));
+            return Err(AgentError::LimitExceeded {
+                label: label.to_string(),
+                breach,
+                elapsed_secs: elapsed,
+                usage,
+            }
+            .into());
+        } else if timed_out {
             return Err(AgentError::IdleTimeout {
                 label: label.to_string(),
                 elapsed_secs: elapsed,
@@ -448,14 +683,39 @@ pub fn run_agent(
             }
             .into());
         } else {
-            return Err(AgentError::Other(anyhow::anyhow!(
-                "Agent '{}' exited with code {}. Check the output above for errors. Run `lisa resume` to retry this phase.",
-                label, code
-            ))
+            let class = class.unwrap_or(FailureClass::Fatal);
+            let hint = match class {
+                FailureClass::Auth => "Log in to the agent CLI again, then run `lisa resume`.",
+                FailureClass::Unavailable => {
+                    "Check the model name in [models], or list fallbacks (e.g. build = [\"sonnet\", \"haiku\"]), then run `lisa resume`."
+                }
+                FailureClass::MaxTurns => {
+                    "Raise its turn limit (e.g. --max-turns in [agent] extra_args), then run `lisa resume`."
+                }
+                _ => "Check the output above for errors. Run `lisa resume` to retry this phase.",
+            };
+            return Err(AgentError::Failed {
+                message: format!(
+                    "Agent '{}' exited with code {} ({} failure). {}",
+                    label, code, class, hint
+                ),
+                class,
+            }
             .into());
         }
     }
     let elapsed = start.elapsed().as_secs();
+    let transcript = transcript.map(|t| {
+        t.finish(&TranscriptSummary {
+            label,
+            model,
+            backend: backend.name(),
+            outcome: "OK",
+            elapsed_secs: elapsed,
+            input,
+            stderr: &stderr_output,
+        })
+    });
 
     // Print summary
     let mut summary = format!("{} tools", stats.tool_count);
@@ -496,6 +756,7 @@ pub fn run_agent(
         elapsed_secs: elapsed,
         tool_log,
         usage,
+        transcript,
     })
 }
 
@@ -571,8 +832,8 @@ pub fn format_tool_call_summary(call: &ToolCall) -> String {
             let first_line = command.lines().next().unwrap_or("");
             format!("Bash $ {}", truncate_str(first_line, 60))
         }
-        ToolCall::Glob { pattern } => format!("Glob {}", pattern),
-        ToolCall::Grep { pattern } => format!("Grep {}", pattern),
+        ToolCall::Glob { pattern, path } => format_search("Glob", pattern, path),
+        ToolCall::Grep { pattern, path } => format_search("Grep", pattern, path),
         ToolCall::Task { description } => {
             format!("Task {}", truncate_str(description, 50))
         }
@@ -580,6 +841,14 @@ pub fn format_tool_call_summary(call: &ToolCall) -> String {
     }
 }
 
+fn format_search(tool: &str, pattern: &str, path: &str) -> String {
+    if path.is_empty() {
+        format!("{} {}", tool, pattern)
+    } else {
+        format!("{} {} in {}", tool, pattern, path)
+    }
+}
+
 fn format_tool_detail(name: &str, input: &Value) -> String {
     match name {
         "Read" => {
@@ -663,6 +932,11 @@ fn parse_tool_call(name: &str, input: &Value) -> ToolCall {
                 .and_then(|p| p.as_str())
                 .unwrap_or("")
                 .to_string(),
+            path: input
+                .get("path")
+                .and_then(|p| p.as_str())
+                .unwrap_or("")
+                .to_string(),
         },
         "Grep" => ToolCall::Grep {
             pattern: input
@@ -670,6 +944,11 @@ fn parse_tool_call(name: &str, input: &Value) -> ToolCall {
                 .and_then(|p| p.as_str())
                 .unwrap_or("")
                 .to_string(),
+            path: input
+                .get("path")
+                .and_then(|p| p.as_str())
+                .unwrap_or("")
+                .to_string(),
         },
         "Task" => ToolCall::Task {
             description: input
@@ -766,6 +1045,17 @@ mod tests {
         assert!(summary.contains("..."));
     }
 
+    #[test]
+    fn test_parse_tool_call_grep_path() {
+        let call = parse_tool_call(
+            "Grep",
+            &serde_json::json!({"pattern": "fn solve", "path": "src"}),
+        );
+        assert_eq!(format_tool_call_summary(&call), "Grep fn solve in src");
+        let call = parse_tool_call("Glob", &serde_json::json!({"pattern": "**/*.py"}));
+        assert_eq!(format_tool_call_summary(&call), "Glob **/*.py");
+    }
+
     #[test]
     fn test_format_tool_call_summary_other() {
         let call = ToolCall::Other {
@@ -773,4 +1063,133 @@ mod tests {
         };
         assert_eq!(format_tool_call_summary(&call), "WebSearch");
     }
+
+    fn silent_agent(script: &str) -> crate::backend::CommandBackend {
+        let config: crate::config::BackendConfig = toml::from_str(&format!(
+            "command = \"bash\"\nargs = [\"-c\", {:?}]\nmodel_flag = \"\"",
+            script
+        ))
+        .unwrap();
+        crate::backend::CommandBackend::new("test", &config)
+    }
+
+    fn request(idle_timeout_secs: u64) -> AgentRequest<'static> {
+        AgentRequest {
+            input: "",
+            model: "",
+            label: "test",
+            collapse_output: true,
+            error_log_path: None,
+            idle_timeout_secs,
+            transcript_path: None,
+            working_dir: None,
+            permissions: None,
+            limits: PhaseLimits::default(),
+            price: None,
+            resume_session: None,
+            session: None,
+        }
+    }
+
+    #[test]
+    fn test_silent_but_working_agent_is_not_idle() {
+        let backend = silent_agent(
+            r#"timeout 3 bash -c 'while :; do :; done'; echo '{"type":"result","result":"done"}'"#,
+        );
+        let result = run_agent(&backend, &request(1)).unwrap();
+        assert_eq!(result.result_text, "done");
+    }
+
+    #[test]
+    fn test_silent_idle_agent_is_stopped() {
+        let backend = silent_agent("sleep 30");
+        let started = Instant::now();
+        let err = run_agent(&backend, &request(1)).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert!(matches!(
+            err.downcast_ref::<AgentError>(),
+            Some(AgentError::IdleTimeout { .. })
+        ));
+    }
+
+    #[test]
+    fn test_wall_clock_limit_stops_busy_agent() {
+        let backend = silent_agent(r#"while :; do echo '{"type":"system"}'; sleep 0.2; done"#);
+        let mut req = request(30);
+        req.limits.max_wall_secs = 1;
+        let err = run_agent(&backend, &req).unwrap_err();
+        match err.downcast_ref::<AgentError>() {
+            Some(AgentError::LimitExceeded { breach, .. }) => {
+                assert_eq!(*breach, LimitBreach::WallTime { limit_secs: 1 })
+            }
+            other => panic!("Expected LimitExceeded, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cost_limit_uses_streamed_usage() {
+        // Two lines of the same response, then a second response: 3M output tokens in total
+        let response = |id: &str, out: u64| {
+            format!(
+                r#"echo '{{"type":"assistant","message":{{"id":"{}","usage":{{"output_tokens":{}}},"content":[]}}}}'"#,
+                id, out
+            )
+        };
+        let script = format!(
+            "{}; {}; {}; sleep 30",
+            response("a", 500_000),
+            response("a", 1_000_000),
+            response("b", 2_000_000)
+        );
+        let backend = silent_agent(&script);
+        let mut req = request(30);
+        req.model = "sonnet";
+        req.limits.max_cost_usd = 40.0;
+        req.price = Some(ModelPrice {
+            input: 3.0,
+            output: 15.0,
+        });
+        let started = Instant::now();
+        let err = run_agent(&backend, &req).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        match err.downcast_ref::<AgentError>() {
+            Some(AgentError::LimitExceeded {
+                breach: LimitBreach::Cost { spent_usd, .. },
+                usage,
+                ..
+            }) => {
+                assert!((spent_usd - 45.0).abs() < 1e-9, "{}", spent_usd);
+                assert_eq!(usage.output_tokens, 3_000_000);
+            }
+            other => panic!("Expected a cost breach, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_failures_are_classified() {
+        let class = |script: &str| match run_agent(&silent_agent(script), &request(30))
+            .unwrap_err()
+            .downcast_ref::<AgentError>()
+        {
+            Some(AgentError::Failed { class, .. }) => class.clone(),
+            other => panic!("Expected Failed, got {:?}", other),
+        };
+        assert_eq!(
+            class(
+                r#"echo '{"type":"result","subtype":"error_max_turns","is_error":true}'; exit 1"#
+            ),
+            FailureClass::MaxTurns
+        );
+        assert_eq!(
+            class("echo 'API Error: 529 overloaded_error' >&2; exit 1"),
+            FailureClass::Transient
+        );
+        // An error result fails the invocation even with exit code 0
+        assert_eq!(
+            class(
+                r#"echo '{"type":"result","is_error":true,"result":"Invalid API key · Please run /login"}'"#
+            ),
+            FailureClass::Auth
+        );
+    }
 }
@@ -0,0 +1,695 @@
+//! Agent CLIs lisa can drive, and the events decoded from their output.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+use crate::agent::{AgentRequest, UsageInfo};
+use crate::config::{BackendConfig, Config, ModelSpec, PermissionsConfig, DEFAULT_BACKEND};
+use crate::recording::{RecordingBackend, ReplayBackend, ReplaySlot};
+
+/// A single event decoded from an agent's output stream.
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    /// Extended thinking text.
+    Thinking(String),
+    /// The agent invoked a tool.
+    ToolUse { name: String, input: Value },
+    /// Token usage of one model response, streamed while the agent runs. A
+    /// response split over several lines repeats the same `message_id`.
+    Usage {
+        message_id: Option<String>,
+        usage: UsageInfo,
+    },
+    /// Final result of the invocation, with usage accounting.
+    Result {
+        text: Option<String>,
+        usage: UsageInfo,
+        /// `success`, `error_max_turns`, `error_during_execution`, ...
+        subtype: Option<String>,
+        is_error: bool,
+    },
+    /// Id of the backend session, which can later be continued.
+    Session(String),
+}
+
+/// A coding-agent CLI that lisa can drive.
+///
+/// Implementations start the agent with the prompt to be written on stdin,
+/// and decode each stdout line into zero or more [`AgentEvent`]s.
+pub trait AgentBackend {
+    /// Short name shown in logs and errors.
+    fn name(&self) -> &str;
+
+    /// Start the agent for `request.model`. The prompt has not been written yet.
+    fn spawn(&self, request: &AgentRequest) -> Result<Box<dyn AgentProcess>>;
+
+    /// Decode one line of stdout.
+    fn parse_line(&self, line: &str) -> Result<Vec<AgentEvent>>;
+
+    /// Whether `spawn` can continue a session given in `request.resume_session`.
+    fn supports_resume(&self) -> bool {
+        false
+    }
+}
+
+/// A running agent invocation. Each pipe can be taken once.
+pub trait AgentProcess: Send {
+    fn take_stdin(&mut self) -> Option<Box<dyn Write + Send>>;
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>>;
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>>;
+    /// Stop the agent and any processes it started.
+    fn kill(&mut self) -> Result<()>;
+    /// Wait for the agent to exit. Returns the exit code, or `None` if it was killed by a signal.
+    fn wait(&mut self) -> Result<Option<i32>>;
+    /// OS process id, for activity sampling; `None` when there is no real process.
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+}
+
+/// An agent running as an OS subprocess, leading its own process group so
+/// that stopping it also stops whatever it started.
+pub struct ChildProcess(Child);
+
+impl ChildProcess {
+    fn new(child: Child) -> Self {
+        crate::interrupt::track(child.id());
+        ChildProcess(child)
+    }
+
+    /// SIGTERM the agent's process group, SIGKILL after a grace period, and
+    /// report descendants that escaped the group.
+    fn terminate(&mut self) -> Result<()> {
+        #[cfg(unix)]
+        {
+            let orphans = crate::process::terminate_group(self.0.id(), crate::process::TERM_GRACE);
+            crate::process::report_orphans("the agent", &orphans);
+            Ok(())
+        }
+        #[cfg(not(unix))]
+        {
+            Ok(self.0.kill()?)
+        }
+    }
+}
+
+impl AgentProcess for ChildProcess {
+    fn take_stdin(&mut self) -> Option<Box<dyn Write + Send>> {
+        self.0
+            .stdin
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Write + Send>)
+    }
+
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.0
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>)
+    }
+
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.0
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>)
+    }
+
+    fn kill(&mut self) -> Result<()> {
+        self.terminate()
+    }
+
+    fn wait(&mut self) -> Result<Option<i32>> {
+        let status = self.0.wait()?;
+        // A failed agent may leave solvers or servers behind in its group
+        #[cfg(unix)]
+        if !status.success() && crate::process::group_running(self.0.id()) {
+            self.terminate()?;
+        }
+        crate::interrupt::untrack(self.0.id());
+        Ok(status.code())
+    }
+
+    fn pid(&self) -> Option<u32> {
+        Some(self.0.id())
+    }
+}
+
+/// The Claude Code CLI (`claude -p ... --output-format stream-json`).
+pub struct ClaudeBackend {
+    extra_args: Vec<String>,
+}
+
+impl ClaudeBackend {
+    pub fn new(extra_args: &[String]) -> Self {
+        Self {
+            extra_args: extra_args.to_vec(),
+        }
+    }
+
+    /// Permission prompts are skipped unless the phase has allow rules in
+    /// `[permissions]`; deny rules apply either way.
+    fn build_command(&self, request: &AgentRequest) -> Command {
+        let mut cmd = Command::new("claude");
+        cmd.arg("-p");
+        if request.permissions.is_none_or(PermissionsConfig::deny_only) {
+            cmd.arg("--dangerously-skip-permissions");
+        }
+        cmd.args([
+            "--verbose",
+            "--model",
+            request.model,
+            "--output-format",
+            "stream-json",
+        ]);
+        if let Some(session) = request.resume_session {
+            cmd.args(["--resume", session]);
+        }
+        if let Some(permissions) = request.permissions {
+            permission_args(&mut cmd, "--allowedTools", "--disallowedTools", permissions);
+        }
+        if !self.extra_args.is_empty() {
+            cmd.args(&self.extra_args);
+        }
+        cmd
+    }
+}
+
+impl AgentBackend for ClaudeBackend {
+    fn name(&self) -> &str {
+        DEFAULT_BACKEND
+    }
+
+    fn supports_resume(&self) -> bool {
+        true
+    }
+
+    fn spawn(&self, request: &AgentRequest) -> Result<Box<dyn AgentProcess>> {
+        let child = piped(&mut self.build_command(request), request)
+            .spawn()
+            .context("Failed to spawn claude CLI. Is it installed and on PATH?")?;
+        Ok(Box::new(ChildProcess::new(child)))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<AgentEvent>> {
+        parse_stream_json(line)
+    }
+}
+
+/// A user-defined CLI from `[agent.backends.<name>]` that speaks Claude's stream-json.
+pub struct CommandBackend {
+    name: String,
+    config: BackendConfig,
+}
+
+impl CommandBackend {
+    pub fn new(name: &str, config: &BackendConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            config: config.clone(),
+        }
+    }
+
+    fn build_command(&self, request: &AgentRequest) -> Result<Command> {
+        let mut cmd = Command::new(&self.config.command);
+        cmd.args(&self.config.args);
+        if !self.config.model_flag.is_empty() && !request.model.is_empty() {
+            cmd.arg(&self.config.model_flag).arg(request.model);
+        }
+        if let Some(session) = request.resume_session.filter(|_| self.supports_resume()) {
+            cmd.arg(&self.config.resume_flag).arg(session);
+        }
+        if let Some(permissions) = request.permissions {
+            if self.config.allowed_tools_flag.is_empty() {
+                anyhow::bail!(
+                    "Agent backend '{}' cannot enforce [permissions] for '{}': \
+                     set allowed_tools_flag in [agent.backends.{}]",
+                    self.name,
+                    request.label,
+                    self.name
+                );
+            }
+            if self.config.disallowed_tools_flag.is_empty()
+                && !permissions.disallowed_tools.is_empty()
+            {
+                anyhow::bail!(
+                    "Agent backend '{}' cannot enforce the disallowed_tools of [permissions] \
+                     for '{}': set disallowed_tools_flag in [agent.backends.{}]",
+                    self.name,
+                    request.label,
+                    self.name
+                );
+            }
+            permission_args(
+                &mut cmd,
+                &self.config.allowed_tools_flag,
+                &self.config.disallowed_tools_flag,
+                permissions,
+            );
+        }
+        Ok(cmd)
+    }
+}
+
+impl AgentBackend for CommandBackend {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spawn(&self, request: &AgentRequest) -> Result<Box<dyn AgentProcess>> {
+        let child = piped(&mut self.build_command(request)?, request)
+            .spawn()
+            .with_context(|| {
+                format!(
+                    "Failed to spawn '{}' for agent backend '{}'. Is it installed and on PATH?",
+                    self.config.command, self.name
+                )
+            })?;
+        Ok(Box::new(ChildProcess::new(child)))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<AgentEvent>> {
+        parse_stream_json(line)
+    }
+
+    fn supports_resume(&self) -> bool {
+        !self.config.resume_flag.is_empty()
+    }
+}
+
+thread_local! {
+    static RUN: RefCell<Option<ReplaySlot>> = const { RefCell::new(None) };
+}
+
+/// Run `f` as one run: every replay [`resolve`] builds on this thread shares
+/// one cursor, whichever config it is given.
+pub fn in_run<T>(f: impl FnOnce() -> T) -> T {
+    let previous = RUN.with(|r| r.replace(Some(ReplaySlot::default())));
+    let result = f();
+    RUN.with(|r| *r.borrow_mut() = previous);
+    result
+}
+
+/// Build the backend a model spec points at, wrapped for recording or
+/// replaced by a replay when `[agent] record` / `replay` are set.
+pub fn resolve(
+    spec: &ModelSpec,
+    config: &Config,
+    project_root: &Path,
+) -> Result<Box<dyn AgentBackend>> {
+    let lisa_root = config.lisa_root(project_root);
+    if !config.agent.replay.is_empty() {
+        let dir = project_root.join(&config.agent.replay);
+        return match RUN.with(|r| r.borrow().clone()) {
+            Some(slot) => Ok(Box::new(slot.get_or_load(&dir, project_root)?)),
+            None => Ok(Box::new(ReplayBackend::new(&dir, project_root)?)),
+        };
+    }
+
+    let backend: Box<dyn AgentBackend> = if spec.backend == DEFAULT_BACKEND {
+        Box::new(ClaudeBackend::new(&config.agent.extra_args))
+    } else {
+        match config.agent.backends.get(&spec.backend) {
+            Some(backend) => Box::new(CommandBackend::new(&spec.backend, backend)),
+            None => anyhow::bail!(
+                "Unknown agent backend '{}'. Declare it under [agent.backends.{}] in lisa.toml.",
+                spec.backend,
+                spec.backend
+            ),
+        }
+    };
+
+    if config.agent.record {
+        return Ok(Box::new(RecordingBackend::new(
+            backend,
+            &lisa_root.join("recordings"),
+            project_root,
+            &lisa_root,
+        )));
+    }
+    Ok(backend)
+}
+
+/// Pass each allow and deny rule as a value of its flag. Rules are separate
+/// arguments since patterns like `Bash(git diff:*)` contain spaces.
+fn permission_args(cmd: &mut Command, allow: &str, deny: &str, permissions: &PermissionsConfig) {
+    let allowed = permissions.allow_rules();
+    if !allowed.is_empty() {
+        cmd.arg(allow).args(&allowed);
+    }
+    if !permissions.disallowed_tools.is_empty() {
+        cmd.arg(deny).args(&permissions.disallowed_tools);
+    }
+}
+
+/// Pipe stdio and run in the request's working directory, in a new process group.
+fn piped<'a>(cmd: &'a mut Command, request: &AgentRequest) -> &'a mut Command {
+    if let Some(dir) = request
+        .working_dir
+        .map(Path::to_path_buf)
+        .or_else(crate::process::workdir)
+    {
+        cmd.current_dir(dir);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+}
+
+/// Decode one line of Claude-style stream-json (NDJSON).
+pub fn parse_stream_json(line: &str) -> Result<Vec<AgentEvent>> {
+    if line.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let parsed: Value = serde_json::from_str(line)?;
+    let mut events = Vec::new();
+
+    // `system` (init) and `result` lines both carry the session id
+    if let Some(id) = parsed.get("session_id").and_then(|s| s.as_str()) {
+        events.push(AgentEvent::Session(id.to_string()));
+    }
+
+    match parsed.get("type").and_then(|t| t.as_str()) {
+        Some("assistant") => {
+            let message = parsed.get("message");
+            if let Some(u) = message.and_then(|m| m.get("usage")) {
+                events.push(AgentEvent::Usage {
+                    message_id: message
+                        .and_then(|m| m.get("id"))
+                        .and_then(|i| i.as_str())
+                        .map(String::from),
+                    usage: parse_usage(u),
+                });
+            }
+            let contents = message
+                .and_then(|m| m.get("content"))
+                .and_then(|c| c.as_array());
+            for item in contents.into_iter().flatten() {
+                match item.get("type").and_then(|t| t.as_str()) {
+                    Some("thinking") => {
+                        if let Some(thought) = item.get("thinking").and_then(|t| t.as_str()) {
+                            events.push(AgentEvent::Thinking(thought.to_string()));
+                        }
+                    }
+                    Some("tool_use") => {
+                        let name = item.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                        events.push(AgentEvent::ToolUse {
+                            name: name.to_string(),
+                            input: item.get("input").cloned().unwrap_or(Value::Null),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Some("result") => {
+            let mut usage = parsed.get("usage").map(parse_usage).unwrap_or_default();
+            if let Some(cost) = parsed.get("total_cost_usd").and_then(|c| c.as_f64()) {
+                usage.cost_usd = cost;
+            }
+            events.push(AgentEvent::Result {
+                text: parsed
+                    .get("result")
+                    .and_then(|r| r.as_str())
+                    .map(String::from),
+                usage,
+                subtype: parsed
+                    .get("subtype")
+                    .and_then(|s| s.as_str())
+                    .map(String::from),
+                is_error: parsed
+                    .get("is_error")
+                    .and_then(|e| e.as_bool())
+                    .unwrap_or(false),
+            });
+        }
+        _ => {}
+    }
+
+    Ok(events)
+}
+
+fn parse_usage(u: &Value) -> UsageInfo {
+    let field = |key: &str| u.get(key).and_then(|t| t.as_u64()).unwrap_or(0);
+    UsageInfo {
+        input_tokens: field("input_tokens"),
+        output_tokens: field("output_tokens"),
+        cache_creation_input_tokens: field("cache_creation_input_tokens"),
+        cache_read_input_tokens: field("cache_read_input_tokens"),
+        cost_usd: 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assistant_events() {
+        let line = r#"{"type":"assistant","message":{"content":[
+            {"type":"thinking","thinking":"plan first"},
+            {"type":"text","text":"ignored"},
+            {"type":"tool_use","name":"Read","input":{"file_path":"src/lib.rs"}}
+        ]}}"#
+            .replace('\n', "");
+        let events = parse_stream_json(&line).unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], AgentEvent::Thinking(t) if t == "plan first"));
+        match &events[1] {
+            AgentEvent::ToolUse { name, input } => {
+                assert_eq!(name, "Read");
+                assert_eq!(input["file_path"], "src/lib.rs");
+            }
+            other => panic!("Expected ToolUse, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_result_event() {
+        let line = r#"{"type":"result","subtype":"success","is_error":false,"result":"done","total_cost_usd":0.25,"usage":{"input_tokens":100,"output_tokens":20,"cache_read_input_tokens":5}}"#;
+        let events = parse_stream_json(line).unwrap();
+        match &events[0] {
+            AgentEvent::Result {
+                text,
+                usage,
+                subtype,
+                is_error,
+            } => {
+                assert_eq!(text.as_deref(), Some("done"));
+                assert_eq!(subtype.as_deref(), Some("success"));
+                assert!(!is_error);
+                assert_eq!(usage.input_tokens, 100);
+                assert_eq!(usage.output_tokens, 20);
+                assert_eq!(usage.cache_creation_input_tokens, 0);
+                assert_eq!(usage.cache_read_input_tokens, 5);
+                assert!((usage.cost_usd - 0.25).abs() < 1e-9);
+            }
+            other => panic!("Expected Result, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_streamed_usage() {
+        let line = r#"{"type":"assistant","message":{"id":"msg_1","usage":{"input_tokens":7,"output_tokens":3},"content":[{"type":"text","text":"hi"}]}}"#;
+        let events = parse_stream_json(line).unwrap();
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            AgentEvent::Usage { message_id, usage } => {
+                assert_eq!(message_id.as_deref(), Some("msg_1"));
+                assert_eq!(usage.input_tokens, 7);
+                assert_eq!(usage.output_tokens, 3);
+            }
+            other => panic!("Expected Usage, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_session_id() {
+        let init = r#"{"type":"system","subtype":"init","session_id":"9a7c","model":"opus"}"#;
+        let events = parse_stream_json(init).unwrap();
+        assert!(matches!(&events[..], [AgentEvent::Session(id)] if id == "9a7c"));
+
+        let result = r#"{"type":"result","subtype":"success","result":"ok","session_id":"9a7c"}"#;
+        let events = parse_stream_json(result).unwrap();
+        assert!(matches!(&events[0], AgentEvent::Session(id) if id == "9a7c"));
+        assert!(matches!(&events[1], AgentEvent::Result { .. }));
+    }
+
+    #[test]
+    fn test_parse_ignores_blank_and_unknown() {
+        assert!(parse_stream_json("   ").unwrap().is_empty());
+        assert!(parse_stream_json(r#"{"type":"system"}"#)
+            .unwrap()
+            .is_empty());
+        assert!(parse_stream_json("not json").is_err());
+    }
+
+    fn request<'a>(permissions: Option<&'a PermissionsConfig>) -> AgentRequest<'a> {
+        AgentRequest {
+            input: "",
+            model: "big",
+            label: "Build: task 1 iter 1",
+            collapse_output: true,
+            error_log_path: None,
+            idle_timeout_secs: 30,
+            transcript_path: None,
+            working_dir: None,
+            permissions,
+            limits: Default::default(),
+            price: None,
+            resume_session: None,
+            session: None,
+        }
+    }
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    fn build_permissions() -> PermissionsConfig {
+        PermissionsConfig {
+            allowed_tools: vec!["Read".to_string()],
+            disallowed_tools: vec!["WebFetch".to_string()],
+            bash: vec!["git diff:*".to_string()],
+            writable: vec!["src/**".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_command_backend_args() {
+        let mut config = BackendConfig {
+            command: "other-agent".to_string(),
+            args: vec!["exec".to_string()],
+            model_flag: "-m".to_string(),
+            allowed_tools_flag: String::new(),
+            disallowed_tools_flag: String::new(),
+            resume_flag: String::new(),
+        };
+        let backend = CommandBackend::new("other", &config);
+        let cmd = backend.build_command(&request(None)).unwrap();
+        assert_eq!(cmd.get_program(), "other-agent");
+        assert_eq!(args(&cmd), vec!["exec", "-m", "big"]);
+
+        // Permissions cannot be dropped silently
+        let permissions = build_permissions();
+        assert!(backend.build_command(&request(Some(&permissions))).is_err());
+
+        // Neither can deny rules
+        config.allowed_tools_flag = "--allow".to_string();
+        let backend = CommandBackend::new("other", &config);
+        assert!(backend.build_command(&request(Some(&permissions))).is_err());
+
+        config.disallowed_tools_flag = "--deny".to_string();
+        let backend = CommandBackend::new("other", &config);
+        let cmd = backend.build_command(&request(Some(&permissions))).unwrap();
+        assert_eq!(
+            args(&cmd),
+            vec![
+                "exec",
+                "-m",
+                "big",
+                "--allow",
+                "Read",
+                "Bash(git diff:*)",
+                "Edit(src/**)",
+                "--deny",
+                "WebFetch"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_claude_permission_flags() {
+        let backend = ClaudeBackend::new(&[]);
+        let unrestricted = args(&backend.build_command(&request(None)));
+        assert!(unrestricted.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(!unrestricted.contains(&"--allowedTools".to_string()));
+
+        let permissions = build_permissions();
+        let restricted = args(&backend.build_command(&request(Some(&permissions))));
+        assert!(!restricted.contains(&"--dangerously-skip-permissions".to_string()));
+        let allow = restricted
+            .iter()
+            .position(|a| a == "--allowedTools")
+            .unwrap();
+        assert_eq!(
+            restricted[allow + 1..allow + 4],
+            ["Read", "Bash(git diff:*)", "Edit(src/**)"]
+        );
+        let deny = restricted
+            .iter()
+            .position(|a| a == "--disallowedTools")
+            .unwrap();
+        assert_eq!(restricted[deny + 1], "WebFetch");
+
+        let deny_only = PermissionsConfig {
+            disallowed_tools: vec!["Read(//project/**)".to_string()],
+            ..Default::default()
+        };
+        let denied = args(&backend.build_command(&request(Some(&deny_only))));
+        assert!(denied.contains(&"--dangerously-skip-permissions".to_string()));
+        assert!(!denied.contains(&"--allowedTools".to_string()));
+        let deny = denied
+            .iter()
+            .position(|a| a == "--disallowedTools")
+            .unwrap();
+        assert_eq!(denied[deny + 1], "Read(//project/**)");
+    }
+
+    #[test]
+    fn test_resume_session_args() {
+        let claude = ClaudeBackend::new(&[]);
+        let resumed = AgentRequest {
+            resume_session: Some("9a7c"),
+            ..request(None)
+        };
+        let claude_args = args(&claude.build_command(&resumed));
+        let flag = claude_args.iter().position(|a| a == "--resume").unwrap();
+        assert_eq!(claude_args[flag + 1], "9a7c");
+
+        // A command backend without resume_flag starts over
+        let mut config = BackendConfig {
+            command: "other-agent".to_string(),
+            args: Vec::new(),
+            model_flag: String::new(),
+            allowed_tools_flag: String::new(),
+            disallowed_tools_flag: String::new(),
+            resume_flag: String::new(),
+        };
+        let backend = CommandBackend::new("other", &config);
+        assert!(!backend.supports_resume());
+        assert!(args(&backend.build_command(&resumed).unwrap()).is_empty());
+
+        config.resume_flag = "--continue-session".to_string();
+        let backend = CommandBackend::new("other", &config);
+        assert!(backend.supports_resume());
+        assert_eq!(
+            args(&backend.build_command(&resumed).unwrap()),
+            vec!["--continue-session", "9a7c"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_unknown_backend() {
+        let config: Config = toml::from_str(&crate::config::default_config_toml("test")).unwrap();
+        let spec = ModelSpec {
+            backend: "missing".to_string(),
+            model: "x".to_string(),
+        };
+        let root = Path::new(".");
+        assert!(resolve(&spec, &config, root).is_err());
+        let spec = ModelSpec::parse("sonnet", &config);
+        assert_eq!(resolve(&spec, &config, root).unwrap().name(), "claude");
+    }
+}
@@ -0,0 +1,182 @@
+//! Project commands lisa runs itself: `setup` before building, `build` and
+//! `lint` after every build iteration.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::process::{self, run_check};
+use crate::terminal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// No command configured.
+    Skipped,
+}
+
+/// Build and lint outcome of one build iteration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterationChecks {
+    pub task: u32,
+    pub iteration: u32,
+    pub build: CheckStatus,
+    pub lint: CheckStatus,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CheckLog {
+    #[serde(default)]
+    pub iterations: Vec<IterationChecks>,
+}
+
+fn log_path(lisa_root: &Path, pass: u32) -> PathBuf {
+    lisa_root.join(format!("spiral/pass-{}/checks.toml", pass))
+}
+
+pub fn load_log(lisa_root: &Path, pass: u32) -> Result<CheckLog> {
+    let path = log_path(lisa_root, pass);
+    if !path.exists() {
+        return Ok(CheckLog::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn record(lisa_root: &Path, pass: u32, checks: IterationChecks) -> Result<()> {
+    let mut log = load_log(lisa_root, pass)?;
+    log.iterations.push(checks);
+    let path = log_path(lisa_root, pass);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, toml::to_string_pretty(&log)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Run `commands.setup` (dependency installation and the like). A failure is
+/// reported but does not stop the spiral: the build checks will surface it.
+pub fn run_setup(config: &Config) {
+    let cmd = &config.commands.setup;
+    if cmd.is_empty() {
+        return;
+    }
+    terminal::log_info(&format!("Running setup: {}", cmd));
+    match run_check(cmd, config) {
+        Ok(None) => terminal::log_success("Setup complete."),
+        Ok(Some(output)) => {
+            terminal::log_warn(&format!("Setup command `{}` failed:\n{}", cmd, output))
+        }
+        Err(e) => terminal::log_warn(&format!("Failed to run setup `{}`: {}", cmd, e)),
+    }
+}
+
+/// Run `commands.build` and `commands.lint` after a build iteration, record the
+/// outcome and return the diagnostics for the next Build context (`None` if
+/// both pass or are not configured).
+pub fn run_iteration_checks(
+    config: &Config,
+    lisa_root: &Path,
+    pass: u32,
+    task: u32,
+    iteration: u32,
+) -> Result<Option<String>> {
+    let mut diagnostics = Vec::new();
+    let mut check = |name: &str, cmd: &str| -> CheckStatus {
+        if cmd.is_empty() {
+            return CheckStatus::Skipped;
+        }
+        let (verdict, failure) = match process::run_shell(cmd, config) {
+            Ok(result) => match result.failure_output() {
+                None => {
+                    terminal::log_success(&format!("  {} OK", name));
+                    return CheckStatus::Pass;
+                }
+                Some(output) => (result.status.describe(), format!("```\n{}\n```", output)),
+            },
+            Err(e) => ("failed".to_string(), format!("Failed to run: {}", e)),
+        };
+        terminal::log_warn(&format!("  {} {} (`{}`)", name, verdict, cmd));
+        diagnostics.push(format!(
+            "### {} {} (`{}`)\n\n{}",
+            name, verdict, cmd, failure
+        ));
+        CheckStatus::Fail
+    };
+    let build = check("Build", &config.commands.build);
+    let lint = check("Lint", &config.commands.lint);
+
+    if build == CheckStatus::Skipped && lint == CheckStatus::Skipped {
+        return Ok(None);
+    }
+    record(
+        lisa_root,
+        pass,
+        IterationChecks {
+            task,
+            iteration,
+            build,
+            lint,
+        },
+    )?;
+
+    if diagnostics.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "\n\n## Build and Lint Status\n\n\
+         Lisa ran the project's build and lint commands after your previous iteration. \
+         Fix these problems before continuing:\n\n{}",
+        diagnostics.join("\n\n")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(build: &str, lint: &str) -> Config {
+        let mut config: Config = toml::from_str("[project]\nname = \"t\"\n").unwrap();
+        config.commands.build = build.to_string();
+        config.commands.lint = lint.to_string();
+        config
+    }
+
+    #[test]
+    fn test_iteration_checks_record_and_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let lisa_root = dir.path();
+
+        let failing = config("true", "echo 'unused import' && false");
+        let report = run_iteration_checks(&failing, lisa_root, 1, 2, 3)
+            .unwrap()
+            .unwrap();
+        assert!(report.contains("### Lint failed (exit 1) (`echo 'unused import' && false`)"));
+        assert!(report.contains("unused import"));
+        assert!(!report.contains("Build failed"));
+
+        let passing = config("true", "");
+        assert!(run_iteration_checks(&passing, lisa_root, 1, 2, 4)
+            .unwrap()
+            .is_none());
+
+        let log = load_log(lisa_root, 1).unwrap();
+        assert_eq!(log.iterations.len(), 2);
+        assert_eq!(log.iterations[0].lint, CheckStatus::Fail);
+        assert_eq!(log.iterations[0].build, CheckStatus::Pass);
+        assert_eq!(log.iterations[1].lint, CheckStatus::Skipped);
+    }
+
+    #[test]
+    fn test_nothing_configured_records_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(run_iteration_checks(&config("", ""), dir.path(), 1, 1, 1)
+            .unwrap()
+            .is_none());
+        assert!(!log_path(dir.path(), 1).exists());
+    }
+}
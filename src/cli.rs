@@ -1,4 +1,4 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(
@@ -62,6 +62,12 @@ pub enum Commands {
         /// Continue a completed spiral with a follow-up question
         #[arg(long)]
         follow_up: Option<String>,
+        /// Record every agent invocation under .lisa/recordings/
+        #[arg(long, conflicts_with = "replay")]
+        record: bool,
+        /// Replay agent invocations from a recordings directory instead of calling agents
+        #[arg(long, value_name = "DIR")]
+        replay: Option<String>,
     },
     /// Resume from saved state
     Resume {
@@ -71,6 +77,12 @@ pub enum Commands {
         /// Show full agent output (overrides collapse_output config)
         #[arg(long, short)]
         verbose: bool,
+        /// Record every agent invocation under .lisa/recordings/
+        #[arg(long, conflicts_with = "replay")]
+        record: bool,
+        /// Replay agent invocations from a recordings directory instead of calling agents
+        #[arg(long, value_name = "DIR")]
+        replay: Option<String>,
     },
     /// Print current spiral state and pass history
     Status,
@@ -78,6 +90,11 @@ pub enum Commands {
     Doctor,
     /// Copy compiled-in prompts to .lisa/prompts/ for customization
     EjectPrompts,
+    /// Inspect and edit the task plan (.lisa/methodology/plan.md)
+    Tasks {
+        #[command(subcommand)]
+        command: TasksCommand,
+    },
     /// Roll back to a previous pass boundary
     Rollback {
         /// Pass number to roll back to (e.g., 1 for end of pass 1)
@@ -87,3 +104,54 @@ pub enum Commands {
         force: bool,
     },
 }
+
+#[derive(Subcommand)]
+pub enum TasksCommand {
+    /// List tasks with their status, pass and dependencies
+    List {
+        /// Only tasks scheduled in this pass
+        #[arg(long)]
+        pass: Option<u32>,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show every field of one task
+    Show {
+        task: u32,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Set a task's status: TODO, IN_PROGRESS, DONE or BLOCKED
+    SetStatus { task: u32, status: String },
+    /// Mark a task BLOCKED
+    Block { task: u32 },
+    /// Move a BLOCKED task back to TODO
+    Unblock { task: u32 },
+    /// Make a task depend on another
+    AddDep { task: u32, dependency: u32 },
+    /// Remove one of a task's dependencies
+    RmDep { task: u32, dependency: u32 },
+    /// Show the task the build loop picks next
+    Next {
+        /// Pass to pick from (defaults to the current pass)
+        #[arg(long)]
+        pass: Option<u32>,
+        /// Print JSON (`null` when no task is ready)
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the dependency graph, clustered by pass, with the critical path in red
+    Graph {
+        #[arg(long, value_enum, default_value_t = GraphFormat::Mermaid)]
+        format: GraphFormat,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz, e.g. for `dot -Tsvg`
+    Dot,
+    Mermaid,
+}
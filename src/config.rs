@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +22,14 @@ pub struct Config {
     pub commands: CommandsConfig,
     #[serde(default)]
     pub agent: AgentConfig,
+    #[serde(default)]
+    pub bounds: BoundsConfig,
+    #[serde(default)]
+    pub write_scope: WriteScopeConfig,
+    /// Tool permissions per phase (`[permissions.<phase>]`), with `default` as fallback.
+    /// Phases without an entry run with unrestricted permissions.
+    #[serde(default)]
+    pub permissions: BTreeMap<String, PermissionsConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,15 +40,88 @@ pub struct ProjectConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelsConfig {
     #[serde(default = "default_opus")]
-    pub scope: String,
+    pub scope: ModelChain,
     #[serde(default = "default_opus")]
-    pub refine: String,
+    pub refine: ModelChain,
     #[serde(default = "default_opus")]
-    pub bounds: String,
+    pub bounds: ModelChain,
     #[serde(default = "default_sonnet")]
-    pub build: String,
+    pub build: ModelChain,
     #[serde(default = "default_opus")]
-    pub audit: String,
+    pub audit: ModelChain,
+}
+
+/// A `[models]` entry: one model, or an ordered list to fall back through when
+/// a model is unavailable or rate-limited (`build = ["sonnet", "haiku"]`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "OneOrMany", into = "OneOrMany")]
+pub struct ModelChain(Vec<String>);
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl TryFrom<OneOrMany> for ModelChain {
+    type Error = String;
+
+    fn try_from(value: OneOrMany) -> std::result::Result<Self, Self::Error> {
+        match value {
+            OneOrMany::One(model) => Ok(Self(vec![model])),
+            OneOrMany::Many(models) if models.is_empty() => {
+                Err("a [models] list needs at least one model".to_string())
+            }
+            OneOrMany::Many(models) => Ok(Self(models)),
+        }
+    }
+}
+
+impl From<ModelChain> for OneOrMany {
+    fn from(chain: ModelChain) -> Self {
+        match <[String; 1]>::try_from(chain.0) {
+            Ok([model]) => OneOrMany::One(model),
+            Err(models) => OneOrMany::Many(models),
+        }
+    }
+}
+
+impl From<&str> for ModelChain {
+    fn from(model: &str) -> Self {
+        Self(vec![model.to_string()])
+    }
+}
+
+impl ModelChain {
+    /// The configured model first, then its fallbacks.
+    pub fn models(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl ModelsConfig {
+    /// The chains a phase (as named in `[permissions]`) runs on; every chain
+    /// for `default`.
+    pub fn for_phase(&self, phase: &str) -> Vec<&ModelChain> {
+        match phase {
+            "default" => vec![
+                &self.scope,
+                &self.refine,
+                &self.bounds,
+                &self.build,
+                &self.audit,
+            ],
+            "init" | "scope" | "research" | "validation_design" | "planning" | "explore" => {
+                vec![&self.scope]
+            }
+            "refine" | "refine_methodology" | "refine_plan" => vec![&self.refine],
+            "bounds" => vec![&self.bounds],
+            "build" => vec![&self.build],
+            "audit" | "finalize" => vec![&self.audit],
+            _ => Vec::new(),
+        }
+    }
 }
 
 impl Default for ModelsConfig {
@@ -54,11 +136,11 @@ impl Default for ModelsConfig {
     }
 }
 
-fn default_opus() -> String {
-    "opus".to_string()
+fn default_opus() -> ModelChain {
+    ModelChain::from("opus")
 }
-fn default_sonnet() -> String {
-    "sonnet".to_string()
+fn default_sonnet() -> ModelChain {
+    ModelChain::from("sonnet")
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +153,12 @@ pub struct LimitsConfig {
     pub max_tasks_per_pass: u32,
     #[serde(default = "default_stall_threshold")]
     pub stall_threshold: u32,
+    /// Ready tasks built at once, each in its own worktree. 1 = one at a time.
+    #[serde(default = "default_max_parallel_tasks")]
+    pub max_parallel_tasks: u32,
+    /// Times a plan that fails validation goes back to the planning agent.
+    #[serde(default = "default_max_plan_revisions")]
+    pub max_plan_revisions: u32,
     #[serde(default)]
     pub budget_usd: f64,
     #[serde(default = "default_budget_warn_pct")]
@@ -79,6 +167,76 @@ pub struct LimitsConfig {
     pub idle_timeout_secs: u64,
     #[serde(default = "default_max_agent_retries")]
     pub max_agent_retries: u32,
+    /// First delay before retrying a transient failure; doubles with each retry.
+    #[serde(default = "default_retry_backoff_secs")]
+    pub retry_backoff_secs: u64,
+    #[serde(default = "default_retry_backoff_max_secs")]
+    pub retry_backoff_max_secs: u64,
+    /// On a usage limit with a known reset time, sleep until then and retry.
+    #[serde(default = "default_true")]
+    pub wait_for_usage_reset: bool,
+    /// Kill commands lisa runs itself (setup, build, lint, tests) after this long. 0 = no limit.
+    #[serde(default = "default_command_timeout_secs")]
+    pub command_timeout_secs: u64,
+    /// Output kept per stream of those commands; the middle of longer output is dropped.
+    #[serde(default = "default_command_output_kb")]
+    pub command_output_kb: usize,
+    /// Ceilings per agent invocation (`[limits.phase.<phase>]`), with `default` as fallback.
+    #[serde(default)]
+    pub phase: BTreeMap<String, PhaseLimits>,
+    /// Cheaper models per phase (`[limits.downgrade]`), used instead of `[models]`
+    /// once spending crosses `budget_warn_pct`. Never applies to bounds or audit.
+    #[serde(default)]
+    pub downgrade: BTreeMap<String, ModelChain>,
+    /// Prices by a fragment of the model name (`[limits.prices]`); the longest
+    /// matching fragment wins. Entries add to or override the built-in ones.
+    #[serde(default = "default_prices", deserialize_with = "merge_prices")]
+    pub prices: BTreeMap<String, ModelPrice>,
+}
+
+/// List price of a model in USD per million tokens. Cache writes cost 1.25x
+/// input, cache reads 0.1x.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+}
+
+fn default_prices() -> BTreeMap<String, ModelPrice> {
+    [
+        ("opus", 5.0, 25.0),
+        // Opus before 4.5 cost three times as much
+        ("3-opus", 15.0, 75.0),
+        ("opus-4-0", 15.0, 75.0),
+        ("opus-4-1", 15.0, 75.0),
+        ("opus-4-2025", 15.0, 75.0),
+        ("sonnet", 3.0, 15.0),
+        ("haiku", 1.0, 5.0),
+    ]
+    .into_iter()
+    .map(|(model, input, output)| (model.to_string(), ModelPrice { input, output }))
+    .collect()
+}
+
+fn merge_prices<'de, D>(
+    deserializer: D,
+) -> std::result::Result<BTreeMap<String, ModelPrice>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut prices = default_prices();
+    prices.extend(BTreeMap::<String, ModelPrice>::deserialize(deserializer)?);
+    Ok(prices)
+}
+
+/// Hard ceilings on one agent invocation, enforced while it runs. 0 = none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PhaseLimits {
+    #[serde(default)]
+    pub max_wall_secs: u64,
+    /// Estimated from the token usage the agent streams.
+    #[serde(default)]
+    pub max_cost_usd: f64,
 }
 
 impl Default for LimitsConfig {
@@ -88,14 +246,36 @@ impl Default for LimitsConfig {
             max_ralph_iterations: default_max_ralph_iterations(),
             max_tasks_per_pass: default_max_tasks_per_pass(),
             stall_threshold: default_stall_threshold(),
+            max_parallel_tasks: default_max_parallel_tasks(),
+            max_plan_revisions: default_max_plan_revisions(),
             budget_usd: 0.0,
             budget_warn_pct: default_budget_warn_pct(),
             idle_timeout_secs: default_idle_timeout_secs(),
             max_agent_retries: default_max_agent_retries(),
+            retry_backoff_secs: default_retry_backoff_secs(),
+            retry_backoff_max_secs: default_retry_backoff_max_secs(),
+            wait_for_usage_reset: true,
+            command_timeout_secs: default_command_timeout_secs(),
+            command_output_kb: default_command_output_kb(),
+            phase: BTreeMap::new(),
+            downgrade: BTreeMap::new(),
+            prices: default_prices(),
         }
     }
 }
 
+fn default_retry_backoff_secs() -> u64 {
+    30
+}
+fn default_retry_backoff_max_secs() -> u64 {
+    900
+}
+fn default_command_timeout_secs() -> u64 {
+    600
+}
+fn default_command_output_kb() -> usize {
+    1024
+}
 fn default_max_spiral_passes() -> u32 {
     5
 }
@@ -108,6 +288,12 @@ fn default_max_tasks_per_pass() -> u32 {
 fn default_stall_threshold() -> u32 {
     2
 }
+fn default_max_parallel_tasks() -> u32 {
+    1
+}
+fn default_max_plan_revisions() -> u32 {
+    2
+}
 fn default_budget_warn_pct() -> u32 {
     80
 }
@@ -209,12 +395,209 @@ fn default_tests_integration() -> String {
     String::new()
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BoundsConfig {
+    /// What to do when the Bounds agent touches implementation code (`paths.source`).
+    #[serde(default)]
+    pub on_source_access: SourceAccessPolicy,
+    /// Run the Bounds agent in a temporary worktree without `paths.source` or the
+    /// software and integration tests. Only the file tools are denied the project;
+    /// Bash is left to the access audit.
+    #[serde(default)]
+    pub sandbox: bool,
+    /// What to do when the Build agent modifies the bounding tests.
+    #[serde(default)]
+    pub on_tamper: TamperPolicy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceAccessPolicy {
+    /// Record the violation and continue.
+    #[default]
+    Warn,
+    /// Discard the bounds tests and re-run the phase once; fail if it happens again.
+    Rerun,
+    /// Stop the spiral.
+    Fail,
+}
+
+impl std::fmt::Display for SourceAccessPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceAccessPolicy::Warn => write!(f, "warn"),
+            SourceAccessPolicy::Rerun => write!(f, "rerun"),
+            SourceAccessPolicy::Fail => write!(f, "fail"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TamperPolicy {
+    /// Restore the bounding tests and tell the next build iteration what was undone.
+    #[default]
+    Restore,
+    /// Stop at a review gate that shows the diff.
+    Gate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteScopeConfig {
+    /// Check each phase's file changes against the paths it may write.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Extra paths any phase may write, e.g. build output that is not gitignored.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Revert out-of-scope writes right after the phase instead of flagging them.
+    #[serde(default)]
+    pub auto_revert: bool,
+}
+
+impl Default for WriteScopeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            allow: Vec::new(),
+            auto_revert: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AgentConfig {
     /// Extra CLI arguments passed to every `claude` invocation.
     /// Each element is one argument, e.g. ["--max-turns", "50"].
     #[serde(default)]
     pub extra_args: Vec<String>,
+    /// Additional agent CLIs, selectable per phase with a `name:model` spec in [models].
+    #[serde(default)]
+    pub backends: BTreeMap<String, BackendConfig>,
+    /// Save every invocation's input, output stream and file effects under `.lisa/recordings/`.
+    #[serde(default)]
+    pub record: bool,
+    /// Replay invocations from this recordings directory instead of calling any agent.
+    /// Relative to the project root. Empty disables replay.
+    #[serde(default)]
+    pub replay: String,
+}
+
+/// Phase names accepted under `[permissions]`, plus `default`.
+pub const PERMISSION_PHASES: &[&str] = &[
+    "default",
+    "init",
+    "research",
+    "validation_design",
+    "planning",
+    "refine",
+    "refine_methodology",
+    "refine_plan",
+    "bounds",
+    "build",
+    "audit",
+    "finalize",
+    "explore",
+];
+
+/// What an agent may do in one phase. Anything not listed is denied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PermissionsConfig {
+    /// Tools allowed without restriction, e.g. `"Read"`, `"Grep"`, `"WebSearch"`.
+    #[serde(default)]
+    pub allowed_tools: Vec<String>,
+    /// Tools always denied, even if allowed elsewhere.
+    #[serde(default)]
+    pub disallowed_tools: Vec<String>,
+    /// Shell command patterns the agent may run, e.g. `"pytest:*"`.
+    #[serde(default)]
+    pub bash: Vec<String>,
+    /// Path globs the agent may create or edit, e.g. `"src/**"`.
+    #[serde(default)]
+    pub writable: Vec<String>,
+}
+
+impl PermissionsConfig {
+    /// Allow rules in Claude's permission-rule syntax (`Tool` or `Tool(specifier)`).
+    pub fn allow_rules(&self) -> Vec<String> {
+        let mut rules = self.allowed_tools.clone();
+        rules.extend(self.bash.iter().map(|p| format!("Bash({})", p)));
+        rules.extend(self.writable.iter().map(|g| format!("Edit({})", g)));
+        rules
+    }
+
+    /// Only deny rules: everything else stays allowed.
+    pub fn deny_only(&self) -> bool {
+        self.allow_rules().is_empty() && !self.disallowed_tools.is_empty()
+    }
+}
+
+/// A user-defined agent CLI. It must read the prompt on stdin and emit
+/// Claude-compatible stream-json (NDJSON) on stdout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendConfig {
+    /// Executable to spawn.
+    pub command: String,
+    /// Arguments passed on every invocation, before the model flag.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Flag used to select the model. Empty means the model is not passed.
+    #[serde(default = "default_model_flag")]
+    pub model_flag: String,
+    /// Flags taking the allow and deny rules of `[permissions]`. A backend without
+    /// them cannot run phases that have permissions, or deny rules, configured.
+    #[serde(default)]
+    pub allowed_tools_flag: String,
+    #[serde(default)]
+    pub disallowed_tools_flag: String,
+    /// Flag taking a session id to continue, e.g. `--resume`. Empty means
+    /// interrupted invocations always start over.
+    #[serde(default)]
+    pub resume_flag: String,
+}
+
+fn default_model_flag() -> String {
+    "--model".to_string()
+}
+
+/// Name of the built-in Claude CLI backend.
+pub const DEFAULT_BACKEND: &str = "claude";
+
+/// A resolved `[models]` entry: which backend to run and which model to ask it for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSpec {
+    pub backend: String,
+    pub model: String,
+}
+
+impl ModelSpec {
+    /// Parse a `[models]` value. `"opus"` runs on the Claude CLI; `"mycli:gpt-5"` runs
+    /// model `gpt-5` on the backend `mycli` declared under `[agent.backends]`.
+    /// A prefix that is not a known backend is treated as part of the model name.
+    pub fn parse(value: &str, config: &Config) -> Self {
+        if let Some((prefix, model)) = value.split_once(':') {
+            if prefix == DEFAULT_BACKEND || config.agent.backends.contains_key(prefix) {
+                return Self {
+                    backend: prefix.to_string(),
+                    model: model.to_string(),
+                };
+            }
+        }
+        Self {
+            backend: DEFAULT_BACKEND.to_string(),
+            model: value.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for ModelSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.backend == DEFAULT_BACKEND {
+            write!(f, "{}", self.model)
+        } else {
+            write!(f, "{}:{}", self.backend, self.model)
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -233,6 +616,49 @@ pub struct CommandsConfig {
     pub test_integration: String,
     #[serde(default)]
     pub lint: String,
+    /// How to read real test results per category, instead of the agents' summaries.
+    #[serde(default)]
+    pub results: TestResultsConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestResultsConfig {
+    #[serde(default)]
+    pub bounds: Option<ResultSource>,
+    #[serde(default)]
+    pub software: Option<ResultSource>,
+    #[serde(default)]
+    pub integration: Option<ResultSource>,
+}
+
+/// Where a test category's results come from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultSource {
+    pub format: ResultFormat,
+    /// Report file written by the test command (JUnit XML, pytest JSON), relative
+    /// to the project root. libtest and CTest results are read from stdout.
+    #[serde(default)]
+    pub report: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResultFormat {
+    /// JUnit XML (`pytest --junitxml`, `ctest --output-junit`, most test runners).
+    Junit,
+    /// pytest-json-report (`pytest --json-report --json-report-file=...`).
+    PytestJson,
+    /// libtest JSON on stdout (`cargo test -- -Z unstable-options --format json`).
+    Libtest,
+    /// CTest's console summary on stdout.
+    Ctest,
+}
+
+impl ResultFormat {
+    /// Formats read from a report file rather than the command's stdout.
+    pub fn needs_report(&self) -> bool {
+        matches!(self, ResultFormat::Junit | ResultFormat::PytestJson)
+    }
 }
 
 impl Config {
@@ -242,9 +668,145 @@ impl Config {
             .with_context(|| format!("Failed to read {}", config_path.display()))?;
         let config: Config =
             toml::from_str(&content).with_context(|| "Failed to parse lisa.toml")?;
+        config.validate_permissions()?;
+        config.validate_phase_limits()?;
+        config.validate_downgrade()?;
+        config.validate_prices()?;
+        config.validate_results()?;
         Ok(config)
     }
 
+    /// Permissions for a phase (as named in `[permissions]`), falling back to `default`.
+    pub fn permissions_for(&self, phase: &str) -> Option<&PermissionsConfig> {
+        self.permissions
+            .get(phase)
+            .or_else(|| self.permissions.get("default"))
+    }
+
+    /// Report-based result formats are useless without the report path.
+    pub fn validate_results(&self) -> Result<()> {
+        let results = &self.commands.results;
+        for (category, source) in [
+            ("bounds", &results.bounds),
+            ("software", &results.software),
+            ("integration", &results.integration),
+        ] {
+            if let Some(source) = source {
+                if source.format.needs_report() && source.report.is_empty() {
+                    anyhow::bail!(
+                        "[commands.results] {} needs `report`: the file the test command writes",
+                        category
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject misspelled phase names, which would otherwise leave a phase unrestricted.
+    pub fn validate_permissions(&self) -> Result<()> {
+        for phase in self.permissions.keys() {
+            if !PERMISSION_PHASES.contains(&phase.as_str()) {
+                anyhow::bail!(
+                    "Unknown phase [permissions.{}] in lisa.toml. Valid phases: {}",
+                    phase,
+                    PERMISSION_PHASES.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Invocation ceilings for a phase (as named in `[permissions]`), falling back to `default`.
+    pub fn phase_limits(&self, phase: &str) -> PhaseLimits {
+        let phases = &self.limits.phase;
+        phases
+            .get(phase)
+            .or_else(|| phases.get("default"))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Price of `model` from `[limits.prices]`, `None` if no fragment matches.
+    pub fn price(&self, model: &str) -> Option<ModelPrice> {
+        let model = model.to_lowercase();
+        self.limits
+            .prices
+            .iter()
+            .filter(|(fragment, _)| model.contains(&fragment.to_lowercase()))
+            .max_by_key(|(fragment, _)| fragment.len())
+            .map(|(_, price)| *price)
+    }
+
+    /// A cost ceiling cannot be enforced on a model without a price.
+    pub fn validate_prices(&self) -> Result<()> {
+        for (phase, limits) in &self.limits.phase {
+            if limits.max_cost_usd <= 0.0 {
+                continue;
+            }
+            let chains = self
+                .models
+                .for_phase(phase)
+                .into_iter()
+                .chain(self.limits.downgrade.get(phase))
+                .chain(if phase == "default" {
+                    self.limits.downgrade.values().collect()
+                } else {
+                    Vec::new()
+                });
+            for chain in chains {
+                for model in chain.models() {
+                    let spec = ModelSpec::parse(model, self);
+                    if self.price(&spec.model).is_none() {
+                        anyhow::bail!(
+                            "[limits.phase.{}] sets max_cost_usd, but '{}' has no price: \
+                             add it to [limits.prices] in lisa.toml",
+                            phase,
+                            model
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_phase_limits(&self) -> Result<()> {
+        for phase in self.limits.phase.keys() {
+            if !PERMISSION_PHASES.contains(&phase.as_str()) {
+                anyhow::bail!(
+                    "Unknown phase [limits.phase.{}] in lisa.toml. Valid phases: {}",
+                    phase,
+                    PERMISSION_PHASES.join(", ")
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate_downgrade(&self) -> Result<()> {
+        for phase in self.limits.downgrade.keys() {
+            match phase.as_str() {
+                "bounds" | "audit" => anyhow::bail!(
+                    "[limits.downgrade] cannot list '{}': bounds and audit always run on their configured models",
+                    phase
+                ),
+                p if p != "default" && PERMISSION_PHASES.contains(&p) => {}
+                _ => anyhow::bail!(
+                    "Unknown phase '{}' in [limits.downgrade] in lisa.toml. Valid phases: {}",
+                    phase,
+                    PERMISSION_PHASES
+                        .iter()
+                        .filter(|p| !matches!(**p, "default" | "bounds" | "audit"))
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            }
+        }
+        Ok(())
+    }
+
     pub fn lisa_root(&self, project_root: &Path) -> PathBuf {
         project_root.join(&self.paths.lisa_root)
     }
@@ -272,6 +834,7 @@ impl Config {
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
 
@@ -280,12 +843,14 @@ mod tests {
         let toml_str = default_config_toml("test-project");
         let config: Config = toml::from_str(&toml_str).unwrap();
         assert_eq!(config.project.name, "test-project");
-        assert_eq!(config.models.scope, "opus");
-        assert_eq!(config.models.build, "sonnet");
+        assert_eq!(config.models.scope.models()[0], "opus");
+        assert_eq!(config.models.build.models()[0], "sonnet");
         assert_eq!(config.limits.max_spiral_passes, 5);
         assert_eq!(config.limits.max_ralph_iterations, 15);
         assert_eq!(config.limits.max_tasks_per_pass, 5);
         assert_eq!(config.limits.stall_threshold, 2);
+        assert_eq!(config.limits.max_parallel_tasks, 1);
+        assert_eq!(config.limits.max_plan_revisions, 2);
         assert!(config.review.pause);
         assert!(config.git.auto_commit);
         assert!(!config.git.auto_push);
@@ -295,6 +860,9 @@ mod tests {
         assert_eq!(config.paths.tests_bounds, "");
         assert_eq!(config.limits.idle_timeout_secs, 300);
         assert_eq!(config.limits.max_agent_retries, 2);
+        assert_eq!(config.limits.retry_backoff_secs, 30);
+        assert_eq!(config.limits.retry_backoff_max_secs, 900);
+        assert!(config.limits.wait_for_usage_reset);
         assert!(config.agent.extra_args.is_empty());
     }
 
@@ -314,6 +882,253 @@ extra_args = ["--max-turns", "50", "--verbose"]
         );
     }
 
+    #[test]
+    fn test_parse_custom_backend() {
+        let toml_str = r#"
+[project]
+name = "with-backend"
+
+[models]
+bounds = "other:gpt-5"
+
+[agent.backends.other]
+command = "other-agent"
+args = ["exec", "--json"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let backend = &config.agent.backends["other"];
+        assert_eq!(backend.command, "other-agent");
+        assert_eq!(backend.args, vec!["exec", "--json"]);
+        assert_eq!(backend.model_flag, "--model");
+
+        let spec = ModelSpec::parse(&config.models.bounds.models()[0], &config);
+        assert_eq!(spec.backend, "other");
+        assert_eq!(spec.model, "gpt-5");
+        assert_eq!(spec.to_string(), "other:gpt-5");
+    }
+
+    #[test]
+    fn test_model_spec_defaults_to_claude() {
+        let config: Config = toml::from_str(&default_config_toml("test")).unwrap();
+        let spec = ModelSpec::parse("opus", &config);
+        assert_eq!(spec.backend, DEFAULT_BACKEND);
+        assert_eq!(spec.model, "opus");
+        assert_eq!(spec.to_string(), "opus");
+
+        // Unknown prefixes are part of the model name, not a backend
+        let spec = ModelSpec::parse("llama3:8b", &config);
+        assert_eq!(spec.backend, DEFAULT_BACKEND);
+        assert_eq!(spec.model, "llama3:8b");
+
+        let spec = ModelSpec::parse("claude:sonnet", &config);
+        assert_eq!(spec.backend, DEFAULT_BACKEND);
+        assert_eq!(spec.model, "sonnet");
+    }
+
+    #[test]
+    fn test_parse_bounds_policy() {
+        let config: Config = toml::from_str(&default_config_toml("test")).unwrap();
+        assert_eq!(config.bounds.on_source_access, SourceAccessPolicy::Warn);
+        assert!(!config.bounds.sandbox);
+        assert_eq!(config.bounds.on_tamper, TamperPolicy::Restore);
+
+        let toml_str = r#"
+[project]
+name = "strict"
+
+[bounds]
+on_source_access = "rerun"
+sandbox = true
+on_tamper = "gate"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.bounds.on_source_access, SourceAccessPolicy::Rerun);
+        assert!(config.bounds.sandbox);
+        assert_eq!(config.bounds.on_tamper, TamperPolicy::Gate);
+
+        let bad = "[project]\nname = \"x\"\n[bounds]\non_source_access = \"ignore\"\n";
+        assert!(toml::from_str::<Config>(bad).is_err());
+    }
+
+    #[test]
+    fn test_parse_result_sources() {
+        let config: Config = toml::from_str(&default_config_toml("test")).unwrap();
+        assert!(config.commands.results.bounds.is_none());
+
+        let toml_str = r#"
+[project]
+name = "results"
+
+[commands.results]
+bounds = { format = "junit", report = "reports/bounds.xml" }
+software = { format = "pytest-json", report = "reports/software.json" }
+integration = { format = "ctest" }
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let bounds = config.commands.results.bounds.as_ref().unwrap();
+        assert_eq!(bounds.format, ResultFormat::Junit);
+        assert_eq!(bounds.report, "reports/bounds.xml");
+        assert_eq!(
+            config.commands.results.software.as_ref().unwrap().format,
+            ResultFormat::PytestJson
+        );
+        assert!(config.validate_results().is_ok());
+
+        let missing =
+            "[project]\nname = \"x\"\n[commands.results]\nbounds = { format = \"junit\" }\n";
+        let config: Config = toml::from_str(missing).unwrap();
+        assert!(config.validate_results().is_err());
+    }
+
+    #[test]
+    fn test_parse_permissions() {
+        let config: Config = toml::from_str(&default_config_toml("test")).unwrap();
+        assert!(config.permissions.is_empty());
+        assert!(config.permissions_for("build").is_none());
+
+        let toml_str = r#"
+[project]
+name = "locked"
+
+[permissions.default]
+allowed_tools = ["Read", "Glob", "Grep"]
+
+[permissions.build]
+allowed_tools = ["Read"]
+bash = ["pytest:*"]
+writable = ["src/**"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        config.validate_permissions().unwrap();
+        assert_eq!(
+            config.permissions_for("build").unwrap().allow_rules(),
+            vec!["Read", "Bash(pytest:*)", "Edit(src/**)"]
+        );
+        // Phases without their own section fall back to default
+        assert_eq!(
+            config.permissions_for("audit").unwrap().allowed_tools,
+            vec!["Read", "Glob", "Grep"]
+        );
+
+        let typo = "[project]\nname = \"x\"\n[permissions.biuld]\nbash = [\"make\"]\n";
+        let config: Config = toml::from_str(typo).unwrap();
+        assert!(config.validate_permissions().is_err());
+    }
+
+    #[test]
+    fn test_parse_phase_limits() {
+        let config: Config = toml::from_str(&default_config_toml("test")).unwrap();
+        assert_eq!(config.phase_limits("build"), PhaseLimits::default());
+
+        let toml_str = r#"
+[project]
+name = "capped"
+
+[limits.phase.default]
+max_wall_secs = 1800
+
+[limits.phase.build]
+max_wall_secs = 3600
+max_cost_usd = 5.0
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        config.validate_phase_limits().unwrap();
+        assert_eq!(config.phase_limits("build").max_wall_secs, 3600);
+        assert_eq!(config.phase_limits("build").max_cost_usd, 5.0);
+        assert_eq!(config.phase_limits("audit").max_wall_secs, 1800);
+        assert_eq!(config.phase_limits("audit").max_cost_usd, 0.0);
+
+        let typo = "[project]\nname = \"x\"\n[limits.phase.biuld]\nmax_wall_secs = 1\n";
+        let config: Config = toml::from_str(typo).unwrap();
+        assert!(config.validate_phase_limits().is_err());
+    }
+
+    #[test]
+    fn test_prices() {
+        let config: Config = toml::from_str(&default_config_toml("test")).unwrap();
+        assert_eq!(config.limits.prices, default_prices());
+        assert_eq!(config.price("claude-sonnet-4-5").unwrap().input, 3.0);
+        assert_eq!(
+            config.price("claude-opus-4-1-20250805").unwrap().input,
+            15.0
+        );
+        assert_eq!(config.price("opus").unwrap().input, 5.0);
+        assert!(config.price("llama3:8b").is_none());
+
+        let unpriced = r#"
+[project]
+name = "x"
+
+[models]
+build = ["sonnet", "other:llama3"]
+
+[agent.backends.other]
+command = "other-agent"
+
+[limits.phase.build]
+max_cost_usd = 5.0
+"#;
+        let mut config: Config = toml::from_str(unpriced).unwrap();
+        let err = config.validate_prices().unwrap_err().to_string();
+        assert!(err.contains("'other:llama3' has no price"), "{}", err);
+        config.limits.prices.insert(
+            "llama3".to_string(),
+            ModelPrice {
+                input: 0.5,
+                output: 1.0,
+            },
+        );
+        config.validate_prices().unwrap();
+
+        let one_entry = r#"
+[project]
+name = "x"
+
+[limits.prices]
+sonnet = { input = 2.0, output = 10.0 }
+llama3 = { input = 0.5, output = 1.0 }
+"#;
+        let config: Config = toml::from_str(one_entry).unwrap();
+        assert_eq!(config.price("claude-sonnet-4-5").unwrap().input, 2.0);
+        assert_eq!(config.price("llama3:8b").unwrap().input, 0.5);
+        // The built-in prices the file does not mention are kept
+        assert_eq!(config.price("claude-opus-4-6").unwrap().input, 5.0);
+        assert_eq!(config.price("claude-haiku-4-5").unwrap().input, 1.0);
+    }
+
+    #[test]
+    fn test_downgrade_phases() {
+        let toml_str = r#"
+[project]
+name = "x"
+
+[limits.downgrade]
+build = "haiku"
+refine_plan = ["sonnet", "haiku"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        config.validate_downgrade().unwrap();
+        assert_eq!(config.limits.downgrade["build"].models(), ["haiku"]);
+        assert_eq!(
+            config.limits.downgrade["refine_plan"].models(),
+            ["sonnet", "haiku"]
+        );
+
+        for phase in ["audit", "bounds", "biuld"] {
+            let toml_str = format!(
+                "[project]\nname = \"x\"\n[limits.downgrade]\n{} = \"haiku\"\n",
+                phase
+            );
+            let config: Config = toml::from_str(&toml_str).unwrap();
+            assert!(config.validate_downgrade().is_err(), "{}", phase);
+        }
+        let toml_str = "[project]\nname = \"x\"\n[limits.downgrade]\nbiuld = \"haiku\"\n";
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let err = config.validate_downgrade().unwrap_err().to_string();
+        assert!(err.contains("Valid phases: init, research,"), "{}", err);
+        assert!(!err.contains("bounds") && !err.contains("audit"), "{}", err);
+    }
+
     #[test]
     fn test_parse_minimal_config() {
         let toml_str = r#"
@@ -323,12 +1138,39 @@ name = "minimal"
         let config: Config = toml::from_str(toml_str).unwrap();
         assert_eq!(config.project.name, "minimal");
         // All defaults should apply
-        assert_eq!(config.models.scope, "opus");
+        assert_eq!(config.models.scope.models()[0], "opus");
         assert_eq!(config.limits.max_spiral_passes, 5);
         assert!(config.review.pause);
         assert!(config.agent.extra_args.is_empty());
     }
 
+    #[test]
+    fn test_model_chains() {
+        let toml_str = r#"
+[project]
+name = "chains"
+
+[models]
+build = ["sonnet", "haiku"]
+audit = "opus"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.models.build.models(), ["sonnet", "haiku"]);
+        assert_eq!(config.models.audit.models(), ["opus"]);
+
+        // A single model is written back as a plain string
+        let written = toml::to_string(&config.models).unwrap();
+        assert!(written.contains("audit = \"opus\""), "{}", written);
+        assert!(
+            written.contains("build = [\"sonnet\", \"haiku\"]"),
+            "{}",
+            written
+        );
+
+        let empty = "[project]\nname = \"x\"\n[models]\nbuild = []\n";
+        assert!(toml::from_str::<Config>(empty).is_err());
+    }
+
     #[test]
     fn test_source_dirs_display() {
         let toml_str = default_config_toml("test");
@@ -371,16 +1213,53 @@ refine = "opus"
 bounds = "opus"
 build = "sonnet"
 audit = "opus"
+# A list falls back to the next model when one is unavailable or rate-limited:
+# build = ["sonnet", "haiku"]
 
 [limits]
 max_spiral_passes = 5
 max_ralph_iterations = 15
 max_tasks_per_pass = 5
 stall_threshold = 2
+# max_parallel_tasks = 1 # build up to N ready tasks at once, each in its own git worktree
+max_plan_revisions = 2   # send a plan that fails validation back to the planning agent up to N times
 # budget_usd = 0.0       # 0 = unlimited
 # budget_warn_pct = 80   # warn at this % of budget
-idle_timeout_secs = 300  # kill agent after 5 min with no output
-max_agent_retries = 2    # auto-retry on idle timeout before surfacing to human
+idle_timeout_secs = 300  # stop agent after 5 min with no output and no CPU or I/O in its processes
+max_agent_retries = 2    # auto-retry on idle timeouts and transient API errors before surfacing to human
+retry_backoff_secs = 30  # first retry delay, doubling each retry
+retry_backoff_max_secs = 900
+wait_for_usage_reset = true  # on a usage limit, sleep until the reported reset time and retry
+command_timeout_secs = 600  # kill setup/build/lint/test commands after 10 min (0 = no limit)
+command_output_kb = 1024    # output kept per stream of those commands
+
+# Hard ceilings per agent invocation, enforced while the agent runs (0 = none).
+# Cost is estimated from the token usage the agent streams, at [limits.prices].
+# On a breach the agent is stopped and a gate offers a retry with a narrower
+# scope, or a stop.
+# [limits.phase.default] applies to phases without their own section.
+# [limits.phase.build]
+# max_wall_secs = 3600
+# max_cost_usd = 5.0
+
+# Past budget_warn_pct of the budget, run these phases on cheaper models
+# instead of stopping. Bounds and audit always keep their configured models.
+# [limits.downgrade]
+# build = "haiku"
+# refine_plan = "sonnet"
+
+# List prices in USD per million tokens, by a fragment of the model name (the
+# longest match wins). They estimate the cost of a running agent, which
+# max_cost_usd needs. Entries here override or add to the built-in ones: update
+# them when prices change, and add any other model that has a cost ceiling.
+[limits.prices]
+opus = {{ input = 5.0, output = 25.0 }}
+"3-opus" = {{ input = 15.0, output = 75.0 }}
+"opus-4-0" = {{ input = 15.0, output = 75.0 }}
+"opus-4-1" = {{ input = 15.0, output = 75.0 }}
+"opus-4-2025" = {{ input = 15.0, output = 75.0 }}
+sonnet = {{ input = 3.0, output = 15.0 }}
+haiku = {{ input = 1.0, output = 5.0 }}
 
 [review]
 # Human review gates. When false, loop runs fully autonomously.
@@ -413,8 +1292,67 @@ tests_integration = ""
 # Each element becomes one argument, e.g. ["--max-turns", "50"]
 extra_args = []
 
+# Other agent CLIs can be declared here and selected per phase in [models]
+# with a "<backend>:<model>" value, e.g. bounds = "other:some-model".
+# The command must read the prompt on stdin and emit Claude-compatible stream-json.
+# [agent.backends.other]
+# command = "other-agent"
+# args = ["--output-format", "stream-json"]
+# model_flag = "--model"
+# allowed_tools_flag = ""      # needed to enforce [permissions]
+# disallowed_tools_flag = ""   # needed for disallowed_tools
+# resume_flag = ""             # continues an interrupted session, e.g. "--resume"
+
+# Record every agent invocation (prompt, output stream, file changes) under .lisa/recordings/
+record = false
+# Replay recorded invocations from this directory instead of calling agents (for tests)
+replay = ""
+
+[bounds]
+# The Bounds agent must never see implementation code. Lisa audits its file
+# accesses against paths.source. On a violation:
+#   "warn"  — record it and continue (independence is reported as NOT verified)
+#   "rerun" — discard its tests and re-run the phase once, then fail
+#   "fail"  — stop the spiral
+on_source_access = "warn"
+# Run the Bounds agent in a temporary git worktree without paths.source or the
+# software and integration tests. Only paths.tests_bounds is copied back.
+# The file tools are denied the project by absolute path; Bash is not, so
+# shell commands into it are only caught by the audit above.
+sandbox = false
+# The Build agent must not change the bounding tests. Lisa compares them after
+# every build iteration. On a modification:
+#   "restore" — put the originals back and tell the next iteration what was undone
+#   "gate"    — stop at a review gate that shows the diff
+on_tamper = "restore"
+
+[write_scope]
+# Each phase may only write to its own paths (e.g. Build never touches bounding
+# tests). Out-of-scope writes are flagged at the next review gate, where they
+# can be reverted.
+enabled = true
+# Extra paths any phase may write (e.g. build output that is not gitignored)
+allow = []
+# Revert out-of-scope writes immediately instead of flagging them
+auto_revert = false
+
+# Tool permissions per phase. Without a section, agents run with unrestricted
+# permissions (--dangerously-skip-permissions). With one, anything not listed is
+# denied; a section with only disallowed_tools denies just those.
+# Phases: init, research, validation_design, planning, refine,
+# refine_methodology, refine_plan, bounds, build, audit, finalize, explore;
+# [permissions.default] applies to phases without their own section.
+# [permissions.build]
+# allowed_tools = ["Read", "Glob", "Grep"]
+# disallowed_tools = ["WebFetch"]
+# bash = ["pytest:*", "python:*"]
+# writable = ["src/**", "tests/**", ".lisa/**"]
+
 [commands]
-# These get populated by the scope agent, but can be pre-filled
+# These get populated by the scope agent, but can be pre-filled.
+# Lisa runs setup once after scope and on resume, build and lint after every
+# build iteration (failures go to the next Build iteration), and the test
+# commands to verify tasks the Build agent marks DONE.
 setup = ""
 build = ""
 test_all = ""
@@ -422,6 +1360,14 @@ test_bounds = ""
 test_software = ""
 test_integration = ""
 lint = ""
+
+# Read real test results instead of the agents' summaries. Formats: "junit",
+# "pytest-json" (both need the report file the command writes), "libtest", "ctest"
+# (parsed from stdout, which must fit in limits.command_output_kb). Lisa runs each
+# category after Build and stores the counts in spiral/pass-N/test-results.toml.
+# [commands.results]
+# bounds = {{ format = "junit", report = "reports/bounds.xml" }}
+# software = {{ format = "libtest" }}
 "#
     )
 }
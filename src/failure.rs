@@ -0,0 +1,482 @@
+//! Why an agent invocation failed, and whether and when to try again.
+
+use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveTime, TimeZone};
+use regex::Regex;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use crate::agent::AgentError;
+use crate::config::Config;
+use crate::interrupt;
+use crate::terminal;
+
+/// Rate-limit waits per invocation before giving up, so a limit that never
+/// lifts cannot stall the spiral forever.
+const MAX_RESET_WAITS: u32 = 3;
+/// Added to a reported reset time, in case the clocks disagree.
+const RESET_MARGIN: Duration = Duration::from_secs(60);
+/// Reset times further away than this are not waited for.
+const MAX_RESET_WAIT: Duration = Duration::from_secs(24 * 3600);
+
+/// Each class has its own retry policy: transient errors back off
+/// exponentially, usage limits wait for the reported reset, authentication and
+/// other fatal errors surface at once.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureClass {
+    /// Overloaded or failing API, network trouble, request timeouts.
+    Transient,
+    /// Rate or usage limit; `reset` when the message says when it lifts.
+    RateLimited { reset: Option<DateTime<Local>> },
+    /// Missing, invalid or expired credentials.
+    Auth,
+    /// The model does not exist or is not available to this account.
+    Unavailable,
+    /// The agent ran out of turns (`--max-turns`).
+    MaxTurns,
+    /// Anything else.
+    Fatal,
+}
+
+impl std::fmt::Display for FailureClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FailureClass::Transient => write!(f, "transient"),
+            FailureClass::RateLimited { reset: Some(at) } => {
+                write!(f, "rate-limited until {}", at.format("%Y-%m-%d %H:%M"))
+            }
+            FailureClass::RateLimited { reset: None } => write!(f, "rate-limited"),
+            FailureClass::Auth => write!(f, "auth"),
+            FailureClass::Unavailable => write!(f, "model unavailable"),
+            FailureClass::MaxTurns => write!(f, "max-turns"),
+            FailureClass::Fatal => write!(f, "fatal"),
+        }
+    }
+}
+
+/// An `API Error: ...` line the CLI writes as the result when a request fails.
+static API_ERROR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?mi)^\s*API Error:?\s*(.*)$").unwrap());
+static STATUS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d{3})\b").unwrap());
+static ERROR_TYPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""type"\s*:\s*"(\w+_error)""#).unwrap());
+static SERVER_ERROR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"api error:?\s*5\d\d").unwrap());
+static HOUR_LIMIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b\d+-hour limit reached").unwrap());
+static RESET_EPOCH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"limit reached\|(\d{9,})").unwrap());
+static RESET_RELATIVE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:in|after)\s+(\d+)\s*(s|sec|second|m|min|minute|h|hr|hour)s?\b").unwrap()
+});
+static RESET_CLOCK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)resets?\s+(?:at\s+)?(\d{1,2})(?::(\d{2}))?\s*(am|pm)?").unwrap()
+});
+
+/// Classify a failed invocation from its final `result` event (if any) and stderr.
+///
+/// The result text is the agent's own prose unless the CLI put an error there,
+/// so only its `API Error:` lines (status, error type, message) and the CLI's
+/// fixed login and usage-limit messages are read; the broad patterns are
+/// matched against those and stderr alone.
+pub fn classify(
+    subtype: Option<&str>,
+    result_text: &str,
+    stderr: &str,
+    now: DateTime<Local>,
+) -> FailureClass {
+    if subtype == Some("error_max_turns") {
+        return FailureClass::MaxTurns;
+    }
+    let api_errors: Vec<&str> = API_ERROR
+        .captures_iter(result_text)
+        .map(|c| c.get(1).map_or("", |m| m.as_str()))
+        .collect();
+    let status: Vec<&str> = api_errors
+        .iter()
+        .filter_map(|e| STATUS.captures(e).and_then(|c| c.get(1)))
+        .map(|m| m.as_str())
+        .collect();
+    let error_type: Vec<&str> = api_errors
+        .iter()
+        .flat_map(|e| ERROR_TYPE.captures_iter(e))
+        .filter_map(|c| c.get(1))
+        .map(|m| m.as_str())
+        .collect();
+    let errors = format!("{}\n{}", api_errors.join("\n"), stderr).to_lowercase();
+    let result = result_text.to_lowercase();
+
+    let has = |needles: &[&str]| needles.iter().any(|n| errors.contains(n));
+    let said = |needles: &[&str]| needles.iter().any(|n| result.contains(n));
+    let status_is = |codes: &[&str]| status.iter().any(|s| codes.contains(s));
+    let type_is = |types: &[&str]| error_type.iter().any(|t| types.contains(t));
+
+    if has(&["maximum number of turns", "max turns reached"]) {
+        return FailureClass::MaxTurns;
+    }
+    if status_is(&["401", "403"])
+        || type_is(&["authentication_error", "permission_error"])
+        || said(&[
+            "invalid api key",
+            "please run /login",
+            "oauth token has expired",
+        ])
+        || has(&[
+            "authentication_error",
+            "invalid api key",
+            "oauth token has expired",
+            "please run /login",
+            "not logged in",
+            "permission_error",
+        ])
+    {
+        return FailureClass::Auth;
+    }
+    if status_is(&["404"])
+        || type_is(&["not_found_error"])
+        || has(&[
+            "not_found_error",
+            "model_not_found",
+            "model not found",
+            "invalid model",
+            "unknown model",
+            "not available for your account",
+            "does not have access to model",
+        ])
+    {
+        return FailureClass::Unavailable;
+    }
+    if status_is(&["429"])
+        || type_is(&["rate_limit_error"])
+        || said(&["usage limit reached|", "hit your limit"])
+        || HOUR_LIMIT.is_match(result_text)
+        || has(&[
+            "usage limit",
+            "rate_limit",
+            "rate limit",
+            "hit your limit",
+            "limit reached",
+        ])
+    {
+        return FailureClass::RateLimited {
+            reset: parse_reset(&format!("{}\n{}", result_text, stderr), now),
+        };
+    }
+    if status.iter().any(|s| s.starts_with('5'))
+        || type_is(&["overloaded_error", "api_error"])
+        || SERVER_ERROR.is_match(&errors)
+        || has(&[
+            "overloaded",
+            "internal server error",
+            "api_error",
+            "timed out",
+            "timeout",
+            "econnreset",
+            "econnrefused",
+            "etimedout",
+            "enotfound",
+            "socket hang up",
+            "fetch failed",
+            "connection error",
+            "network error",
+        ])
+    {
+        return FailureClass::Transient;
+    }
+    FailureClass::Fatal
+}
+
+/// When a rate or usage limit lifts, from messages such as
+/// `usage limit reached|1767225600`, `resets 3pm`, `resets at 14:30` or
+/// `try again in 20 minutes`. Clock times are taken as local time.
+pub fn parse_reset(text: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if let Some(c) = RESET_EPOCH.captures(text) {
+        let secs: i64 = c[1].parse().ok()?;
+        return Local.timestamp_opt(secs, 0).single();
+    }
+
+    if let Some(c) = RESET_RELATIVE.captures(text) {
+        let n: i64 = c[1].parse().ok()?;
+        let secs = match c[2].to_lowercase().chars().next()? {
+            's' => n,
+            'm' => n * 60,
+            _ => n * 3600,
+        };
+        return Some(now + ChronoDuration::seconds(secs));
+    }
+
+    let c = RESET_CLOCK.captures(text)?;
+    let mut hour: u32 = c[1].parse().ok()?;
+    let minute: u32 = c.get(2).map_or(Some(0), |m| m.as_str().parse().ok())?;
+    match c.get(3).map(|m| m.as_str().to_lowercase()).as_deref() {
+        Some("pm") if hour < 12 => hour += 12,
+        Some("am") if hour == 12 => hour = 0,
+        _ => {}
+    }
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+    let today = now.date_naive().and_time(time);
+    let mut at = Local.from_local_datetime(&today).earliest()?;
+    if at <= now {
+        at += ChronoDuration::days(1);
+    }
+    Some(at)
+}
+
+/// Retries used so far by one invocation.
+#[derive(Debug, Default)]
+pub struct RetryState {
+    /// Idle timeouts, transient errors and max-turns reruns.
+    pub attempts: u32,
+    /// Waits for a rate or usage limit to reset.
+    pub reset_waits: u32,
+}
+
+impl RetryState {
+    /// How long to wait before retrying after `err`, with a log message, or
+    /// `None` if the error should surface.
+    pub fn next(
+        &mut self,
+        err: &anyhow::Error,
+        config: &Config,
+        now: DateTime<Local>,
+    ) -> Option<(Duration, String)> {
+        let max = config.limits.max_agent_retries;
+        let class = match err.downcast_ref::<AgentError>()? {
+            AgentError::IdleTimeout { .. } => {
+                return self.backoff(config, max).map(|d| {
+                    (
+                        d,
+                        format!("Idle timeout — retrying agent ({}/{})", self.attempts, max),
+                    )
+                })
+            }
+            AgentError::Failed { class, .. } => class,
+            _ => return None,
+        };
+        match class {
+            FailureClass::Transient => self.backoff(config, max).map(|d| {
+                (
+                    d,
+                    format!(
+                        "Transient API error — retrying agent ({}/{})",
+                        self.attempts, max
+                    ),
+                )
+            }),
+            FailureClass::RateLimited { reset } => {
+                let until_reset = reset
+                    .and_then(|at| (at - now).to_std().ok())
+                    .filter(|d| *d <= MAX_RESET_WAIT);
+                match until_reset {
+                    Some(wait)
+                        if config.limits.wait_for_usage_reset
+                            && self.reset_waits < MAX_RESET_WAITS =>
+                    {
+                        self.reset_waits += 1;
+                        let at = reset.unwrap_or(now);
+                        Some((
+                            wait + RESET_MARGIN,
+                            format!(
+                                "Rate limited — waiting until {} for the limit to reset",
+                                at.format("%Y-%m-%d %H:%M")
+                            ),
+                        ))
+                    }
+                    _ => self.backoff(config, max).map(|d| {
+                        (
+                            d,
+                            format!("Rate limited — retrying agent ({}/{})", self.attempts, max),
+                        )
+                    }),
+                }
+            }
+            // The agent's changes are kept, so one more run continues from there
+            FailureClass::MaxTurns if self.attempts == 0 && max > 0 => {
+                self.attempts += 1;
+                Some((
+                    Duration::ZERO,
+                    "Agent ran out of turns — running it once more to continue".to_string(),
+                ))
+            }
+            FailureClass::MaxTurns
+            | FailureClass::Auth
+            | FailureClass::Unavailable
+            | FailureClass::Fatal => None,
+        }
+    }
+
+    /// `retry_backoff_secs`, doubling with each attempt up to `retry_backoff_max_secs`.
+    fn backoff(&mut self, config: &Config, max: u32) -> Option<Duration> {
+        if self.attempts >= max {
+            return None;
+        }
+        let base = config.limits.retry_backoff_secs;
+        let delay = base
+            .saturating_mul(1u64 << self.attempts.min(32))
+            .min(config.limits.retry_backoff_max_secs.max(base));
+        self.attempts += 1;
+        Some(Duration::from_secs(delay))
+    }
+}
+
+/// Whether `err` is specific to the model, so the next model of a `[models]`
+/// list should be tried: it is unavailable or rate-limited.
+pub fn falls_back(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<AgentError>(),
+        Some(AgentError::Failed {
+            class: FailureClass::Unavailable | FailureClass::RateLimited { .. },
+            ..
+        })
+    )
+}
+
+/// Sleep for `delay`, returning early with an error on Ctrl-C.
+pub fn wait(delay: Duration) -> anyhow::Result<()> {
+    if delay >= Duration::from_secs(60) {
+        terminal::log_info(&format!(
+            "Sleeping {}m{}s (Ctrl-C to stop)...",
+            delay.as_secs() / 60,
+            delay.as_secs() % 60
+        ));
+    }
+    let deadline = std::time::Instant::now() + delay;
+    while std::time::Instant::now() < deadline {
+        interrupt::check()?;
+        let left = deadline.saturating_duration_since(std::time::Instant::now());
+        std::thread::sleep(left.min(Duration::from_millis(500)));
+    }
+    interrupt::check()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 3, 10, 22, 15, 0).unwrap()
+    }
+
+    #[test]
+    fn test_classify() {
+        let class = |subtype, result, stderr| classify(subtype, result, stderr, now());
+        assert_eq!(
+            class(Some("error_max_turns"), "", ""),
+            FailureClass::MaxTurns
+        );
+        assert_eq!(
+            class(None, "Invalid API key · Please run /login", ""),
+            FailureClass::Auth
+        );
+        assert_eq!(
+            class(
+                None,
+                r#"API Error: 529 {"type":"error","error":{"type":"overloaded_error"}}"#,
+                ""
+            ),
+            FailureClass::Transient
+        );
+        assert_eq!(
+            class(None, "", "Error: read ECONNRESET"),
+            FailureClass::Transient
+        );
+        assert_eq!(
+            class(None, "API Error: 500 Internal server error", ""),
+            FailureClass::Transient
+        );
+        assert!(matches!(
+            class(None, "5-hour limit reached ∙ resets 2am", ""),
+            FailureClass::RateLimited { reset: Some(_) }
+        ));
+        assert_eq!(
+            class(
+                None,
+                r#"API Error: 404 {"type":"error","error":{"type":"not_found_error","message":"model: claude-nope"}}"#,
+                ""
+            ),
+            FailureClass::Unavailable
+        );
+        assert_eq!(
+            class(None, "", "TypeError: undefined is not a function"),
+            FailureClass::Fatal
+        );
+        assert_eq!(
+            class(None, "API Error: Request timed out.", ""),
+            FailureClass::Transient
+        );
+        // The agent's own summary of what went wrong is not an API error
+        assert_eq!(
+            class(
+                None,
+                "The solver timed out on the fine mesh and the permission_error \
+                 check hit its rate limit; api_error handling is missing.",
+                ""
+            ),
+            FailureClass::Fatal
+        );
+    }
+
+    #[test]
+    fn test_parse_reset() {
+        let at = |text: &str| parse_reset(text, now()).map(|t| t.naive_local().to_string());
+        // Already past today: tomorrow
+        assert_eq!(
+            at("You've hit your limit · resets 2am (Europe/Berlin)").as_deref(),
+            Some("2026-03-11 02:00:00")
+        );
+        assert_eq!(
+            at("resets at 23:30").as_deref(),
+            Some("2026-03-10 23:30:00")
+        );
+        assert_eq!(
+            at("Rate limited, try again in 20 minutes").as_deref(),
+            Some("2026-03-10 22:35:00")
+        );
+        let epoch = parse_reset("Claude AI usage limit reached|1773180000", now()).unwrap();
+        assert_eq!(epoch.timestamp(), 1773180000);
+        assert_eq!(at("something broke"), None);
+    }
+
+    #[test]
+    fn test_retry_policies() {
+        let mut config: Config = toml::from_str("[project]\nname = \"t\"\n").unwrap();
+        config.limits.max_agent_retries = 3;
+        config.limits.retry_backoff_secs = 10;
+        config.limits.retry_backoff_max_secs = 25;
+        let failed = |class| -> anyhow::Error {
+            AgentError::Failed {
+                class,
+                message: String::new(),
+            }
+            .into()
+        };
+
+        let mut state = RetryState::default();
+        let transient = failed(FailureClass::Transient);
+        let delays: Vec<_> = std::iter::from_fn(|| state.next(&transient, &config, now()))
+            .map(|(d, _)| d.as_secs())
+            .collect();
+        assert_eq!(delays, vec![10, 20, 25]);
+
+        let mut state = RetryState::default();
+        let reset = now() + ChronoDuration::hours(4);
+        let limited = failed(FailureClass::RateLimited { reset: Some(reset) });
+        let (delay, msg) = state.next(&limited, &config, now()).unwrap();
+        assert_eq!(delay, Duration::from_secs(4 * 3600) + RESET_MARGIN);
+        assert!(msg.contains("waiting until 2026-03-11 02:15"), "{}", msg);
+        assert_eq!(state.attempts, 0);
+
+        let mut state = RetryState::default();
+        assert!(state
+            .next(&failed(FailureClass::Auth), &config, now())
+            .is_none());
+        assert!(state
+            .next(&failed(FailureClass::Fatal), &config, now())
+            .is_none());
+        let max_turns = failed(FailureClass::MaxTurns);
+        assert_eq!(
+            state.next(&max_turns, &config, now()).unwrap().0,
+            Duration::ZERO
+        );
+        assert!(state.next(&max_turns, &config, now()).is_none());
+    }
+}
@@ -1,10 +1,25 @@
 use anyhow::{Context, Result};
-use std::process::Command;
+use std::cell::RefCell;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
 
 use crate::config::Config;
+use crate::interrupt;
+use crate::process;
 use crate::terminal;
 
+/// A git command in this thread's working directory (see [`process::in_dir`]).
+fn git() -> Command {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = process::workdir() {
+        cmd.current_dir(dir);
+    }
+    cmd
+}
+
 pub fn commit_all(msg: &str, config: &Config) -> Result<bool> {
+    interrupt::check()?;
     if !config.git.auto_commit {
         terminal::log_info("Skipping commit (auto_commit = false)");
         return Ok(false);
@@ -42,52 +57,83 @@ pub fn commit_all(msg: &str, config: &Config) -> Result<bool> {
     }
 
     let mut args = vec!["add".to_string(), "--".to_string()];
-    args.extend(paths);
+    args.extend(paths.iter().cloned());
 
-    let status = Command::new("git")
+    let status = git()
         .args(&args)
         .status()
         .context("Failed to run git add")?;
+    STAGED.with(|s| *s.borrow_mut() = paths);
 
     if !status.success() {
+        let _ = unstage_pending();
         anyhow::bail!("git add failed");
     }
 
     // Check if there are staged changes
-    let diff = Command::new("git")
+    let diff = git()
         .args(["diff", "--cached", "--quiet"])
         .status()
         .context("Failed to run git diff")?;
 
     if diff.success() {
+        STAGED.with(|s| s.borrow_mut().clear());
         terminal::log_info("No changes to commit.");
         return Ok(false);
     }
+    if interrupt::requested() {
+        let _ = unstage_pending();
+        return Err(interrupt::Interrupted.into());
+    }
 
     terminal::log_info(&format!("Committing: {}", msg));
 
-    let status = Command::new("git")
+    let status = git()
         .args(["commit", "-m", msg])
         .status()
         .context("Failed to run git commit")?;
 
     if status.success() {
+        STAGED.with(|s| s.borrow_mut().clear());
         terminal::log_success("Commit created.");
         Ok(true)
     } else {
         // Unstage files to avoid leaving a dirty index for the next resume
-        let _ = Command::new("git").args(["reset", "HEAD", "--"]).status();
+        let _ = unstage_pending();
         anyhow::bail!("git commit failed")
     }
 }
 
+thread_local! {
+    /// Paths [`commit_all`] staged on this thread and has not committed yet.
+    static STAGED: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Unstage what [`commit_all`] staged without committing, keeping the working
+/// tree. Whatever the user staged elsewhere stays staged.
+pub fn unstage_pending() -> Result<()> {
+    let paths = STAGED.with(|s| std::mem::take(&mut *s.borrow_mut()));
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let status = git()
+        .args(["reset", "-q", "HEAD", "--"])
+        .args(&paths)
+        .status()
+        .context("Failed to run git reset")?;
+    if !status.success() {
+        anyhow::bail!("git reset HEAD failed");
+    }
+    Ok(())
+}
+
 pub fn push(config: &Config) -> Result<()> {
     if !config.git.auto_push {
         terminal::log_info("Skipping push (auto_push = false)");
         return Ok(());
     }
 
-    let output = Command::new("git")
+    let output = git()
         .args(["rev-parse", "--abbrev-ref", "HEAD"])
         .output()
         .context("Failed to get current branch")?;
@@ -95,7 +141,7 @@ pub fn push(config: &Config) -> Result<()> {
     let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
     terminal::log_info(&format!("Pushing to origin/{}...", branch));
 
-    let status = Command::new("git")
+    let status = git()
         .args(["push", "-u", "origin", &branch])
         .status()
         .context("Failed to run git push")?;
@@ -112,7 +158,7 @@ pub fn push(config: &Config) -> Result<()> {
 }
 
 pub fn is_git_repo() -> bool {
-    Command::new("git")
+    git()
         .args(["rev-parse", "--git-dir"])
         .output()
         .map(|o| o.status.success())
@@ -132,7 +178,7 @@ pub fn source_changed_in_last_commit(source_dirs: &[String]) -> Result<bool> {
     ];
     args.extend(source_dirs.iter().cloned());
 
-    let output = Command::new("git")
+    let output = git()
         .args(&args)
         .output()
         .context("Failed to run git diff HEAD~1 HEAD")?;
@@ -148,7 +194,7 @@ pub fn source_changed_in_last_commit(source_dirs: &[String]) -> Result<bool> {
 
 /// Create a lightweight git tag (force-replace for idempotency).
 pub fn create_tag(name: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = git()
         .args(["tag", "-f", name])
         .status()
         .context("Failed to create git tag")?;
@@ -161,10 +207,7 @@ pub fn create_tag(name: &str) -> Result<()> {
 
 /// List pass tags (lisa/pass-*) and return sorted pass numbers.
 pub fn list_pass_tags() -> Vec<u32> {
-    let output = match Command::new("git")
-        .args(["tag", "--list", "lisa/pass-*"])
-        .output()
-    {
+    let output = match git().args(["tag", "--list", "lisa/pass-*"]).output() {
         Ok(o) if o.status.success() => o,
         _ => return Vec::new(),
     };
@@ -187,7 +230,7 @@ fn parse_pass_tags(output: &str) -> Vec<u32> {
 
 /// Create a branch at current HEAD.
 pub fn create_branch(name: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = git()
         .args(["branch", name])
         .status()
         .context("Failed to create git branch")?;
@@ -199,7 +242,7 @@ pub fn create_branch(name: &str) -> Result<()> {
 
 /// git reset --hard to a target ref.
 pub fn reset_hard(target: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = git()
         .args(["reset", "--hard", target])
         .status()
         .context("Failed to run git reset --hard")?;
@@ -209,9 +252,170 @@ pub fn reset_hard(target: &str) -> Result<()> {
     Ok(())
 }
 
+/// Discard uncommitted changes under `path`: restore tracked files and
+/// remove untracked ones.
+pub fn discard_changes(path: &str) -> Result<()> {
+    // Restore fails when nothing under `path` is tracked yet; cleaning still applies
+    let _ = git().args(["checkout", "HEAD", "--", path]).output();
+    let status = git()
+        .args(["clean", "-fdq", "--", path])
+        .status()
+        .context("Failed to run git clean")?;
+    if !status.success() {
+        anyhow::bail!("git clean {} failed", path);
+    }
+    Ok(())
+}
+
+/// Commit hash of HEAD, or `None` before the first commit.
+pub fn head_commit() -> Option<String> {
+    rev_parse("HEAD")
+}
+
+/// Object id `rev` names, `None` if it does not exist.
+pub fn rev_parse(rev: &str) -> Option<String> {
+    let output = git()
+        .args(["rev-parse", "--verify", "-q", rev])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The tag matching `pattern` closest behind HEAD, `None` if there is none.
+pub fn nearest_tag(pattern: &str) -> Option<String> {
+    let output = git()
+        .args([
+            "describe",
+            "--tags",
+            "--abbrev=0",
+            "--match",
+            pattern,
+            "HEAD",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Files under `dir` committed at `rev`, relative to the repository root.
+pub fn ls_tree(rev: &str, dir: &str) -> Result<Vec<String>> {
+    let output = git()
+        .args(["ls-tree", "-r", "--name-only", rev, "--", dir])
+        .output()
+        .context("Failed to run git ls-tree")?;
+    if !output.status.success() {
+        anyhow::bail!("git ls-tree {} failed", rev);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Store the contents of `paths` in the object database. Returns their blob ids,
+/// in order.
+pub fn hash_objects(paths: &[&Path]) -> Result<Vec<String>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut child = git()
+        .args(["hash-object", "-w", "--stdin-paths"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to run git hash-object")?;
+    let mut stdin = child.stdin.take().expect("piped stdin");
+    for path in paths {
+        writeln!(stdin, "{}", path.display())?;
+    }
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("git hash-object failed");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Contents of a blob, by id or as `rev:path`.
+pub fn read_blob(id: &str) -> Result<Vec<u8>> {
+    let output = git()
+        .args(["cat-file", "blob", id])
+        .output()
+        .context("Failed to run git cat-file")?;
+    if !output.status.success() {
+        anyhow::bail!("git cat-file blob {} failed", id);
+    }
+    Ok(output.stdout)
+}
+
+/// Apply the change from blob `base` to blob `other` onto blob `current`
+/// (`git merge-file`). `None` when the change conflicts.
+pub fn merge_blobs(current: &str, base: &str, other: &str) -> Result<Option<Vec<u8>>> {
+    let dir = std::env::temp_dir();
+    let mut files = Vec::new();
+    for id in [current, base, other] {
+        let file = dir.join(format!("lisa-blob-{}", id));
+        std::fs::write(&file, read_blob(id)?)
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+        files.push(file);
+    }
+    let output = git()
+        .args(["merge-file", "-p", "-q"])
+        .args(&files)
+        .output()
+        .context("Failed to run git merge-file")?;
+    for file in &files {
+        let _ = std::fs::remove_file(file);
+    }
+    match output.status.code() {
+        Some(0) => Ok(Some(output.stdout)),
+        Some(n) if n > 0 => Ok(None),
+        _ => anyhow::bail!("git merge-file failed"),
+    }
+}
+
+/// Check out HEAD into a new detached worktree at `path`.
+pub fn add_worktree(path: &Path) -> Result<()> {
+    let output = git()
+        .args(["worktree", "add", "--detach"])
+        .arg(path)
+        .arg("HEAD")
+        .output()
+        .context("Failed to run git worktree add")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git worktree add {} failed: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// Forget worktrees whose directories no longer exist.
+pub fn prune_worktrees() -> Result<()> {
+    let status = git()
+        .args(["worktree", "prune"])
+        .status()
+        .context("Failed to run git worktree prune")?;
+    if !status.success() {
+        anyhow::bail!("git worktree prune failed");
+    }
+    Ok(())
+}
+
 /// Check for uncommitted changes (staged or unstaged).
 pub fn has_uncommitted_changes() -> Result<bool> {
-    let output = Command::new("git")
+    let output = git()
         .args(["status", "--porcelain"])
         .output()
         .context("Failed to run git status")?;
@@ -220,7 +424,7 @@ pub fn has_uncommitted_changes() -> Result<bool> {
 
 /// Checkout a branch or ref.
 pub fn checkout(target: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = git()
         .args(["checkout", target])
         .status()
         .context("Failed to run git checkout")?;
@@ -232,7 +436,7 @@ pub fn checkout(target: &str) -> Result<()> {
 
 /// Get the current branch name.
 pub fn current_branch() -> Result<String> {
-    let output = Command::new("git")
+    let output = git()
         .args(["rev-parse", "--abbrev-ref", "HEAD"])
         .output()
         .context("Failed to get current branch")?;
@@ -244,7 +448,7 @@ pub fn current_branch() -> Result<String> {
 
 /// Merge a branch into the current branch with --no-ff.
 pub fn merge_branch(branch: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = git()
         .args([
             "merge",
             branch,
@@ -260,9 +464,35 @@ pub fn merge_branch(branch: &str) -> Result<()> {
     Ok(())
 }
 
+/// Merge `rev` into the current branch with --no-ff. On a conflict the
+/// merge is aborted, leaving the tree as it was, and false is returned.
+pub fn try_merge(rev: &str, message: &str) -> Result<bool> {
+    let output = git()
+        .args(["merge", "--no-ff", "-m", message, rev])
+        .output()
+        .context("Failed to run git merge")?;
+    if output.status.success() {
+        return Ok(true);
+    }
+    let _ = git().args(["merge", "--abort"]).output();
+    Ok(false)
+}
+
+/// Point branch `name` at `target`, creating or moving it.
+pub fn set_branch(name: &str, target: &str) -> Result<()> {
+    let status = git()
+        .args(["branch", "-f", name, target])
+        .status()
+        .context("Failed to create git branch")?;
+    if !status.success() {
+        anyhow::bail!("git branch {} failed", name);
+    }
+    Ok(())
+}
+
 /// Delete a local branch.
 pub fn delete_branch(name: &str) -> Result<()> {
-    let status = Command::new("git")
+    let status = git()
         .args(["branch", "-D", name])
         .status()
         .context("Failed to delete git branch")?;
@@ -0,0 +1,295 @@
+//! The task plan's dependency graph as Graphviz DOT or Mermaid.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::tasks::{Plan, Status, Task};
+
+/// Fill colors per status, shared by both formats.
+fn fill(status: &Status) -> &'static str {
+    match status {
+        Status::Todo => "#eeeeee",
+        Status::InProgress => "#fff59d",
+        Status::Done => "#c8e6c9",
+        Status::Blocked => "#ffcdd2",
+        Status::Unknown(_) => "#d7ccc8",
+    }
+}
+
+const CRITICAL: &str = "#d32f2f";
+
+/// The longest chain of dependencies, first task first. Ties go to the task
+/// that comes first in the plan. Edges to missing tasks and edges that close a
+/// cycle are ignored. Empty when no task depends on another, since a lone
+/// task is no chain worth highlighting.
+pub fn critical_path(plan: &Plan) -> Vec<u32> {
+    fn depth(
+        task: &Task,
+        plan: &Plan,
+        memo: &mut HashMap<u32, usize>,
+        on_stack: &mut HashSet<u32>,
+    ) -> usize {
+        if let Some(&d) = memo.get(&task.number) {
+            return d;
+        }
+        on_stack.insert(task.number);
+        let mut deepest = 0;
+        for dep in &task.dependencies {
+            if on_stack.contains(dep) {
+                continue;
+            }
+            if let Some(dep) = plan.task(*dep) {
+                deepest = deepest.max(depth(dep, plan, memo, on_stack));
+            }
+        }
+        on_stack.remove(&task.number);
+        memo.insert(task.number, deepest + 1);
+        deepest + 1
+    }
+
+    let mut memo = HashMap::new();
+    let mut end: Option<(&Task, usize)> = None;
+    for task in &plan.tasks {
+        let d = depth(task, plan, &mut memo, &mut HashSet::new());
+        if end.is_none_or(|(_, best)| d > best) {
+            end = Some((task, d));
+        }
+    }
+
+    let mut path = Vec::new();
+    let mut current = end.map(|(task, _)| task);
+    while let Some(task) = current {
+        path.push(task.number);
+        let want = memo[&task.number] - 1;
+        current = task
+            .dependencies
+            .iter()
+            .filter(|d| !path.contains(*d))
+            .filter_map(|d| plan.task(*d))
+            .find(|d| want > 0 && memo.get(&d.number) == Some(&want));
+    }
+    if path.len() < 2 {
+        return Vec::new();
+    }
+    path.reverse();
+    path
+}
+
+/// Dependency edges between tasks that exist, in plan order.
+fn edges(plan: &Plan) -> Vec<(u32, u32)> {
+    plan.tasks
+        .iter()
+        .flat_map(|task| {
+            task.dependencies
+                .iter()
+                .filter(|d| plan.task(**d).is_some())
+                .map(move |d| (*d, task.number))
+        })
+        .collect()
+}
+
+fn is_critical_edge(path: &[u32], from: u32, to: u32) -> bool {
+    path.windows(2).any(|w| w[0] == from && w[1] == to)
+}
+
+/// Passes in ascending order, each with its tasks in plan order.
+fn by_pass(plan: &Plan) -> Vec<(u32, Vec<&Task>)> {
+    let mut passes: Vec<u32> = plan.tasks.iter().map(|t| t.pass).collect();
+    passes.sort_unstable();
+    passes.dedup();
+    passes
+        .into_iter()
+        .map(|pass| (pass, plan.tasks.iter().filter(|t| t.pass == pass).collect()))
+        .collect()
+}
+
+pub fn render_dot(plan: &Plan) -> String {
+    let path = critical_path(plan);
+    let mut out = String::new();
+    writeln!(out, "digraph plan {{").unwrap();
+    writeln!(out, "  rankdir=LR;").unwrap();
+    writeln!(out, "  node [shape=box, style=\"rounded,filled\"];").unwrap();
+    for (pass, tasks) in by_pass(plan) {
+        writeln!(out, "  subgraph cluster_pass_{} {{", pass).unwrap();
+        writeln!(out, "    label=\"Pass {}\";", pass).unwrap();
+        for task in tasks {
+            let label =
+                format!("{}: {}\\n{}", task.number, task.name, task.status).replace('"', "\\\"");
+            let critical = if path.contains(&task.number) {
+                format!(", color=\"{}\", penwidth=2", CRITICAL)
+            } else {
+                String::new()
+            };
+            writeln!(
+                out,
+                "    task{} [label=\"{}\", fillcolor=\"{}\"{}];",
+                task.number,
+                label,
+                fill(&task.status),
+                critical
+            )
+            .unwrap();
+        }
+        writeln!(out, "  }}").unwrap();
+    }
+    for (from, to) in edges(plan) {
+        if is_critical_edge(&path, from, to) {
+            writeln!(
+                out,
+                "  task{} -> task{} [color=\"{}\", penwidth=2];",
+                from, to, CRITICAL
+            )
+            .unwrap();
+        } else {
+            writeln!(out, "  task{} -> task{};", from, to).unwrap();
+        }
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+pub fn render_mermaid(plan: &Plan) -> String {
+    let path = critical_path(plan);
+    let mut out = String::new();
+    writeln!(out, "flowchart LR").unwrap();
+    for (pass, tasks) in by_pass(plan) {
+        writeln!(out, "  subgraph pass{}[\"Pass {}\"]", pass, pass).unwrap();
+        for task in tasks {
+            let label = format!("{}: {}<br/>{}", task.number, task.name, task.status)
+                .replace('"', "#quot;");
+            writeln!(out, "    task{}[\"{}\"]", task.number, label).unwrap();
+        }
+        writeln!(out, "  end").unwrap();
+    }
+    let edges = edges(plan);
+    for (from, to) in &edges {
+        writeln!(out, "  task{} --> task{}", from, to).unwrap();
+    }
+    for task in &plan.tasks {
+        let mut style = format!("fill:{}", fill(&task.status));
+        if path.contains(&task.number) {
+            write!(style, ",stroke:{},stroke-width:3px", CRITICAL).unwrap();
+        }
+        writeln!(out, "  style task{} {}", task.number, style).unwrap();
+    }
+    let critical: Vec<String> = edges
+        .iter()
+        .enumerate()
+        .filter(|(_, (from, to))| is_critical_edge(&path, *from, *to))
+        .map(|(i, _)| i.to_string())
+        .collect();
+    if !critical.is_empty() {
+        writeln!(
+            out,
+            "  linkStyle {} stroke:{},stroke-width:3px",
+            critical.join(","),
+            CRITICAL
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// A fenced Mermaid block, for embedding in markdown reports.
+pub fn mermaid_block(plan: &Plan) -> String {
+    format!("```mermaid\n{}```\n", render_mermaid(plan))
+}
+
+const EMBED_START: &str = "<!-- lisa:task-graph -->";
+const EMBED_END: &str = "<!-- /lisa:task-graph -->";
+
+/// `markdown` with a "Task Graph" section at the end, replacing the one a
+/// previous call added.
+pub fn embed(markdown: &str, plan: &Plan) -> String {
+    let mut out = match (markdown.find(EMBED_START), markdown.find(EMBED_END)) {
+        (Some(start), Some(end)) if start < end => format!(
+            "{}{}",
+            &markdown[..start],
+            markdown[end + EMBED_END.len()..].trim_start_matches('\n')
+        ),
+        _ => markdown.to_string(),
+    };
+    let kept = out.trim_end().len();
+    out.truncate(kept);
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    format!(
+        "{}{}\n## Task Graph\n\n{}{}\n",
+        out,
+        EMBED_START,
+        mermaid_block(plan),
+        EMBED_END
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = "### Task 1: Units\n- **Status:** DONE\n- **Pass:** 1\n\n\
+        ### Task 2: Drag \"model\"\n- **Status:** IN_PROGRESS\n- **Pass:** 1\n- **Dependencies:** Task 1\n\n\
+        ### Task 3: Lift\n- **Status:** TODO\n- **Pass:** 1\n- **Dependencies:** Task 1\n\n\
+        ### Task 4: Solver\n- **Status:** BLOCKED\n- **Pass:** 2\n- **Dependencies:** Task 2, Task 3, Task 9\n";
+
+    #[test]
+    fn test_critical_path_is_longest_chain() {
+        let plan = Plan::parse(PLAN);
+        assert_eq!(critical_path(&plan), vec![1, 2, 4]);
+        assert!(critical_path(&Plan::default()).is_empty());
+    }
+
+    #[test]
+    fn test_no_critical_path_without_dependencies() {
+        let plan = Plan::parse("### Task 1: A\n- **Pass:** 1\n\n### Task 2: B\n- **Pass:** 1\n");
+        assert!(critical_path(&plan).is_empty());
+        assert!(!render_dot(&plan).contains(CRITICAL));
+        assert!(!render_mermaid(&plan).contains(CRITICAL));
+    }
+
+    #[test]
+    fn test_critical_path_ignores_cycles() {
+        let plan = Plan::parse(
+            "### Task 1: A\n- **Dependencies:** Task 2\n\n### Task 2: B\n- **Dependencies:** Task 1\n",
+        );
+        assert_eq!(critical_path(&plan).len(), 2);
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let dot = render_dot(&Plan::parse(PLAN));
+        assert!(dot.starts_with("digraph plan {\n"));
+        assert!(dot.contains("  subgraph cluster_pass_2 {\n    label=\"Pass 2\";\n"));
+        assert!(dot.contains(
+            "    task2 [label=\"2: Drag \\\"model\\\"\\nIN_PROGRESS\", fillcolor=\"#fff59d\", color=\"#d32f2f\", penwidth=2];"
+        ));
+        assert!(dot.contains("    task3 [label=\"3: Lift\\nTODO\", fillcolor=\"#eeeeee\"];"));
+        assert!(dot.contains("  task2 -> task4 [color=\"#d32f2f\", penwidth=2];"));
+        assert!(dot.contains("  task3 -> task4;"));
+        assert!(!dot.contains("task9"));
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        let mermaid = render_mermaid(&Plan::parse(PLAN));
+        assert!(mermaid.starts_with("flowchart LR\n  subgraph pass1[\"Pass 1\"]\n"));
+        assert!(mermaid.contains("    task2[\"2: Drag #quot;model#quot;<br/>IN_PROGRESS\"]"));
+        assert!(mermaid.contains("  style task3 fill:#eeeeee\n"));
+        assert!(mermaid.contains("  style task4 fill:#ffcdd2,stroke:#d32f2f,stroke-width:3px\n"));
+        // Edges: 1->2, 1->3, 2->4, 3->4; the critical ones are 1->2 and 2->4
+        assert!(mermaid.contains("  linkStyle 0,2 stroke:#d32f2f,stroke-width:3px\n"));
+    }
+
+    #[test]
+    fn test_embed_replaces_previous_graph() {
+        let plan = Plan::parse(PLAN);
+        let once = embed("# Refine Summary\n\nNotes.\n", &plan);
+        assert!(once.starts_with("# Refine Summary\n\nNotes.\n\n<!-- lisa:task-graph -->\n## Task Graph\n\n```mermaid\nflowchart LR\n"));
+        assert!(once.ends_with("```\n<!-- /lisa:task-graph -->\n"));
+
+        let edited = once.replace("Notes.", "More notes.");
+        let twice = embed(&edited, &plan);
+        assert_eq!(twice, once.replace("Notes.", "More notes."));
+        assert_eq!(twice.matches("## Task Graph").count(), 1);
+    }
+}
@@ -0,0 +1,539 @@
+//! Verification that the Bounds agent never touched implementation code.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+use crate::agent::{format_tool_call_summary, ToolCall};
+use crate::config::Config;
+
+/// Heading of the section lisa appends to `review-package.md`.
+const SECTION_HEADING: &str = "## Bounds Independence (verified by Lisa)";
+
+/// Outcome of one Bounds agent run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoundsRun {
+    pub task: u32,
+    pub attempt: u32,
+    pub tool_calls: usize,
+    /// Tool calls that touched implementation code.
+    #[serde(default)]
+    pub violations: Vec<String>,
+    pub outcome: BoundsOutcome,
+}
+
+/// What became of a Bounds run's tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoundsOutcome {
+    /// No source access; the tests were kept.
+    Verified,
+    /// Source was accessed and `on_source_access = "warn"`; the tests were kept.
+    Warned,
+    /// Source was accessed; the tests were discarded and Bounds ran again.
+    Rerun,
+    /// Source was accessed; the tests were discarded and the run stopped.
+    Failed,
+}
+
+impl std::fmt::Display for BoundsOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BoundsOutcome::Verified => "verified",
+            BoundsOutcome::Warned => "warned",
+            BoundsOutcome::Rerun => "rerun",
+            BoundsOutcome::Failed => "failed",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// All Bounds runs of a pass, stored in `spiral/pass-N/bounds-independence.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndependenceLog {
+    #[serde(default)]
+    pub runs: Vec<BoundsRun>,
+}
+
+impl IndependenceLog {
+    /// True when every task's last Bounds run was free of source access.
+    pub fn verified(&self) -> bool {
+        self.runs
+            .iter()
+            .filter(|r| r.outcome != BoundsOutcome::Rerun)
+            .all(|r| r.violations.is_empty())
+    }
+}
+
+fn log_path(lisa_root: &Path, pass: u32) -> PathBuf {
+    lisa_root.join(format!("spiral/pass-{}/bounds-independence.toml", pass))
+}
+
+pub fn load_log(lisa_root: &Path, pass: u32) -> Result<IndependenceLog> {
+    let path = log_path(lisa_root, pass);
+    if !path.exists() {
+        return Ok(IndependenceLog::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+pub fn record_run(lisa_root: &Path, pass: u32, run: BoundsRun) -> Result<()> {
+    let mut log = load_log(lisa_root, pass)?;
+    log.runs.push(run);
+    let path = log_path(lisa_root, pass);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, toml::to_string_pretty(&log)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Return a summary of every tool call that read, searched, listed or ran
+/// something inside `paths.source`. `working_dir` is where the agent ran, if not
+/// the project root; absolute paths into either tree are recognised.
+pub fn find_source_accesses(
+    tool_log: &[ToolCall],
+    config: &Config,
+    project_root: &Path,
+    working_dir: Option<&Path>,
+) -> Vec<String> {
+    let mut roots = vec![project_root];
+    roots.extend(working_dir);
+    let scope = SourceScope::new(config, roots);
+    tool_log
+        .iter()
+        .filter(|call| scope.is_accessed_by(call))
+        .map(format_tool_call_summary)
+        .collect()
+}
+
+struct SourceScope<'a> {
+    /// The project root, then the agent's working directory if different.
+    roots: Vec<&'a Path>,
+    source: Vec<PathBuf>,
+    /// Process artifacts and test dirs, which may live inside a source dir.
+    allowed: Vec<PathBuf>,
+}
+
+impl<'a> SourceScope<'a> {
+    fn new(config: &Config, roots: Vec<&'a Path>) -> Self {
+        let normalize_all = |dirs: Vec<&String>| -> Vec<PathBuf> {
+            dirs.into_iter()
+                .filter(|d| !d.is_empty())
+                .filter_map(|d| normalize(d, &roots))
+                .collect()
+        };
+        Self {
+            source: normalize_all(config.paths.source.iter().collect()),
+            allowed: normalize_all(vec![
+                &config.paths.lisa_root,
+                &config.paths.tests_bounds,
+                &config.paths.tests_software,
+                &config.paths.tests_integration,
+            ])
+            .into_iter()
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect(),
+            roots,
+        }
+    }
+
+    fn is_source(&self, path: &Path) -> bool {
+        self.source.iter().any(|s| path.starts_with(s))
+            && !self.allowed.iter().any(|a| path.starts_with(a))
+    }
+
+    /// A search rooted at `path` covers source code if it starts in or above a source dir.
+    fn search_covers_source(&self, path: &Path) -> bool {
+        self.is_source(path)
+            || self
+                .source
+                .iter()
+                .any(|s| s.starts_with(path) && !self.allowed.iter().any(|a| s.starts_with(a)))
+    }
+
+    /// A shell word as a path relative to the project root. The shell may have
+    /// changed directory, so leading `..` are dropped rather than resolved, and
+    /// paths are matched on their source-dir prefix whether or not they exist.
+    /// Only when the project root itself is a source dir is a word required to
+    /// name an existing file, since every word would match otherwise.
+    fn bash_path(&self, token: &str) -> Option<PathBuf> {
+        let path = normalize(token, &self.roots).or_else(|| {
+            let rest: PathBuf = Path::new(token)
+                .components()
+                .skip_while(|c| matches!(c, Component::ParentDir | Component::CurDir))
+                .collect();
+            normalize(rest.to_str()?, &self.roots)
+        })?;
+        let under_named_source = self
+            .source
+            .iter()
+            .any(|s| !s.as_os_str().is_empty() && path.starts_with(s));
+        (under_named_source || self.roots[0].join(&path).exists()).then_some(path)
+    }
+
+    fn is_accessed_by(&self, call: &ToolCall) -> bool {
+        let norm = |p: &str| normalize(p, &self.roots);
+        match call {
+            ToolCall::Read { path } | ToolCall::Write { path } | ToolCall::Edit { path } => {
+                norm(path).is_some_and(|p| self.is_source(&p))
+            }
+            ToolCall::Grep { path, .. } => {
+                norm(path).is_some_and(|p| self.search_covers_source(&p))
+            }
+            ToolCall::Glob { pattern, path } => {
+                // Only the literal part of the pattern can pin it to a directory
+                let parts: Vec<&str> = pattern.split('/').collect();
+                let literal = parts
+                    .iter()
+                    .take_while(|part| !part.contains(['*', '?', '[', '{']))
+                    .count();
+                let Some(root) = norm(path).map(|root| root.join(parts[..literal].join("/")))
+                else {
+                    return false;
+                };
+                // A wildcard part spanning directories searches like Grep does
+                let wild = &parts[literal..];
+                if wild.len() > 1 || wild.iter().any(|part| part.contains("**")) {
+                    self.search_covers_source(&root)
+                } else {
+                    self.is_source(&root)
+                }
+            }
+            ToolCall::Bash { command } => command
+                .split(|c: char| c.is_whitespace() || ";|&<>()'\"`=,".contains(c))
+                .filter(|token| !token.is_empty() && !token.starts_with('-'))
+                .filter_map(|token| self.bash_path(token))
+                .any(|p| self.is_source(&p)),
+            ToolCall::Task { .. } | ToolCall::Other { .. } => false,
+        }
+    }
+}
+
+/// Resolve `path` to a path relative to one of `roots`, without `.` or `..`.
+/// Returns `None` for paths outside them.
+fn normalize(path: &str, roots: &[&Path]) -> Option<PathBuf> {
+    let path = Path::new(path);
+    let relative = if path.is_absolute() {
+        roots.iter().find_map(|root| path.strip_prefix(root).ok())?
+    } else {
+        path
+    };
+    let mut out = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(c) => out.push(c),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(out)
+}
+
+/// Markdown section stating whether Bounds independence was verified this pass.
+pub fn render_section(log: &IndependenceLog, config: &Config) -> String {
+    let mut out = format!("{}\n\n", SECTION_HEADING);
+    out.push_str(&format!(
+        "Lisa audited every file access of the Bounds agent against the implementation \
+         paths ({}). Policy: `on_source_access = \"{}\"`.\n\n",
+        config.source_dirs_display(),
+        config.bounds.on_source_access
+    ));
+    if config.bounds.sandbox {
+        out.push_str(
+            "The Bounds agent ran in a sandboxed worktree from which these paths and the \
+             git history were removed. The sandbox only restricts the file tools: reading \
+             or editing the project by absolute path is denied, but shell commands can \
+             still reach it and are only caught by the audit above.\n\n",
+        );
+    }
+
+    if log.runs.is_empty() {
+        out.push_str("No Bounds runs this pass.\n");
+        return out;
+    }
+
+    out.push_str("| Task | Attempt | Tool calls | Source accesses | Outcome |\n");
+    out.push_str("|------|---------|------------|-----------------|---------|\n");
+    for run in &log.runs {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            run.task,
+            run.attempt,
+            run.tool_calls,
+            run.violations.len(),
+            run.outcome
+        ));
+    }
+    out.push('\n');
+
+    if log.verified() {
+        out.push_str(
+            "**Independence verified:** no accepted Bounds run read, searched or ran implementation code.\n",
+        );
+    } else {
+        out.push_str(
+            "**Independence NOT verified:** the bounding tests below were derived by an agent that accessed implementation code.\n\n",
+        );
+        for run in log
+            .runs
+            .iter()
+            .filter(|r| r.outcome == BoundsOutcome::Warned)
+        {
+            for violation in &run.violations {
+                out.push_str(&format!("- Task {}: `{}`\n", run.task, violation));
+            }
+        }
+    }
+    out
+}
+
+/// Append (or replace) the independence section at the end of `review-package.md`.
+pub fn append_to_review_package(lisa_root: &Path, pass: u32, config: &Config) -> Result<()> {
+    let log = load_log(lisa_root, pass)?;
+    if log.runs.is_empty() {
+        return Ok(());
+    }
+    let path = lisa_root.join(format!("spiral/pass-{}/review-package.md", pass));
+    let existing = std::fs::read_to_string(&path).unwrap_or_default();
+    let body = match existing.find(SECTION_HEADING) {
+        Some(idx) => &existing[..idx],
+        None => &existing,
+    };
+    let mut content = body.trim_end().to_string();
+    if !content.is_empty() {
+        content.push_str("\n\n");
+    }
+    content.push_str(&render_section(&log, config));
+    std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+[project]
+name = "t"
+
+[paths]
+source = ["src", "pkg"]
+tests_bounds = "pkg/tests/bounds"
+tests_software = "tests/software"
+"#,
+        )
+        .unwrap()
+    }
+
+    fn read(path: &str) -> ToolCall {
+        ToolCall::Read {
+            path: path.to_string(),
+        }
+    }
+
+    fn grep(path: &str) -> ToolCall {
+        ToolCall::Grep {
+            pattern: "def".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    fn glob(pattern: &str) -> ToolCall {
+        ToolCall::Glob {
+            pattern: pattern.to_string(),
+            path: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_reads_of_source_are_flagged() {
+        let root = Path::new("/work/proj");
+        let log = vec![
+            read("src/model.py"),
+            read("/work/proj/pkg/solver.py"),
+            read("./tests/../src/util.py"),
+            read(".lisa/methodology/methodology.md"),
+            read("pkg/tests/bounds/test_limits.py"),
+            read("/etc/hosts"),
+            ToolCall::Write {
+                path: "src/new.py".to_string(),
+            },
+        ];
+        let hits = find_source_accesses(&log, &config(), root, None);
+        assert_eq!(
+            hits,
+            vec![
+                "Read src/model.py",
+                "Read /work/proj/pkg/solver.py",
+                "Read ./tests/../src/util.py",
+                "Write src/new.py",
+            ]
+        );
+
+        // Agents run in a sandbox: absolute paths into either tree count
+        let sandboxed = vec![
+            read("/tmp/sandbox/src/model.py"),
+            read("/tmp/other/src/x.py"),
+        ];
+        let hits =
+            find_source_accesses(&sandboxed, &config(), root, Some(Path::new("/tmp/sandbox")));
+        assert_eq!(hits, vec!["Read /tmp/sandbox/src/model.py"]);
+    }
+
+    #[test]
+    fn test_searches_covering_source_are_flagged() {
+        let root = Path::new("/work/proj");
+        let log = vec![
+            grep(""),
+            grep("src"),
+            grep(".lisa"),
+            grep("pkg/tests/bounds"),
+            glob("src/**/*.py"),
+            glob("**/*.py"),
+            glob("*/model.py"),
+            glob("*.toml"),
+            glob(".lisa/**/*.md"),
+            glob("pkg/tests/bounds/*.py"),
+        ];
+        let hits = find_source_accesses(&log, &config(), root, None);
+        assert_eq!(
+            hits,
+            vec![
+                "Grep def",
+                "Grep def in src",
+                "Glob src/**/*.py",
+                "Glob **/*.py",
+                "Glob */model.py"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bash_paths_under_source_are_flagged() {
+        let root = Path::new("/work/proj");
+        let bash = |c: &str| ToolCall::Bash {
+            command: c.to_string(),
+        };
+        let log = vec![
+            bash("cat src/model.py | head -20"),
+            bash("grep -rn 'alpha' src"),
+            bash("cat src/*.py"),
+            bash("cd pkg && cat solver.py"),
+            bash("cd tests/software && cat ../../src/util.py"),
+            bash("echo src/missing.py"),
+            bash("pytest tests/software -q"),
+            bash("ls pkg/tests/bounds"),
+            bash("python -c 'import srcs'"),
+        ];
+        let hits = find_source_accesses(&log, &config(), root, None);
+        assert_eq!(
+            hits,
+            vec![
+                "Bash $ cat src/model.py | head -20",
+                "Bash $ grep -rn 'alpha' src",
+                "Bash $ cat src/*.py",
+                "Bash $ cd pkg && cat solver.py",
+                "Bash $ cd tests/software && cat ../../src/util.py",
+                "Bash $ echo src/missing.py",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bash_with_root_source_needs_existing_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("model.py"), "").unwrap();
+        let mut config = config();
+        config.paths.source = vec![".".to_string()];
+        config.paths.tests_bounds = "tests".to_string();
+        let log = vec![
+            ToolCall::Bash {
+                command: "cat model.py".to_string(),
+            },
+            ToolCall::Bash {
+                command: "pytest -q".to_string(),
+            },
+        ];
+        let hits = find_source_accesses(&log, &config, root, None);
+        assert_eq!(hits, vec!["Bash $ cat model.py"]);
+    }
+
+    #[test]
+    fn test_log_roundtrip_and_verdict() {
+        let dir = tempfile::tempdir().unwrap();
+        let lisa_root = dir.path();
+        let config = config();
+
+        record_run(
+            lisa_root,
+            1,
+            BoundsRun {
+                task: 1,
+                attempt: 1,
+                tool_calls: 12,
+                violations: vec!["Read src/model.py".to_string()],
+                outcome: BoundsOutcome::Rerun,
+            },
+        )
+        .unwrap();
+        record_run(
+            lisa_root,
+            1,
+            BoundsRun {
+                task: 1,
+                attempt: 2,
+                tool_calls: 9,
+                violations: vec![],
+                outcome: BoundsOutcome::Verified,
+            },
+        )
+        .unwrap();
+        let log = load_log(lisa_root, 1).unwrap();
+        assert_eq!(log.runs.len(), 2);
+        assert!(log.verified());
+
+        let review = lisa_root.join("spiral/pass-1/review-package.md");
+        std::fs::write(&review, "# Review\n\nAll good.\n").unwrap();
+        append_to_review_package(lisa_root, 1, &config).unwrap();
+        // Appending twice replaces the section instead of duplicating it
+        append_to_review_package(lisa_root, 1, &config).unwrap();
+        let content = std::fs::read_to_string(&review).unwrap();
+        assert!(content.starts_with("# Review\n\nAll good.\n\n## Bounds Independence"));
+        assert_eq!(content.matches(SECTION_HEADING).count(), 1);
+        assert!(content.contains("**Independence verified:**"));
+
+        record_run(
+            lisa_root,
+            1,
+            BoundsRun {
+                task: 2,
+                attempt: 1,
+                tool_calls: 5,
+                violations: vec!["Grep def in src".to_string()],
+                outcome: BoundsOutcome::Warned,
+            },
+        )
+        .unwrap();
+        let log = load_log(lisa_root, 1).unwrap();
+        assert!(!log.verified());
+        let section = render_section(&log, &config);
+        assert!(section.contains("**Independence NOT verified:**"));
+        assert!(section.contains("- Task 2: `Grep def in src`"));
+        assert!(!section.contains("sandboxed worktree"));
+
+        let mut sandboxed = config.clone();
+        sandboxed.bounds.sandbox = true;
+        let section = render_section(&log, &sandboxed);
+        assert!(section.contains("The sandbox only restricts the file tools"));
+    }
+}
@@ -4,7 +4,9 @@ use std::io::IsTerminal;
 use std::path::Path;
 
 use crate::agent;
-use crate::config::default_config_toml;
+use crate::backend;
+use crate::config::{default_config_toml, ModelSpec};
+use crate::interrupt;
 use crate::prompt;
 use crate::terminal;
 
@@ -43,10 +45,12 @@ pub fn run(project_root: &Path, name: Option<String>, tech: Option<String>) -> R
             println!();
             terminal::println_bold("  Lisa Loop — Initialize Project");
             println!();
-            let name: String = dialoguer::Input::new()
-                .with_prompt("  Assignment name")
-                .default(dir_name)
-                .interact_text()?;
+            let name: String = interrupt::at_prompt(|| {
+                dialoguer::Input::new()
+                    .with_prompt("  Assignment name")
+                    .default(dir_name)
+                    .interact_text()
+            })?;
             name
         } else {
             dir_name
@@ -57,10 +61,12 @@ pub fn run(project_root: &Path, name: Option<String>, tech: Option<String>) -> R
     let tech_pref = if let Some(t) = tech {
         t
     } else if std::io::stdin().is_terminal() {
-        let pref: String = dialoguer::Input::new()
-            .with_prompt("  Technology preference (blank for auto)")
-            .allow_empty(true)
-            .interact_text()?;
+        let pref: String = interrupt::at_prompt(|| {
+            dialoguer::Input::new()
+                .with_prompt("  Technology preference (blank for auto)")
+                .allow_empty(true)
+                .interact_text()
+        })?;
         pref
     } else {
         String::new()
@@ -170,21 +176,30 @@ pub fn run(project_root: &Path, name: Option<String>, tech: Option<String>) -> R
 
     // Phase 2: Run the init agent to examine the codebase and resolve paths
     terminal::log_phase("INIT AGENT — Examining project structure");
+    let config = crate::config::Config::load(project_root)?;
     let init_prompt = prompt::load_prompt(prompt::Phase::Init, &lisa_root);
-    let init_prompt = prompt::render_prompt(
-        &init_prompt,
-        &crate::config::Config::load(project_root)?,
-        None,
-    );
+    let init_prompt = prompt::render_prompt(&init_prompt, &config, None);
+    let model = ModelSpec::parse("opus", &config);
+    let backend = backend::resolve(&model, &config, project_root)?;
+    let err_log = lisa_root.join("last-error.md");
 
     match agent::run_agent(
-        &init_prompt,
-        "opus",
-        "Init Agent",
-        true,
-        Some(&lisa_root.join("last-error.md")),
-        &[],
-        300, // 5 min idle timeout for init agent
+        backend.as_ref(),
+        &agent::AgentRequest {
+            input: &init_prompt,
+            model: &model.model,
+            label: "Init Agent",
+            collapse_output: true,
+            error_log_path: Some(&err_log),
+            idle_timeout_secs: 300, // 5 min idle timeout for init agent
+            transcript_path: None,
+            working_dir: None,
+            permissions: config.permissions_for("init"),
+            limits: config.phase_limits("init"),
+            price: config.price(&model.model),
+            resume_session: None,
+            session: None,
+        },
     ) {
         Ok(_result) => {
             terminal::log_success("Init agent completed — project structure resolved.");
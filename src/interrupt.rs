@@ -0,0 +1,144 @@
+//! Ctrl-C handling: stop what is running, then leave the project ready for
+//! `lisa resume`.
+
+use anyhow::Result;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::terminal;
+
+/// Exit code after an interrupt (128 + SIGINT).
+pub const EXIT_CODE: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Process groups of running agents and commands.
+static GROUPS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Prompts currently waiting for the user.
+static PROMPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returned once Ctrl-C has been pressed, from anything that would move the
+/// spiral forward.
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Interrupted by Ctrl-C")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+pub fn track(pgid: u32) {
+    GROUPS.lock().unwrap().push(pgid);
+}
+
+pub fn untrack(pgid: u32) {
+    GROUPS.lock().unwrap().retain(|&g| g != pgid);
+}
+
+/// Whether Ctrl-C has been pressed.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Run `f`, which waits for the user to type. A Ctrl-C meanwhile exits right
+/// away: a blocking read never sees the flag, and the state `lisa resume`
+/// needs is saved before every prompt.
+pub fn at_prompt<T>(f: impl FnOnce() -> T) -> T {
+    PROMPTS.fetch_add(1, Ordering::SeqCst);
+    let result = f();
+    PROMPTS.fetch_sub(1, Ordering::SeqCst);
+    result
+}
+
+/// Fail with [`Interrupted`] once Ctrl-C has been pressed.
+pub fn check() -> Result<()> {
+    if requested() {
+        return Err(Interrupted.into());
+    }
+    Ok(())
+}
+
+/// Stop running process groups on the first SIGINT/SIGTERM, force-quit on the second.
+/// Agents and commands run in their own process groups, so the terminal's SIGINT
+/// only reaches lisa.
+#[cfg(unix)]
+pub fn install() -> Result<()> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            let groups = GROUPS.lock().unwrap().clone();
+            let at_prompt = PROMPTS.load(Ordering::SeqCst) > 0;
+            if INTERRUPTED.swap(true, Ordering::SeqCst) || at_prompt {
+                for pgid in groups {
+                    unsafe {
+                        libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+                    }
+                }
+                println!();
+                if at_prompt {
+                    terminal::log_warn("Interrupted. Run `lisa resume` to continue.");
+                } else {
+                    terminal::log_error("Force quit. Run `lisa resume` to continue.");
+                }
+                std::process::exit(128 + signal);
+            }
+            println!();
+            terminal::log_warn(
+                "Interrupted — stopping the running agent. Press Ctrl-C again to force quit.",
+            );
+            // Off this thread, so a second Ctrl-C is seen during the grace period
+            std::thread::spawn(move || {
+                for pgid in groups {
+                    let orphans = crate::process::terminate_group(pgid, crate::process::TERM_GRACE);
+                    crate::process::report_orphans("the interrupted process", &orphans);
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn install() -> Result<()> {
+    Ok(())
+}
+
+/// After an interrupted command has unwound: unstage what `git::commit_all`
+/// left half-staged and record the interruption in `last-error.md`. The saved
+/// state is untouched, so `lisa resume` reruns the interrupted phase.
+pub fn finish(lisa_root: &Path) {
+    if crate::git::is_git_repo() {
+        if let Err(e) = crate::git::unstage_pending() {
+            terminal::log_warn(&format!("Failed to reset the git index: {}", e));
+        }
+    }
+
+    let path = lisa_root.join("last-error.md");
+    let state = crate::state::load_state(lisa_root)
+        .map(|s| s.to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    // Keep the agent's own report (tool calls, partial result) if it wrote one
+    let mut content = match std::fs::read_to_string(&path) {
+        Ok(existing) if existing.contains("INTERRUPTED") => existing + "\n",
+        _ => "# Last Error\n\n- **Reason:** INTERRUPTED (Ctrl-C)\n\n".to_string(),
+    };
+    content.push_str(&format!(
+        "## Interrupted\n\n\
+         Lisa was stopped by Ctrl-C at {} in state `{}`. Uncommitted work is \
+         left in place; `lisa resume` restarts this phase.\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        state
+    ));
+    if lisa_root.exists() {
+        let _ = std::fs::write(&path, content);
+    }
+    terminal::log_warn("Interrupted. Run `lisa resume` to continue.");
+}
@@ -0,0 +1,264 @@
+//! Worktrees in which ready tasks are built side by side.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::git;
+use crate::process;
+use crate::sandbox::copy_tree;
+use crate::tasks::{self, Status};
+use crate::terminal;
+
+/// How merging a lane back into the project went.
+#[derive(Debug, PartialEq)]
+pub enum Merge {
+    /// The lane's commits are merged (or it made none).
+    Merged,
+    /// The lane conflicts with work merged before it; its commits are kept on this branch.
+    Conflict(String),
+}
+
+/// A detached worktree of HEAD with its own copy of the process artifacts, so
+/// its task's Bounds→Build pair keeps its own state and plan. Lanes are merged
+/// back one at a time, in plan order; one that conflicts, failed or was
+/// interrupted is kept on a branch instead.
+pub struct Lane {
+    root: PathBuf,
+    pass: u32,
+    task: u32,
+    /// HEAD the worktree was created at.
+    base: Option<String>,
+    /// Set once [`Lane::merge`] has dealt with the lane's commits, so the
+    /// worktree holds nothing that is not kept elsewhere.
+    settled: bool,
+    /// Entries each of the [`logs`] had when the lane was created, by list.
+    logged: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+/// Logs under the lisa root that every lane appends to. Collecting a lane
+/// appends its new entries to the project's log, rather than copying the file
+/// over the entries of the lanes collected before it.
+fn logs(pass: u32) -> Vec<String> {
+    vec![
+        "write-scope.toml".to_string(),
+        format!("spiral/pass-{}/bounds-independence.toml", pass),
+        format!("spiral/pass-{}/checks.toml", pass),
+    ]
+}
+
+fn load_log(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    content
+        .parse()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// The length of each list in `log`.
+fn lengths(log: &toml::Table) -> BTreeMap<String, usize> {
+    log.iter()
+        .filter_map(|(key, value)| value.as_array().map(|a| (key.clone(), a.len())))
+        .collect()
+}
+
+/// `dest` with the entries `src` gained since it had `before` appended to its lists.
+fn append_new_entries(
+    dest: &mut toml::Table,
+    src: toml::Table,
+    before: Option<&BTreeMap<String, usize>>,
+) {
+    for (key, value) in src {
+        let toml::Value::Array(entries) = value else {
+            continue;
+        };
+        let skip = before.and_then(|b| b.get(&key)).copied().unwrap_or(0);
+        if entries.len() <= skip {
+            continue;
+        }
+        let list = dest
+            .entry(key)
+            .or_insert_with(|| toml::Value::Array(Vec::new()));
+        if let toml::Value::Array(list) = list {
+            list.extend(entries.into_iter().skip(skip));
+        }
+    }
+}
+
+impl Lane {
+    /// Create the lane for `pass`/`task` in the system temp directory.
+    pub fn create(config: &Config, project_root: &Path, pass: u32, task: u32) -> Result<Self> {
+        let root = std::env::temp_dir().join(format!(
+            "lisa-lane-{}-pass{}-task{}",
+            std::process::id(),
+            pass,
+            task
+        ));
+        if root.exists() {
+            std::fs::remove_dir_all(&root)
+                .with_context(|| format!("Failed to remove stale lane {}", root.display()))?;
+            git::prune_worktrees()?;
+        }
+        git::add_worktree(&root)?;
+
+        // The lisa root is gitignored; the lane gets its own copy, minus recordings
+        let lisa_root = config.lisa_root(project_root);
+        let lane_lisa = root.join(&config.paths.lisa_root);
+        std::fs::create_dir_all(&lane_lisa)?;
+        for entry in std::fs::read_dir(&lisa_root)?.filter_map(|e| e.ok()) {
+            if entry.file_name() != "recordings" {
+                copy_tree(&entry.path(), &lane_lisa.join(entry.file_name()))?;
+            }
+        }
+
+        let logged = logs(pass)
+            .into_iter()
+            .map(|log| Ok((log.clone(), lengths(&load_log(&lane_lisa.join(&log))?))))
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            base: git::head_commit(),
+            root,
+            pass,
+            task,
+            settled: false,
+            logged,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Bring the artifacts the lane wrote under `spiral/pass-<pass>/`
+    /// (transcripts, check reports, plots) into the project, and append what
+    /// it added to the [`logs`], among them its out-of-scope writes. Their
+    /// paths are relative to the worktree, which has the project's layout.
+    /// Done for every lane, whether or not its work is merged.
+    pub fn collect(&self, config: &Config, project_root: &Path, pass: u32) -> Result<()> {
+        let lisa_root = config.lisa_root(project_root);
+        let lane_lisa = config.lisa_root(&self.root);
+        let mut merged = Vec::new();
+        for log in logs(pass) {
+            let mut project = load_log(&lisa_root.join(&log))?;
+            let lane = load_log(&lane_lisa.join(&log))?;
+            append_new_entries(&mut project, lane, self.logged.get(&log));
+            if !project.is_empty() {
+                merged.push((log, project));
+            }
+        }
+
+        let spiral = format!("spiral/pass-{}", pass);
+        copy_tree(&lane_lisa.join(&spiral), &lisa_root.join(&spiral))?;
+        for (log, content) in merged {
+            let path = lisa_root.join(log);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, toml::to_string_pretty(&content)?)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// The branch the lane's commits are kept on when they are not merged.
+    fn branch(&self) -> String {
+        format!("lisa/pass-{}-task-{}", self.pass, self.task)
+    }
+
+    /// The lane's HEAD, if it has commits of its own.
+    fn commits(&self) -> Option<String> {
+        process::in_dir(&self.root, git::head_commit).filter(|h| Some(h) != self.base.as_ref())
+    }
+
+    /// Merge the lane's commits into the current branch and copy its task
+    /// status into the project's plan. On a conflict the merge is aborted,
+    /// the commits are kept on `lisa/pass-<pass>-task-<task>` and the task
+    /// goes back to TODO.
+    pub fn merge(&mut self, config: &Config, project_root: &Path) -> Result<Merge> {
+        let plan_path = config.lisa_root(project_root).join("methodology/plan.md");
+
+        if let Some(head) = self.commits() {
+            let message = format!("Merge lane: pass {} task {}", self.pass, self.task);
+            if !git::try_merge(&head, &message)? {
+                let branch = self.branch();
+                git::set_branch(&branch, &head)?;
+                tasks::set_task_status(&plan_path, self.task, Status::Todo)?;
+                self.settled = true;
+                return Ok(Merge::Conflict(branch));
+            }
+        }
+        self.settled = true;
+
+        let lane_plan = config.lisa_root(&self.root).join("methodology/plan.md");
+        if let Some(status) = tasks::task_status(&lane_plan, self.task)? {
+            tasks::set_task_status(&plan_path, self.task, status)?;
+        }
+        Ok(Merge::Merged)
+    }
+}
+
+impl Drop for Lane {
+    /// Remove a merged lane. One that failed or was interrupted may hold
+    /// uncommitted edits and commits nothing else points to: its commits get
+    /// a branch and its worktree stays for the human to look at.
+    fn drop(&mut self) {
+        if !self.settled {
+            if let Some(head) = self.commits() {
+                let branch = self.branch();
+                match git::set_branch(&branch, &head) {
+                    Ok(()) => terminal::log_warn(&format!(
+                        "Task {} was not merged — its commits are kept on branch '{}'.",
+                        self.task, branch
+                    )),
+                    Err(e) => terminal::log_warn(&format!(
+                        "Failed to keep the commits of task {} on a branch: {}",
+                        self.task, e
+                    )),
+                }
+            }
+            terminal::log_warn(&format!(
+                "Worktree of task {} kept at {} (remove it with `git worktree remove --force`).",
+                self.task,
+                self.root.display()
+            ));
+            return;
+        }
+        if let Err(e) = std::fs::remove_dir_all(&self.root) {
+            terminal::log_warn(&format!(
+                "Failed to remove lane {}: {}",
+                self.root.display(),
+                e
+            ));
+        }
+        let _ = git::prune_worktrees();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lane_logs_are_appended_not_copied() {
+        let log = |text: &str| -> toml::Table { text.parse().unwrap() };
+        let at_creation = log("[[runs]]\ntask = 1\n");
+        // Another lane was collected first; this one added task 3
+        let mut project = log("[[runs]]\ntask = 1\n\n[[runs]]\ntask = 2\n");
+        let lane =
+            log("[[runs]]\ntask = 1\n\n[[runs]]\ntask = 3\n\n[[violations]]\nlabel = \"x\"\n");
+        append_new_entries(&mut project, lane, Some(&lengths(&at_creation)));
+        let tasks: Vec<i64> = project["runs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["task"].as_integer().unwrap())
+            .collect();
+        assert_eq!(tasks, vec![1, 2, 3]);
+        assert_eq!(project["violations"].as_array().unwrap().len(), 1);
+    }
+}
@@ -1,16 +1,31 @@
 mod agent;
+mod backend;
+mod checks;
 mod cli;
 mod config;
+mod failure;
 
 mod git;
+mod graph;
+mod independence;
 mod init;
+mod interrupt;
+mod lane;
 mod orchestrator;
+mod process;
 mod prompt;
+mod recording;
 mod review;
+mod sandbox;
+mod snapshot;
 mod state;
+mod tamper;
 mod tasks;
 mod terminal;
+mod testresults;
+mod transcript;
 mod usage;
+mod writescope;
 
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -28,37 +43,66 @@ fn load_config() -> Result<config::Config> {
 
 fn main() -> Result<()> {
     let cli = cli::Cli::parse();
+    interrupt::install()?;
+
+    let result = dispatch(cli.command);
+    if interrupt::requested() {
+        let root = project_root()?;
+        let lisa_root = match load_config() {
+            Ok(config) => config.lisa_root(&root),
+            Err(_) => root.join(".lisa"),
+        };
+        interrupt::finish(&lisa_root);
+        std::process::exit(interrupt::EXIT_CODE);
+    }
+    result
+}
 
-    match cli.command {
+fn dispatch(command: cli::Commands) -> Result<()> {
+    match command {
         cli::Commands::Init { name, tech } => init::scaffold::run(&project_root()?, name, tech),
         cli::Commands::Run {
             max_passes,
             no_pause,
             verbose,
             follow_up,
+            record,
+            replay,
         } => {
             let mut config = load_config()?;
             if verbose {
                 config.terminal.collapse_output = false;
             }
-            orchestrator::run(
-                &config,
-                &project_root()?,
-                max_passes,
-                no_pause,
-                follow_up.as_deref(),
-            )
+            apply_recording_flags(&mut config, record, replay);
+            let project_root = project_root()?;
+            backend::in_run(|| {
+                orchestrator::run(
+                    &config,
+                    &project_root,
+                    max_passes,
+                    no_pause,
+                    follow_up.as_deref(),
+                )
+            })
         }
-        cli::Commands::Resume { no_pause, verbose } => {
+        cli::Commands::Resume {
+            no_pause,
+            verbose,
+            record,
+            replay,
+        } => {
             let mut config = load_config()?;
             if verbose {
                 config.terminal.collapse_output = false;
             }
-            orchestrator::resume(&config, &project_root()?, no_pause)
+            apply_recording_flags(&mut config, record, replay);
+            let project_root = project_root()?;
+            backend::in_run(|| orchestrator::resume(&config, &project_root, no_pause))
         }
         cli::Commands::Status => cmd_status(),
         cli::Commands::Doctor => cmd_doctor(),
         cli::Commands::EjectPrompts => cmd_eject_prompts(),
+        cli::Commands::Tasks { command } => cmd_tasks(command),
         cli::Commands::Rollback { pass, force } => {
             let config = load_config()?;
             orchestrator::rollback(&config, &project_root()?, pass, force)
@@ -66,6 +110,18 @@ fn main() -> Result<()> {
     }
 }
 
+/// `--record` / `--replay` override the `[agent]` settings in lisa.toml.
+fn apply_recording_flags(config: &mut config::Config, record: bool, replay: Option<String>) {
+    if record {
+        config.agent.record = true;
+        config.agent.replay.clear();
+    }
+    if let Some(dir) = replay {
+        config.agent.replay = dir;
+        config.agent.record = false;
+    }
+}
+
 fn cmd_status() -> Result<()> {
     let root = project_root()?;
     let lisa_root = match load_config() {
@@ -156,6 +212,13 @@ fn cmd_status() -> Result<()> {
                     ledger.total_input_tokens(),
                     ledger.total_output_tokens(),
                 );
+                let downgrades = ledger.downgrades();
+                if !downgrades.is_empty() {
+                    println!("  Budget downgrades:");
+                    for ((phase, from, to), count) in downgrades {
+                        println!("    {}: {} → {} ({} invocations)", phase, from, to, count);
+                    }
+                }
             }
 
             // Show rollback points
@@ -213,8 +276,12 @@ fn cmd_status() -> Result<()> {
                                 .unwrap_or_else(|| "-".to_string());
                         let answer_trunc = truncate_str(&answer, 30);
 
-                        let bounds =
-                            extract_bounds_summary(&content).unwrap_or_else(|| "-".to_string());
+                        // Prefer lisa's own measurement over the agent's summary
+                        let bounds = testresults::load(&lisa_root, *pass_num)
+                            .and_then(|r| r.bounds)
+                            .map(|b| b.ratio())
+                            .or_else(|| extract_bounds_summary(&content))
+                            .unwrap_or_else(|| "-".to_string());
                         let bounds_trunc = truncate_str(&bounds, 8);
 
                         let sanity =
@@ -349,9 +416,27 @@ fn cmd_doctor() -> Result<()> {
 
         // Check config
         match load_config() {
-            Ok(_) => {
+            Ok(config) => {
                 terminal::print_colored("  ✓", Color::Green);
                 println!(" lisa.toml is valid");
+
+                // Check custom agent backends are installed
+                for (name, backend) in &config.agent.backends {
+                    let found = std::process::Command::new(&backend.command)
+                        .arg("--version")
+                        .output()
+                        .is_ok();
+                    if found {
+                        terminal::print_colored("  ✓", Color::Green);
+                        println!(" Agent backend '{}' found ({})", name, backend.command);
+                    } else {
+                        terminal::print_colored("  ✗", Color::Red);
+                        println!(
+                            " Agent backend '{}' not found: '{}' is not on PATH",
+                            name, backend.command
+                        );
+                    }
+                }
             }
             Err(e) => {
                 terminal::print_colored("  ✗", Color::Red);
@@ -377,6 +462,226 @@ fn cmd_doctor() -> Result<()> {
     Ok(())
 }
 
+fn cmd_tasks(command: cli::TasksCommand) -> Result<()> {
+    use cli::{GraphFormat, TasksCommand};
+    use tasks::Status;
+
+    let root = project_root()?;
+    let lisa_root = match load_config() {
+        Ok(config) => config.lisa_root(&root),
+        Err(_) => root.join(".lisa"),
+    };
+    let plan_path = lisa_root.join("methodology/plan.md");
+    if !plan_path.exists() {
+        anyhow::bail!(
+            "No plan at {}. Run `lisa run` to scope the project first.",
+            plan_path.display()
+        );
+    }
+    let mut plan = tasks::Plan::load(&plan_path)?;
+
+    let (number, status) = match command {
+        TasksCommand::List { pass, json } => {
+            let selected: Vec<&tasks::Task> = plan
+                .tasks
+                .iter()
+                .filter(|t| pass.is_none_or(|p| t.pass == p))
+                .collect();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&selected)?);
+            } else {
+                print_task_table(&selected);
+            }
+            return Ok(());
+        }
+        TasksCommand::Show { task, json } => {
+            let task = find_task(&plan, task)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(task)?);
+            } else {
+                print_task(&plan, task);
+            }
+            return Ok(());
+        }
+        TasksCommand::Next { pass, json } => {
+            let pass = pass.unwrap_or_else(|| {
+                state::load_state(&lisa_root)
+                    .ok()
+                    .and_then(|s| s.pass())
+                    .unwrap_or(1)
+                    .max(1)
+            });
+            let next = plan.ready(pass).first().copied();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&next)?);
+            } else if let Some(task) = next {
+                print_task(&plan, task);
+            } else {
+                println!(
+                    "No task is ready in pass {} (all done, blocked, or waiting on dependencies).",
+                    pass
+                );
+            }
+            return Ok(());
+        }
+        TasksCommand::Graph { format } => {
+            match format {
+                GraphFormat::Dot => print!("{}", graph::render_dot(&plan)),
+                GraphFormat::Mermaid => print!("{}", graph::render_mermaid(&plan)),
+            }
+            return Ok(());
+        }
+        TasksCommand::SetStatus { task, status } => match Status::parse(&status) {
+            Status::Unknown(_) => anyhow::bail!(
+                "Unknown status '{}' — expected TODO, IN_PROGRESS, DONE or BLOCKED",
+                status
+            ),
+            status => (task, Some(status)),
+        },
+        TasksCommand::Block { task } => (task, Some(Status::Blocked)),
+        TasksCommand::Unblock { task } => {
+            let current = &find_task(&plan, task)?.status;
+            if *current != Status::Blocked {
+                anyhow::bail!("Task {} is {}, not BLOCKED", task, current);
+            }
+            (task, Some(Status::Todo))
+        }
+        TasksCommand::AddDep { task, dependency } => {
+            find_task(&plan, dependency)?;
+            if task == dependency {
+                anyhow::bail!("Task {} cannot depend on itself", task);
+            }
+            let cycles = plan.cycles();
+            let deps = &mut find_task_mut(&mut plan, task)?.dependencies;
+            if deps.contains(&dependency) {
+                anyhow::bail!("Task {} already depends on Task {}", task, dependency);
+            }
+            deps.push(dependency);
+            if let Some(cycle) = plan.cycles().into_iter().find(|c| !cycles.contains(c)) {
+                anyhow::bail!("Adding the dependency would create a cycle: {}", cycle);
+            }
+            (task, None)
+        }
+        TasksCommand::RmDep { task, dependency } => {
+            let deps = &mut find_task_mut(&mut plan, task)?.dependencies;
+            let Some(i) = deps.iter().position(|d| *d == dependency) else {
+                anyhow::bail!("Task {} does not depend on Task {}", task, dependency);
+            };
+            deps.remove(i);
+            (task, None)
+        }
+    };
+
+    let task = find_task_mut(&mut plan, number)?;
+    if let Some(status) = status {
+        task.status = status;
+    }
+    let summary = format!(
+        "Task {}: {} — {}, dependencies: {}",
+        task.number,
+        task.name,
+        task.status,
+        format_dependencies(&task.dependencies)
+    );
+    plan.save(&plan_path)?;
+    terminal::log_success(&summary);
+    Ok(())
+}
+
+fn find_task(plan: &tasks::Plan, number: u32) -> Result<&tasks::Task> {
+    plan.task(number)
+        .with_context(|| format!("No Task {} in plan.md", number))
+}
+
+fn find_task_mut(plan: &mut tasks::Plan, number: u32) -> Result<&mut tasks::Task> {
+    plan.task_mut(number)
+        .with_context(|| format!("No Task {} in plan.md", number))
+}
+
+fn format_dependencies(deps: &[u32]) -> String {
+    if deps.is_empty() {
+        return "None".to_string();
+    }
+    deps.iter()
+        .map(|d| format!("Task {}", d))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn status_color(status: &tasks::Status) -> Color {
+    match status {
+        tasks::Status::Todo => Color::White,
+        tasks::Status::InProgress => Color::Cyan,
+        tasks::Status::Done => Color::Green,
+        tasks::Status::Blocked => Color::Red,
+        tasks::Status::Unknown(_) => Color::Yellow,
+    }
+}
+
+fn print_task_table(tasks: &[&tasks::Task]) {
+    println!();
+    println!(
+        "  {:>4}  {:<11}  {:>4}  {:<30}  Dependencies",
+        "Task", "Status", "Pass", "Name"
+    );
+    println!(
+        "  {:>4}  {:<11}  {:>4}  {:<30}  ------------",
+        "----", "-----------", "----", "------------------------------"
+    );
+    for task in tasks {
+        print!("  {:>4}  ", task.number);
+        terminal::print_colored(
+            &format!("{:<11}", task.status.as_str()),
+            status_color(&task.status),
+        );
+        println!(
+            "  {:>4}  {:<30}  {}",
+            task.pass,
+            truncate_str(&task.name, 30),
+            format_dependencies(&task.dependencies)
+        );
+    }
+    println!();
+}
+
+fn print_task(plan: &tasks::Plan, task: &tasks::Task) {
+    println!();
+    terminal::println_bold(&format!("  Task {}: {}", task.number, task.name));
+    print!("    Status:       ");
+    terminal::println_colored(task.status.as_str(), status_color(&task.status));
+    println!("    Pass:         {}", task.pass);
+    for (label, value) in [
+        ("Methodology:  ", &task.methodology),
+        ("Validation:   ", &task.validation),
+        ("Bounds:       ", &task.bounding_checks),
+    ] {
+        if !value.is_empty() {
+            println!("    {}{}", label, value);
+        }
+    }
+    let deps: Vec<String> = task
+        .dependencies
+        .iter()
+        .map(|d| match plan.task(*d) {
+            Some(dep) => format!("Task {} ({})", d, dep.status),
+            None => format!("Task {} (missing)", d),
+        })
+        .collect();
+    if deps.is_empty() {
+        println!("    Dependencies: None");
+    } else {
+        println!("    Dependencies: {}", deps.join(", "));
+    }
+    if !task.checklist.is_empty() {
+        println!("    Checklist:");
+        for item in &task.checklist {
+            let mark = if item.done { "x" } else { " " };
+            println!("      [{}] {}", mark, item.text);
+        }
+    }
+    println!();
+}
+
 fn cmd_eject_prompts() -> Result<()> {
     let root = project_root()?;
     let lisa_root = match config::Config::load(&root) {
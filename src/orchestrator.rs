@@ -2,19 +2,34 @@ use anyhow::Result;
 use crossterm::style::Color;
 use std::io::IsTerminal;
 use std::path::Path;
+use std::sync::mpsc;
 
 use crate::agent::{self, AgentResult};
-use crate::config::Config;
+use crate::backend;
+use crate::checks;
+use crate::config::{Config, ModelSpec, SourceAccessPolicy, TamperPolicy};
 
+use crate::failure;
 use crate::git;
+use crate::graph;
+use crate::independence::{self, BoundsOutcome};
+use crate::interrupt;
+use crate::lane::{self, Lane};
+use crate::process;
 use crate::prompt::{self, Phase};
 use crate::review::{
     self, BlockDecision, MethodologyDecision, RefineDecision, ReviewDecision, ScopeDecision,
+    TamperDecision,
 };
+use crate::sandbox::Sandbox;
+use crate::snapshot::Snapshot;
 use crate::state::{self, PassPhase, SpiralState};
+use crate::tamper::{self, BoundsGuard};
 use crate::tasks;
 use crate::terminal;
+use crate::testresults;
 use crate::usage;
+use crate::writescope;
 
 /// Run the full spiral: scope if needed, then iterate passes.
 /// If the spiral is already complete and `follow_up` is provided (or prompted interactively),
@@ -41,9 +56,11 @@ pub fn run(
         } else if std::io::stdin().is_terminal() {
             terminal::log_info(&format!("Spiral complete at pass {}.", final_pass));
             println!();
-            let q: String = dialoguer::Input::new()
-                .with_prompt("  Follow-up question (or Ctrl+C to exit)")
-                .interact_text()?;
+            let q: String = interrupt::at_prompt(|| {
+                dialoguer::Input::new()
+                    .with_prompt("  Follow-up question (or Ctrl+C to exit)")
+                    .interact_text()
+            })?;
             q
         } else {
             terminal::log_info(&format!(
@@ -73,6 +90,8 @@ pub fn run(
     // After scope, paths must be resolved (by init agent or scope agent)
     let config = reload_config_if_needed(config, project_root)?;
     config.validate_paths()?;
+    // Scope may have filled in the commands
+    checks::run_setup(&config);
 
     run_pass_range(&config, project_root, 1, max)
 }
@@ -105,6 +124,23 @@ pub fn resume(config: &Config, project_root: &Path, no_pause: bool) -> Result<()
         let _ = std::fs::remove_file(&error_path);
     }
 
+    // Scope states hand over to run(), which runs setup; a complete spiral builds nothing
+    let runs_through_run = matches!(
+        state,
+        SpiralState::NotStarted
+            | SpiralState::Scoping
+            | SpiralState::ScopeResearch
+            | SpiralState::ScopeResearchReview
+            | SpiralState::ScopeValidation
+            | SpiralState::ScopePlanning
+            | SpiralState::ScopeReview
+            | SpiralState::ScopeComplete
+            | SpiralState::Complete { .. }
+    );
+    if !runs_through_run {
+        checks::run_setup(config);
+    }
+
     match state {
         SpiralState::NotStarted => {
             terminal::log_info("No previous run found. Starting fresh.");
@@ -158,23 +194,14 @@ pub fn resume(config: &Config, project_root: &Path, no_pause: bool) -> Result<()
             ));
 
             terminal::log_phase(&format!("PASS {} — REFINE PLAN", pass));
-            let input = prompt::build_agent_input(
-                Phase::RefinePlan,
+            run_plan_agent(
                 config,
-                &lisa_root,
-                pass,
+                project_root,
+                Phase::RefinePlan,
                 Some(&extra),
-            );
-            let model = Phase::RefinePlan.model_key(config);
-            run_agent_with_tracking(
-                config,
-                &lisa_root,
-                &input,
-                &model,
-                &format!("Refine plan: pass {}", pass),
-                "refine_plan",
-                pass,
+                &Invocation::new(&format!("Refine plan: pass {}", pass), "refine_plan", pass),
             )?;
+            embed_task_graph(&lisa_root, pass)?;
             git::commit_all(&format!("refine: pass {} — plan updated", pass), config)?;
             state::save_state(&lisa_root, &SpiralState::RefineComplete { pass })?;
 
@@ -366,11 +393,20 @@ fn resume_from_phase(
             run_audit(config, project_root, pass)?;
             git::push(config)?;
         }
-        PassPhase::Build { iteration, .. } => {
+        PassPhase::Build {
+            iteration, lanes, ..
+        } => {
             terminal::log_info(&format!(
                 "Resuming: build phase at pass {} (iteration {}).",
                 pass, iteration
             ));
+            if !lanes.is_empty() {
+                let tasks: Vec<String> = lanes.iter().map(u32::to_string).collect();
+                terminal::log_info(&format!(
+                    "Tasks {} were being built in parallel; unfinished ones are picked up again.",
+                    tasks.join(", ")
+                ));
+            }
             if !run_build_loop(config, project_root, pass, *iteration)? {
                 return Ok(());
             }
@@ -476,6 +512,8 @@ fn reload_config_if_needed(config: Config, project_root: &Path) -> Result<Config
             // Preserve runtime overrides (e.g. --no-pause)
             fresh.review.pause = config.review.pause;
             fresh.terminal.collapse_output = config.terminal.collapse_output;
+            fresh.agent.record = config.agent.record;
+            fresh.agent.replay = config.agent.replay;
             Ok(fresh)
         }
         Err(_) => Ok(config),
@@ -487,69 +525,353 @@ fn error_log(lisa_root: &Path) -> std::path::PathBuf {
     lisa_root.join("last-error.md")
 }
 
-/// Wrapper: run agent, record usage, check budget.
-fn run_agent_with_tracking(
+/// Identifies one agent invocation for usage tracking and transcripts.
+struct Invocation<'a> {
+    label: &'a str,
+    phase: &'a str,
+    pass: u32,
+    task: Option<u32>,
+    iteration: Option<u32>,
+    /// Where the agent runs, if not the project root.
+    working_dir: Option<&'a Path>,
+}
+
+impl<'a> Invocation<'a> {
+    fn new(label: &'a str, phase: &'a str, pass: u32) -> Self {
+        Self {
+            label,
+            phase,
+            pass,
+            task: None,
+            iteration: None,
+            working_dir: None,
+        }
+    }
+
+    fn task(mut self, task: u32) -> Self {
+        self.task = Some(task);
+        self
+    }
+
+    fn iteration(mut self, iteration: u32) -> Self {
+        self.iteration = Some(iteration);
+        self
+    }
+
+    fn working_dir(mut self, dir: Option<&'a Path>) -> Self {
+        self.working_dir = dir;
+        self
+    }
+
+    /// `<phase>[-<task>[-<iter>]]`
+    fn stem(&self) -> String {
+        let mut stem = self.phase.replace('_', "-");
+        if let Some(task) = self.task {
+            stem.push_str(&format!("-{}", task));
+        }
+        if let Some(iter) = self.iteration {
+            stem.push_str(&format!("-{}", iter));
+        }
+        stem
+    }
+
+    /// Identifies the invocation across runs of lisa: `pass-N/<stem>`.
+    fn key(&self) -> String {
+        format!("pass-{}/{}", self.pass, self.stem())
+    }
+
+    /// Transcript path without extension:
+    /// `spiral/pass-N/transcripts/<phase>[-<task>[-<iter>]]`, with a numeric
+    /// suffix when the same invocation has already been transcribed (retries, resumes).
+    fn transcript_base(&self, lisa_root: &Path) -> std::path::PathBuf {
+        let dir = lisa_root.join(format!("spiral/pass-{}/transcripts", self.pass));
+        let stem = self.stem();
+        let mut base = dir.join(&stem);
+        let mut n = 1;
+        while base.with_extension("jsonl").exists() {
+            n += 1;
+            base = dir.join(format!("{}-r{}", stem, n));
+        }
+        base
+    }
+}
+
+/// The cheaper `[limits.downgrade]` models for `phase`, once spending has
+/// crossed the budget warning threshold.
+fn budget_downgrade(
     config: &Config,
     lisa_root: &Path,
-    input: &str,
-    model: &str,
-    label: &str,
     phase: &str,
-    pass: u32,
+) -> Result<Option<Vec<ModelSpec>>> {
+    let Some(chain) = config.limits.downgrade.get(phase) else {
+        return Ok(None);
+    };
+    let spent = usage::load_usage(lisa_root)?.total_cost();
+    let status = usage::check_budget(
+        spent,
+        config.limits.budget_usd,
+        config.limits.budget_warn_pct,
+    );
+    if status == usage::BudgetStatus::Ok {
+        return Ok(None);
+    }
+    Ok(Some(
+        chain
+            .models()
+            .iter()
+            .map(|m| ModelSpec::parse(m, config))
+            .collect(),
+    ))
+}
+
+/// What a continued agent session is told instead of the full prompt.
+const RESUME_NOTE: &str = "You were interrupted before finishing this task. Your changes so far \
+are still in the working tree. Check where you left off and continue the same task from there; \
+do not start over.\n";
+
+/// Wrapper: run agent, record usage, check budget. `models` is the phase's
+/// `[models]` list: later entries are tried when one is unavailable or rate-limited.
+fn run_agent_with_tracking(
+    config: &Config,
+    project_root: &Path,
+    input: &str,
+    models: &[ModelSpec],
+    invocation: &Invocation,
 ) -> Result<AgentResult> {
-    let err_log = error_log(lisa_root);
-    let max_retries = config.limits.max_agent_retries;
+    let Invocation {
+        label,
+        phase,
+        pass,
+        working_dir,
+        ..
+    } = *invocation;
+    let lisa_root = config.lisa_root(project_root);
+    let err_log = error_log(&lisa_root);
+
+    // Files lisa itself writes during the invocation are not the agent's
+    let transcripts = lisa_root.join(format!("spiral/pass-{}/transcripts", pass));
+    let recordings = lisa_root.join("recordings");
+    let state_file = lisa_root.join("state.toml");
+    let snapshot = || {
+        Snapshot::take(
+            project_root,
+            &[&lisa_root],
+            &[&transcripts, &recordings, &err_log, &state_file],
+        )
+    };
+    let write_scope = match writescope::allowed_paths(phase, pass, config)
+        .filter(|_| config.write_scope.enabled)
+    {
+        Some(allowed) => {
+            let before = snapshot();
+            let backup = writescope::backup(config, project_root, &before, &allowed)?;
+            Some((allowed, before, git::head_commit(), backup))
+        }
+        None => None,
+    };
 
-    let result = {
-        let mut last_err = None;
-        let mut attempt = 0;
+    let configured = models[0].to_string();
+    let downgrade = budget_downgrade(config, &lisa_root, phase)?;
+    if let Some(cheaper) = &downgrade {
+        terminal::log_warn(&format!(
+            "Budget warning threshold crossed — running '{}' on '{}' instead of '{}'.",
+            label, cheaper[0], configured
+        ));
+    }
+    let models = downgrade.as_deref().unwrap_or(models);
+    let names: Vec<String> = models.iter().map(ToString::to_string).collect();
+    let ran_on = |i: usize| usage::ModelRun {
+        model: &names[i],
+        fallback_from: (i > 0).then(|| names[0].as_str()),
+        downgraded_from: downgrade.is_some().then_some(configured.as_str()),
+    };
+    // Index of the model running; those before it were unavailable or rate-limited
+    let mut current = 0;
+
+    // A previous run of this invocation stopped early: offer to continue its
+    // session, on the model it ran on
+    let key = invocation.key();
+    let mut resume = None;
+    let saved = state::load_session(&lisa_root)?.filter(|s| s.invocation == key);
+    if let Some(saved) = saved {
+        if let Some(i) = names.iter().position(|n| *n == saved.model) {
+            if backend::resolve(&models[i], config, project_root)?.supports_resume()
+                && review::session_gate(config, label)? == review::SessionDecision::Continue
+            {
+                current = i;
+                resume = Some(saved.id);
+            }
+        }
+    }
+    let mut backend = backend::resolve(&models[current], config, project_root)?;
+
+    // Every way out of the retry loop, success or not, goes past the write-scope check
+    let outcome = (|| -> Result<AgentResult> {
+        let mut retries = failure::RetryState::default();
+        // Set once a limit was reached and the human (or the default) chose a narrower retry
+        let mut narrowed: Option<String> = None;
 
         loop {
-            match agent::run_agent(
-                input,
-                model,
+            let transcript = invocation.transcript_base(&lisa_root);
+            let session = agent::SessionCapture::default();
+            let request = agent::AgentRequest {
+                input: match resume {
+                    Some(_) => RESUME_NOTE,
+                    None => narrowed.as_deref().unwrap_or(input),
+                },
+                model: &models[current].model,
                 label,
-                config.terminal.collapse_output,
-                Some(&err_log),
-                &config.agent.extra_args,
-                config.limits.idle_timeout_secs,
-            ) {
-                Ok(r) => break r,
+                collapse_output: config.terminal.collapse_output,
+                error_log_path: Some(&err_log),
+                idle_timeout_secs: config.limits.idle_timeout_secs,
+                transcript_path: Some(&transcript),
+                working_dir,
+                permissions: config.permissions_for(phase),
+                limits: config.phase_limits(phase),
+                price: config.price(&models[current].model),
+                resume_session: resume.as_deref(),
+                session: Some(&session),
+            };
+            let outcome = agent::run_agent(backend.as_ref(), &request);
+
+            // Keep the session of an unfinished run for a retry or `lisa resume`
+            let unfinished = match &outcome {
+                Ok(_) => None,
+                Err(_) => session.id().or_else(|| resume.clone()),
+            };
+            state::save_session(
+                &lisa_root,
+                unfinished.clone().map(|id| state::AgentSession {
+                    invocation: key.clone(),
+                    model: names[current].clone(),
+                    id,
+                }),
+            )?;
+
+            match outcome {
+                Ok(r) => return Ok(r),
                 Err(e) => {
-                    // Only retry on idle timeouts
-                    if e.downcast_ref::<agent::AgentError>()
-                        .is_some_and(|ae| matches!(ae, agent::AgentError::IdleTimeout { .. }))
-                        && attempt < max_retries
+                    // The session may be gone (expired, other machine): start over
+                    if resume.is_some()
+                        && matches!(
+                            e.downcast_ref::<agent::AgentError>(),
+                            Some(agent::AgentError::Failed {
+                                class: failure::FailureClass::Fatal,
+                                ..
+                            })
+                        )
                     {
-                        attempt += 1;
                         terminal::log_warn(&format!(
-                            "Idle timeout — retrying agent ({}/{})...",
-                            attempt, max_retries
+                            "Could not continue the session of '{}' ({}) — starting a fresh run.",
+                            label, e
                         ));
-                        std::thread::sleep(std::time::Duration::from_secs(30));
-                        last_err = Some(e);
+                        resume = None;
+                        continue;
+                    }
+                    if failure::falls_back(&e) && current + 1 < models.len() {
+                        terminal::log_warn(&format!(
+                            "Model '{}' is unavailable or rate-limited — falling back to '{}' for '{}'.",
+                            names[current],
+                            names[current + 1],
+                            label
+                        ));
+                        current += 1;
+                        backend = backend::resolve(&models[current], config, project_root)?;
+                        retries = failure::RetryState::default();
+                        resume = None;
+                        continue;
+                    }
+                    if let Some(agent::AgentError::LimitExceeded {
+                        breach,
+                        elapsed_secs,
+                        usage: spent,
+                        ..
+                    }) = e.downcast_ref::<agent::AgentError>()
+                    {
+                        // The stopped agent's spend still counts toward the budget
+                        usage::record_invocation(
+                            &lisa_root,
+                            phase,
+                            pass,
+                            ran_on(current),
+                            spent,
+                            *elapsed_secs,
+                            None,
+                        )?;
+                        let retried = narrowed.is_some();
+                        match review::limit_gate(config, label, breach, retried)? {
+                            review::LimitDecision::RetryNarrower => {
+                                terminal::log_warn(&format!(
+                                    "Retrying '{}' with a narrower scope...",
+                                    label
+                                ));
+                                narrowed = Some(format!(
+                                    "{}\n\n## Invocation Limit\n\n\
+                                     A previous attempt at this phase was stopped after {}s: its {} \
+                                     was reached. Its changes are still in the working tree. Narrow \
+                                     your scope: finish the smallest useful part of the work, write \
+                                     down what remains, and end well within the limit.\n",
+                                    input, elapsed_secs, breach
+                                ));
+                                resume = None;
+                                continue;
+                            }
+                            review::LimitDecision::Stop => return Err(e),
+                        }
+                    }
+                    // Retry per failure class: backoff, wait for a usage reset, or surface
+                    if let Some((delay, reason)) = retries.next(&e, config, chrono::Local::now()) {
+                        terminal::log_warn(&format!("{} ({})...", reason, e));
+                        failure::wait(delay)?;
+                        resume = unfinished.filter(|_| backend.supports_resume());
                         continue;
                     }
-                    // Non-timeout error or retries exhausted
-                    if attempt > 0 {
+                    if retries.attempts > 0 {
                         terminal::log_error(&format!(
                             "Agent failed after {} retries. Surfacing error.",
-                            attempt
+                            retries.attempts
                         ));
                     }
-                    return Err(last_err.unwrap_or(e));
+                    return Err(e);
                 }
             }
         }
+    })();
+
+    // A failed, stopped or interrupted agent may have written out of scope too;
+    // `lisa resume` would snapshot its writes as the starting point
+    let flagged = match write_scope {
+        Some((allowed, before, base, backup)) => {
+            let paths = writescope::out_of_scope(&before.diff(&snapshot()), &allowed);
+            if paths.is_empty() {
+                Ok(())
+            } else {
+                writescope::Violation::capture(
+                    label,
+                    phase,
+                    pass,
+                    base,
+                    paths,
+                    &backup,
+                    config,
+                    project_root,
+                )
+                .and_then(|violation| writescope::flag(config, project_root, violation))
+            }
+        }
+        None => Ok(()),
     };
+    let result = outcome?;
+    flagged?;
 
     let cumulative = usage::record_invocation(
-        lisa_root,
+        &lisa_root,
         phase,
         pass,
-        model,
+        ran_on(current),
         &result.usage,
         result.elapsed_secs,
+        result.transcript.as_deref(),
     )?;
 
     if result.usage.cost_usd > 0.0 {
@@ -662,15 +984,13 @@ fn run_scope(config: &Config, project_root: &Path) -> Result<()> {
                     0,
                     Some(refine_ctx),
                 );
-                let model = Phase::Research.model_key(config);
+                let models = Phase::Research.models(config);
                 run_agent_with_tracking(
                     config,
-                    &lisa_root,
+                    project_root,
                     &input,
-                    &model,
-                    "Research: refinement",
-                    "research",
-                    0,
+                    &models,
+                    &Invocation::new("Research: refinement", "research", 0),
                 )?;
                 git::commit_all("scope: research refined after methodology feedback", config)?;
                 terminal::log_info("Methodology refined. Reviewing again...");
@@ -693,7 +1013,7 @@ fn run_scope(config: &Config, project_root: &Path) -> Result<()> {
                 print!("  Press Enter when you are done editing...");
                 let _ = std::io::Write::flush(&mut std::io::stdout());
                 let mut _buf = String::new();
-                let _ = std::io::stdin().read_line(&mut _buf);
+                let _ = interrupt::at_prompt(|| std::io::stdin().read_line(&mut _buf));
 
                 terminal::log_success(
                     "Methodology approved (manually edited). Proceeding to validation design.",
@@ -749,15 +1069,13 @@ fn run_scope(config: &Config, project_root: &Path) -> Result<()> {
                     0,
                     Some(refine_ctx),
                 );
-                let model = Phase::Research.model_key(config);
+                let models = Phase::Research.models(config);
                 run_agent_with_tracking(
                     config,
-                    &lisa_root,
+                    project_root,
                     &input,
-                    &model,
-                    "Research: scope refinement",
-                    "research",
-                    0,
+                    &models,
+                    &Invocation::new("Research: scope refinement", "research", 0),
                 )?;
                 git::commit_all("scope: research refined after scope feedback", config)?;
 
@@ -791,7 +1109,7 @@ fn run_scope(config: &Config, project_root: &Path) -> Result<()> {
                 print!("  Press Enter when you are done editing...");
                 let _ = std::io::Write::flush(&mut std::io::stdout());
                 let mut _buf = String::new();
-                let _ = std::io::stdin().read_line(&mut _buf);
+                let _ = interrupt::at_prompt(|| std::io::stdin().read_line(&mut _buf));
 
                 display_scope_edit_summary(&lisa_root);
 
@@ -848,10 +1166,14 @@ fn run_research(config: &Config, project_root: &Path) -> Result<()> {
         0,
         extra_context.as_deref(),
     );
-    let model = Phase::Research.model_key(config);
+    let models = Phase::Research.models(config);
 
     run_agent_with_tracking(
-        config, &lisa_root, &input, &model, "Research", "research", 0,
+        config,
+        project_root,
+        &input,
+        &models,
+        &Invocation::new("Research", "research", 0),
     )?;
     git::commit_all(
         "scope: research — methodology and criteria established",
@@ -867,16 +1189,14 @@ fn run_validation_design(config: &Config, project_root: &Path) -> Result<()> {
     state::save_state(&lisa_root, &SpiralState::ScopeValidation)?;
 
     let input = prompt::build_agent_input(Phase::ValidationDesign, config, &lisa_root, 0, None);
-    let model = Phase::ValidationDesign.model_key(config);
+    let models = Phase::ValidationDesign.models(config);
 
     run_agent_with_tracking(
         config,
-        &lisa_root,
+        project_root,
         &input,
-        &model,
-        "Validation Design",
-        "validation_design",
-        0,
+        &models,
+        &Invocation::new("Validation Design", "validation_design", 0),
     )?;
     git::commit_all(
         "scope: validation design — checks and cases defined",
@@ -891,16 +1211,107 @@ fn run_planning(config: &Config, project_root: &Path) -> Result<()> {
 
     state::save_state(&lisa_root, &SpiralState::ScopePlanning)?;
 
-    let input = prompt::build_agent_input(Phase::Planning, config, &lisa_root, 0, None);
-    let model = Phase::Planning.model_key(config);
-
-    run_agent_with_tracking(
-        config, &lisa_root, &input, &model, "Planning", "planning", 0,
+    run_plan_agent(
+        config,
+        project_root,
+        Phase::Planning,
+        None,
+        &Invocation::new("Planning", "planning", 0),
     )?;
     git::commit_all("scope: planning — spiral plan and tasks defined", config)?;
     Ok(())
 }
 
+/// Run the Planning or RefinePlan agent, then validate plan.md. A plan with
+/// problems goes back to the agent with the diagnostics, up to
+/// `limits.max_plan_revisions` times; problems left after that are only logged.
+fn run_plan_agent(
+    config: &Config,
+    project_root: &Path,
+    phase: Phase,
+    extra: Option<&str>,
+    invocation: &Invocation,
+) -> Result<()> {
+    let lisa_root = config.lisa_root(project_root);
+    let models = phase.models(config);
+    let mut context = extra.map(str::to_string);
+
+    for revision in 0..=config.limits.max_plan_revisions {
+        let input = prompt::build_agent_input(
+            phase,
+            config,
+            &lisa_root,
+            invocation.pass,
+            context.as_deref(),
+        );
+        run_agent_with_tracking(config, project_root, &input, &models, invocation)?;
+
+        let diagnostics = lint_plan(config, &lisa_root)?;
+        if diagnostics.is_empty() {
+            return Ok(());
+        }
+        warn_plan_problems(&diagnostics);
+        if revision == config.limits.max_plan_revisions {
+            terminal::log_warn(&format!(
+                "plan.md still has problems after {} revision(s) — review it before building.",
+                revision
+            ));
+            break;
+        }
+
+        terminal::log_info(&format!(
+            "Sending the plan back for revision ({}/{})...",
+            revision + 1,
+            config.limits.max_plan_revisions
+        ));
+        let mut revise = extra.map(|e| format!("{}\n", e)).unwrap_or_default();
+        revise.push_str(&format!(
+            "PLAN VALIDATION: {}/methodology/plan.md has problems. Fix each of them \
+             in place, keeping everything else in the plan as it is:\n",
+            config.paths.lisa_root
+        ));
+        for diagnostic in &diagnostics {
+            revise.push_str(&format!("- {}\n", diagnostic));
+        }
+        context = Some(revise);
+    }
+    Ok(())
+}
+
+/// Add the plan's dependency graph to the pass's refine summary, replacing
+/// the one added by an earlier refine. The summary is the refine agent's to
+/// write; without one there is nothing to add the graph to.
+fn embed_task_graph(lisa_root: &Path, pass: u32) -> Result<()> {
+    let plan = tasks::Plan::load(&lisa_root.join("methodology/plan.md"))?;
+    if plan.tasks.is_empty() {
+        return Ok(());
+    }
+    let summary_path = lisa_root.join(format!("spiral/pass-{}/refine-summary.md", pass));
+    let Ok(summary) = std::fs::read_to_string(&summary_path) else {
+        terminal::log_warn(&format!(
+            "No {} — the task graph is not embedded (see `lisa tasks graph`).",
+            summary_path.display()
+        ));
+        return Ok(());
+    };
+    std::fs::write(&summary_path, graph::embed(&summary, &plan))?;
+    Ok(())
+}
+
+fn warn_plan_problems(diagnostics: &[tasks::Diagnostic]) {
+    terminal::log_warn(&format!("plan.md has {} problem(s):", diagnostics.len()));
+    for diagnostic in diagnostics {
+        terminal::log_warn(&format!("  {}", diagnostic));
+    }
+}
+
+/// Problems with plan.md, checked against methodology.md and the pass limit.
+fn lint_plan(config: &Config, lisa_root: &Path) -> Result<Vec<tasks::Diagnostic>> {
+    let plan = tasks::Plan::load(&lisa_root.join("methodology/plan.md"))?;
+    let methodology = std::fs::read_to_string(lisa_root.join("methodology/methodology.md")).ok();
+    Ok(plan.lint(methodology.as_deref(), config.limits.max_spiral_passes))
+}
+
 /// Post-refine review gate loop. Returns the terminal decision (Approve, Edit, or Quit).
 /// Refine is handled internally by re-running the refine agent with feedback.
 fn refine_gate_loop(config: &Config, project_root: &Path, pass: u32) -> Result<RefineDecision> {
@@ -961,23 +1372,14 @@ fn refine_gate_loop(config: &Config, project_root: &Path, pass: u32) -> Result<R
                     },
                 )?;
 
-                let input = prompt::build_agent_input(
-                    Phase::Refine,
+                run_plan_agent(
                     config,
-                    &lisa_root,
-                    pass,
+                    project_root,
+                    Phase::Refine,
                     Some(&extra),
-                );
-                let model = Phase::Refine.model_key(config);
-                run_agent_with_tracking(
-                    config,
-                    &lisa_root,
-                    &input,
-                    &model,
-                    &format!("Refine: pass {} (feedback)", pass),
-                    "refine",
-                    pass,
+                    &Invocation::new(&format!("Refine: pass {} (feedback)", pass), "refine", pass),
                 )?;
+                embed_task_graph(&lisa_root, pass)?;
                 git::commit_all(
                     &format!("refine: pass {} — refined after human feedback", pass),
                     config,
@@ -996,10 +1398,15 @@ fn refine_gate_loop(config: &Config, project_root: &Path, pass: u32) -> Result<R
                 print!("  Press Enter when you are done editing...");
                 let _ = std::io::Write::flush(&mut std::io::stdout());
                 let mut _buf = String::new();
-                let _ = std::io::stdin().read_line(&mut _buf);
+                let _ = interrupt::at_prompt(|| std::io::stdin().read_line(&mut _buf));
 
                 // Re-display summary after edit
                 display_refine_edit_summary(&lisa_root);
+                // The human has the last word, but should see what the agent would be told
+                let diagnostics = lint_plan(config, &lisa_root)?;
+                if !diagnostics.is_empty() {
+                    warn_plan_problems(&diagnostics);
+                }
 
                 terminal::log_success("Refine approved (manually edited). Proceeding to build.");
                 return Ok(RefineDecision::Edit);
@@ -1086,15 +1493,17 @@ fn run_refine(config: &Config, project_root: &Path, pass: u32) -> Result<()> {
         pass,
         Some(&extra),
     );
-    let model = Phase::RefineMethodology.model_key(config);
+    let models = Phase::RefineMethodology.models(config);
     run_agent_with_tracking(
         config,
-        &lisa_root,
+        project_root,
         &input,
-        &model,
-        &format!("Refine methodology: pass {}", pass),
-        "refine_methodology",
-        pass,
+        &models,
+        &Invocation::new(
+            &format!("Refine methodology: pass {}", pass),
+            "refine_methodology",
+            pass,
+        ),
     )?;
     git::commit_all(
         &format!("refine: pass {} — methodology updated", pass),
@@ -1106,18 +1515,14 @@ fn run_refine(config: &Config, project_root: &Path, pass: u32) -> Result<()> {
     // Sub-phase 2: Refine plan
     terminal::log_phase(&format!("PASS {} — REFINE PLAN", pass));
 
-    let input =
-        prompt::build_agent_input(Phase::RefinePlan, config, &lisa_root, pass, Some(&extra));
-    let model = Phase::RefinePlan.model_key(config);
-    run_agent_with_tracking(
+    run_plan_agent(
         config,
-        &lisa_root,
-        &input,
-        &model,
-        &format!("Refine plan: pass {}", pass),
-        "refine_plan",
-        pass,
+        project_root,
+        Phase::RefinePlan,
+        Some(&extra),
+        &Invocation::new(&format!("Refine plan: pass {}", pass), "refine_plan", pass),
     )?;
+    embed_task_graph(&lisa_root, pass)?;
     git::commit_all(&format!("refine: pass {} — plan updated", pass), config)?;
 
     // Advisory warning if task count exceeds max_tasks_per_pass
@@ -1154,8 +1559,11 @@ fn run_build_loop(
         terminal::log_warn(&format!("Circular dependency detected: {}", cycle));
     }
 
+    let lanes = parallel_lanes(config);
     let mut prev_task_hash = tasks::hash_task_statuses(&plan_path)?;
     let mut stall_count: u32 = 0;
+    // Reported to the next build iteration: undone bounding-test edits, failed verification
+    let mut carry_over: Vec<String> = Vec::new();
 
     for iter in start_iter..=config.limits.max_ralph_iterations {
         println!();
@@ -1189,120 +1597,47 @@ fn run_build_loop(
             break;
         }
 
-        // Orchestrator-driven task selection
-        let task = match tasks::find_next_task(&plan_path, pass)? {
-            Some(t) => {
-                terminal::log_info(&format!(
-                    "Selected Task {}: {} (methodology: {})",
-                    t.number,
-                    t.name,
-                    if t.methodology_ref.is_empty() {
-                        "N/A"
-                    } else {
-                        &t.methodology_ref
-                    }
-                ));
-                // Mark task in-progress (orchestrator does this, not the agent)
-                // Skip if already IN_PROGRESS (retrying after crash/resume)
-                if !content_has_in_progress_for_task(&plan_path, t.number)? {
-                    tasks::mark_task_in_progress(&plan_path, t.number)?;
-                }
-                t
+        // Orchestrator-driven task selection: every ready task at once when
+        // lanes are enabled, otherwise the next one in plan order
+        let ready = if lanes > 1 {
+            tasks::find_ready_tasks(&plan_path, pass)?
+        } else {
+            Vec::new()
+        };
+        if ready.len() > 1 {
+            let wave: Vec<_> = ready.into_iter().take(lanes).collect();
+            if !run_lanes(config, project_root, pass, iter, &wave, &mut carry_over)? {
+                return Ok(false);
             }
-            None => {
-                // No eligible task found (all remaining have unmet deps or are blocked)
-                terminal::log_warn("No eligible tasks found (unmet dependencies or all blocked).");
-                if tasks::has_blocked_tasks(&plan_path, pass)? {
-                    match review::block_gate(config, pass, &plan_path, &lisa_root)? {
-                        BlockDecision::Fix => {
-                            stall_count = 0;
-                            continue;
+        } else {
+            let task = match tasks::find_next_task(&plan_path, pass)? {
+                Some(t) => {
+                    select_task(&plan_path, &t)?;
+                    t
+                }
+                None => {
+                    // No eligible task found (all remaining have unmet deps or are blocked)
+                    terminal::log_warn(
+                        "No eligible tasks found (unmet dependencies or all blocked).",
+                    );
+                    if tasks::has_blocked_tasks(&plan_path, pass)? {
+                        match review::block_gate(config, pass, &plan_path, &lisa_root)? {
+                            BlockDecision::Fix => {
+                                stall_count = 0;
+                                continue;
+                            }
+                            BlockDecision::Abort => return Ok(false),
+                            BlockDecision::Skip => break,
                         }
-                        BlockDecision::Abort => return Ok(false),
-                        BlockDecision::Skip => break,
                     }
+                    break;
                 }
-                break;
+            };
+            if !build_task(config, project_root, pass, iter, &task, &mut carry_over)? {
+                return Ok(false);
             }
-        };
-
-        let task_context = format!(
-            "Current spiral pass: {}\n\
-             Assigned task number: {}\n\
-             Assigned task name: {}\n\
-             Methodology section: {}",
-            pass, task.number, task.name, task.methodology_ref
-        );
-
-        // Phase 1: Bounds derivation (independent from implementation)
-        if task.needs_bounds {
-            run_bounds(config, project_root, pass, &task)?;
-        } else {
-            terminal::log_info(&format!(
-                "Skipping bounds phase for task {} (no bounding checks specified).",
-                task.number
-            ));
         }
 
-        // Phase 2: Build (implementation, aware of bounds tests)
-        state::save_state(
-            &lisa_root,
-            &SpiralState::InPass {
-                pass,
-                phase: PassPhase::Build {
-                    task_id: task.number,
-                    iteration: iter,
-                },
-            },
-        )?;
-
-        // Run tests after bounds to provide diagnostic context to the build agent
-        let test_failures = capture_test_failures(config);
-
-        let mut build_context = if task.needs_bounds {
-            format!(
-                "{}\n\
-                 Bounds tests for this task have been written by an independent agent.\n\
-                 Read the bounding tests in {}/ to understand what your implementation must satisfy.",
-                task_context, config.paths.tests_bounds
-            )
-        } else {
-            format!(
-                "{}\n\
-                 This is an infrastructure/non-phenomenon task — no bounding tests were derived.\n\
-                 Focus on implementation correctness and software quality tests.",
-                task_context
-            )
-        };
-
-        if !test_failures.is_empty() {
-            build_context.push_str(&format!(
-                "\n\n## Current Test Status\n\n{}\n\nFix these failures as part of your implementation.",
-                test_failures
-            ));
-        }
-
-        let input =
-            prompt::build_agent_input(Phase::Build, config, &lisa_root, pass, Some(&build_context));
-        let model = Phase::Build.model_key(config);
-        run_agent_with_tracking(
-            config,
-            &lisa_root,
-            &input,
-            &model,
-            &format!("Build: task {} iter {}", task.number, iter),
-            "build",
-            pass,
-        )?;
-
-        git::commit_all(
-            &format!(
-                "build: pass {} task {} iteration {}",
-                pass, task.number, iter
-            ),
-            config,
-        )?;
-
         // Dual-signal stall detection
         let cur_task_hash = tasks::hash_task_statuses(&plan_path)?;
         let code_changed = git::source_changed_in_last_commit(&config.paths.source)?;
@@ -1370,63 +1705,509 @@ fn run_build_loop(
     Ok(true)
 }
 
-/// Run test suite and return a summary of failures (empty string if all pass).
-/// Used to provide diagnostic context to the next agent invocation.
-/// Check if a task is already marked IN_PROGRESS in the plan file.
-fn content_has_in_progress_for_task(plan_path: &Path, task_number: u32) -> Result<bool> {
-    let content = std::fs::read_to_string(plan_path)?;
-    let task_re = regex::Regex::new(&format!(r"(?i)^#{{2,4}}\s+Task\s+{}\b", task_number)).unwrap();
-    let any_task_re = regex::Regex::new(r"(?i)^#{2,4}\s+Task\s+\d").unwrap();
-    let mut in_target = false;
-    for line in content.lines() {
-        if task_re.is_match(line) {
-            in_target = true;
-        } else if in_target && any_task_re.is_match(line) {
-            break;
-        } else if in_target && line.contains("**Status:**") && line.contains("IN_PROGRESS") {
-            return Ok(true);
+/// How many tasks a build iteration may work on at once (`max_parallel_tasks`).
+/// Lanes commit their work to be merged back, so they need `auto_commit`; and
+/// recordings are kept in invocation order, which lanes would scramble.
+fn parallel_lanes(config: &Config) -> usize {
+    let max = config.limits.max_parallel_tasks.max(1) as usize;
+    if max == 1 {
+        return 1;
+    }
+    if !config.git.auto_commit {
+        terminal::log_warn(
+            "max_parallel_tasks needs git.auto_commit — building one task at a time.",
+        );
+        return 1;
+    }
+    if config.agent.record || !config.agent.replay.is_empty() {
+        terminal::log_warn(
+            "Recording and replay keep agents in a fixed order — building one task at a time.",
+        );
+        return 1;
+    }
+    max
+}
+
+/// Build several ready tasks at once, each in its own [`Lane`]. Finished
+/// lanes are merged back in plan order; a lane whose changes conflict with
+/// those merged before it is left on a branch and its task goes back to
+/// TODO. Gates a lane reaches are asked on this thread while the others keep
+/// running. Returns false on abort.
+fn run_lanes(
+    config: &Config,
+    project_root: &Path,
+    pass: u32,
+    iter: u32,
+    wave: &[tasks::TaskInfo],
+    carry_over: &mut Vec<String>,
+) -> Result<bool> {
+    let lisa_root = config.lisa_root(project_root);
+    let plan_path = lisa_root.join("methodology/plan.md");
+
+    for task in wave {
+        select_task(&plan_path, task)?;
+    }
+    state::save_state(
+        &lisa_root,
+        &SpiralState::InPass {
+            pass,
+            phase: PassPhase::Build {
+                task_id: 0,
+                iteration: iter,
+                lanes: wave.iter().map(|t| t.number).collect(),
+            },
+        },
+    )?;
+    let numbers: Vec<String> = wave.iter().map(|t| t.number.to_string()).collect();
+    terminal::log_info(&format!(
+        "Building tasks {} in parallel worktrees.",
+        numbers.join(", ")
+    ));
+
+    let mut lanes = wave
+        .iter()
+        .map(|t| Lane::create(config, project_root, pass, t.number))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Lane output interleaves, so it cannot collapse
+    let mut lane_config = config.clone();
+    lane_config.terminal.collapse_output = false;
+    let notes = std::mem::take(carry_over);
+    let (prompts, asked) = mpsc::channel::<review::Prompt>();
+
+    let outcomes: Vec<(Result<bool>, Vec<String>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = lanes
+            .iter()
+            .zip(wave)
+            .map(|(lane, task)| {
+                let lane_config = &lane_config;
+                let lisa_root = &lisa_root;
+                let mut lane_notes = notes.clone();
+                let prompts = prompts.clone();
+                scope.spawn(move || {
+                    let result = review::forward_prompts(prompts, || {
+                        usage::share_ledger(lisa_root, || {
+                            process::in_dir(lane.root(), || {
+                                build_task(
+                                    lane_config,
+                                    lane.root(),
+                                    pass,
+                                    iter,
+                                    task,
+                                    &mut lane_notes,
+                                )
+                            })
+                        })
+                    });
+                    (result, lane_notes)
+                })
+            })
+            .collect();
+        // Gates the lanes reach are asked here, one at a time, until all lanes are done
+        drop(prompts);
+        for prompt in asked {
+            prompt();
+        }
+        handles
+            .into_iter()
+            .map(|h| {
+                h.join()
+                    .unwrap_or_else(|_| (Err(anyhow::anyhow!("Build lane panicked")), Vec::new()))
+            })
+            .collect()
+    });
+
+    for lane in &lanes {
+        lane.collect(config, project_root, pass)?;
+    }
+    interrupt::check()?;
+
+    let mut proceed = true;
+    let mut first_error = None;
+    let mut merged_bounds = false;
+    for ((lane, task), (result, notes)) in lanes.iter_mut().zip(wave).zip(outcomes) {
+        match result {
+            Ok(true) => {}
+            Ok(false) => {
+                proceed = false;
+                continue;
+            }
+            Err(e) => {
+                terminal::log_error(&format!("Task {} failed: {}", task.number, e));
+                first_error.get_or_insert(e);
+                continue;
+            }
+        }
+        carry_over.extend(notes);
+        match lane.merge(config, project_root)? {
+            lane::Merge::Merged => {
+                merged_bounds |= task.needs_bounds;
+                if let Some(failures) = verify_done_task(config, &plan_path, task.number)? {
+                    carry_over.push(failures);
+                }
+            }
+            lane::Merge::Conflict(branch) => {
+                terminal::log_warn(&format!(
+                    "Task {} conflicts with work merged before it — its commits are kept on \
+                     branch '{}' and the task is back to TODO.",
+                    task.number, branch
+                ));
+                carry_over.push(format!(
+                    "\n\n## Merge Conflict\n\nTask {} was built in parallel with other tasks, \
+                     but its changes conflicted with theirs and were not merged. They are on \
+                     branch `{}` for reference; redo the task on top of the current code.",
+                    task.number, branch
+                ));
+            }
+        }
+    }
+
+    // Each lane tagged only its own bounding tests; tag them together
+    if merged_bounds {
+        git::create_tag(&format!("lisa/bounds/pass-{}-iter-{}", pass, iter))?;
+    }
+    if let Some(e) = first_error {
+        return Err(e);
+    }
+    Ok(proceed)
+}
+
+/// Log the selected task and mark it IN_PROGRESS (the orchestrator does
+/// this, not the agent). Already IN_PROGRESS when retrying after a crash/resume.
+fn select_task(plan_path: &Path, task: &tasks::TaskInfo) -> Result<()> {
+    terminal::log_info(&format!(
+        "Selected Task {}: {} (methodology: {})",
+        task.number,
+        task.name,
+        if task.methodology_ref.is_empty() {
+            "N/A"
+        } else {
+            &task.methodology_ref
+        }
+    ));
+    tasks::mark_task_in_progress(plan_path, task.number)
+}
+
+/// One build iteration on `task`: Bounds (if it has bounding checks), Build,
+/// verification and checks, then a commit. Returns false on abort.
+fn build_task(
+    config: &Config,
+    project_root: &Path,
+    pass: u32,
+    iter: u32,
+    task: &tasks::TaskInfo,
+    carry_over: &mut Vec<String>,
+) -> Result<bool> {
+    let lisa_root = config.lisa_root(project_root);
+    let plan_path = lisa_root.join("methodology/plan.md");
+
+    let task_context = format!(
+        "Current spiral pass: {}\n\
+         Assigned task number: {}\n\
+         Assigned task name: {}\n\
+         Methodology section: {}",
+        pass, task.number, task.name, task.methodology_ref
+    );
+
+    // Phase 1: Bounds derivation (independent from implementation)
+    if task.needs_bounds {
+        run_bounds(config, project_root, pass, task)?;
+    } else {
+        terminal::log_info(&format!(
+            "Skipping bounds phase for task {} (no bounding checks specified).",
+            task.number
+        ));
+    }
+
+    // Phase 2: Build (implementation, aware of bounds tests)
+    state::save_state(
+        &lisa_root,
+        &SpiralState::InPass {
+            pass,
+            phase: PassPhase::Build {
+                task_id: task.number,
+                iteration: iter,
+                lanes: Vec::new(),
+            },
+        },
+    )?;
+
+    // Run tests after bounds to provide diagnostic context to the build agent
+    let test_failures = capture_test_failures(config);
+
+    let mut build_context = if task.needs_bounds {
+        format!(
+            "{}\n\
+             Bounds tests for this task have been written by an independent agent.\n\
+             Read the bounding tests in {}/ to understand what your implementation must satisfy.",
+            task_context, config.paths.tests_bounds
+        )
+    } else {
+        format!(
+            "{}\n\
+             This is an infrastructure/non-phenomenon task — no bounding tests were derived.\n\
+             Focus on implementation correctness and software quality tests.",
+            task_context
+        )
+    };
+
+    if !test_failures.is_empty() {
+        build_context.push_str(&format!(
+            "\n\n## Current Test Status\n\n{}\n\nFix these failures as part of your implementation.",
+            test_failures
+        ));
+    }
+
+    for note in carry_over.drain(..) {
+        build_context.push_str(&note);
+    }
+
+    let input =
+        prompt::build_agent_input(Phase::Build, config, &lisa_root, pass, Some(&build_context));
+    let models = Phase::Build.models(config);
+    let guard = BoundsGuard::baseline(config, project_root, pass, task.number);
+    let built = run_agent_with_tracking(
+        config,
+        project_root,
+        &input,
+        &models,
+        &Invocation::new(
+            &format!("Build: task {} iter {}", task.number, iter),
+            "build",
+            pass,
+        )
+        .task(task.number)
+        .iteration(iter),
+    );
+    if let Err(e) = built {
+        // Undo this iteration's tampering now: a later check would charge it
+        // to the wrong iteration, or miss it without a tagged baseline
+        let guarded = guard_bounds_tests(
+            config,
+            project_root,
+            pass,
+            task.number,
+            iter,
+            &guard,
+            true,
+            carry_over,
+        );
+        if let Err(restore) = guarded {
+            terminal::log_warn(&format!("Could not check the bounding tests: {}", restore));
         }
+        return Err(e);
+    }
+
+    if !guard_bounds_tests(
+        config,
+        project_root,
+        pass,
+        task.number,
+        iter,
+        &guard,
+        false,
+        carry_over,
+    )? {
+        return Ok(false);
     }
-    Ok(false)
+
+    if let Some(failures) = verify_done_task(config, &plan_path, task.number)? {
+        carry_over.push(failures);
+    }
+    if let Some(diagnostics) =
+        checks::run_iteration_checks(config, &lisa_root, pass, task.number, iter)?
+    {
+        carry_over.push(diagnostics);
+    }
+
+    git::commit_all(
+        &format!(
+            "build: pass {} task {} iteration {}",
+            pass, task.number, iter
+        ),
+        config,
+    )?;
+    Ok(true)
 }
 
+/// Compare the bounding tests with their state before a build iteration and
+/// handle any modification per `[bounds] on_tamper`, or restore it outright
+/// when the agent `failed`. Restored edits are added to `carry_over` for the
+/// next iteration. Returns false on abort.
+#[allow(clippy::too_many_arguments)]
+fn guard_bounds_tests(
+    config: &Config,
+    project_root: &Path,
+    pass: u32,
+    task: u32,
+    iter: u32,
+    guard: &BoundsGuard,
+    failed: bool,
+    carry_over: &mut Vec<String>,
+) -> Result<bool> {
+    let tampering = guard.check(project_root);
+    if tampering.is_empty() {
+        return Ok(true);
+    }
+    let lisa_root = config.lisa_root(project_root);
+    let label = format!("Build: task {} iter {}", task, iter);
+    let summary = tampering.summary();
+    let diff = guard.diff(project_root, &tampering);
+    let diff_path = tamper::save_diff(&lisa_root, pass, task, iter, &diff)?;
+    terminal::log_warn(&format!(
+        "{} modified the bounding tests:\n{}",
+        label, summary
+    ));
+
+    let decision = match config.bounds.on_tamper {
+        _ if failed => TamperDecision::Restore,
+        TamperPolicy::Restore => TamperDecision::Restore,
+        TamperPolicy::Gate => review::tamper_gate(config, &label, &summary, &diff, &diff_path)?,
+    };
+    let restore = || -> Result<()> {
+        guard.restore(project_root, &tampering)?;
+        let paths: Vec<String> = tampering
+            .modified
+            .iter()
+            .chain(&tampering.added)
+            .chain(&tampering.deleted)
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect();
+        // Already undone; keep the write-scope gate from offering them again
+        writescope::resolve_reverted(&lisa_root, &paths)
+    };
+    match decision {
+        TamperDecision::Restore => {
+            restore()?;
+            terminal::log_info("Bounding tests restored.");
+            carry_over.push(format!(
+                "\n\n## Bounding Test Changes Reverted\n\n\
+                 Your previous iteration changed the bounding tests in {}/, which were written \
+                 by an independent agent and must not be modified. Lisa restored them:\n\n\
+                 ```diff\n{}```\n\n\
+                 Make the implementation satisfy the bounds as written. If you believe a bound \
+                 is wrong, follow the Reconsideration Protocol instead of editing the test.",
+                config.paths.tests_bounds, diff
+            ));
+            Ok(true)
+        }
+        TamperDecision::Accept => {
+            terminal::log_warn(&format!(
+                "Keeping modified bounding tests (diff: {}).",
+                diff_path.display()
+            ));
+            Ok(true)
+        }
+        TamperDecision::Abort => {
+            // Restore anyway so a resumed build does not start from the modified tests
+            restore()?;
+            terminal::log_info(&format!(
+                "Bounding tests restored; the attempted changes are in {}.",
+                diff_path.display()
+            ));
+            Ok(false)
+        }
+    }
+}
+
+/// Run test suite and return a summary of failures (empty string if all pass).
+/// Used to provide diagnostic context to the next agent invocation.
 fn capture_test_failures(config: &Config) -> String {
     let test_cmd = &config.commands.test_all;
     if test_cmd.is_empty() {
         return String::new();
     }
 
-    let output = std::process::Command::new("bash")
-        .args(["-c", test_cmd])
-        .output();
-
-    match output {
-        Ok(out) if out.status.success() => String::new(),
-        Ok(out) => {
-            let stderr = String::from_utf8_lossy(&out.stderr);
-            let stdout = String::from_utf8_lossy(&out.stdout);
-            // Combine and truncate to avoid overwhelming the context
-            let combined = format!("{}\n{}", stdout, stderr);
-            let lines: Vec<&str> = combined.lines().collect();
-            let truncated: String = if lines.len() > 80 {
-                let kept: Vec<&str> = lines[lines.len() - 80..].to_vec();
-                format!(
-                    "[...truncated, showing last 80 lines...]\n{}",
-                    kept.join("\n")
-                )
-            } else {
-                combined.to_string()
-            };
-            format!(
-                "TEST FAILURES DETECTED (from `{}`):\n```\n{}\n```",
+    match process::run_shell(test_cmd, config) {
+        Ok(result) => match result.failure_output() {
+            None => String::new(),
+            Some(output) if matches!(result.status, process::Status::TimedOut(_)) => format!(
+                "TESTS {} (from `{}`):\n```\n{}\n```",
+                result.status.describe().to_uppercase(),
                 test_cmd,
-                truncated.trim()
-            )
-        }
+                output
+            ),
+            Some(output) => format!(
+                "TEST FAILURES DETECTED (from `{}`):\n```\n{}\n```",
+                test_cmd, output
+            ),
+        },
         Err(e) => format!("Failed to run test command `{}`: {}", test_cmd, e),
     }
 }
 
+/// A task only counts as DONE once lisa has run the tests itself. If the agent
+/// marked `task` DONE, run `commands.test_bounds` and `commands.test_software`
+/// (or `test_all` when neither is set); on failure reopen the task and return
+/// the failures for the next build iteration.
+fn verify_done_task(config: &Config, plan_path: &Path, task: u32) -> Result<Option<String>> {
+    if tasks::task_status(plan_path, task)? != Some(tasks::Status::Done) {
+        return Ok(None);
+    }
+
+    let mut suites: Vec<(&str, &str)> = [
+        ("bounds", config.commands.test_bounds.as_str()),
+        ("software", config.commands.test_software.as_str()),
+    ]
+    .into_iter()
+    .filter(|(_, cmd)| !cmd.is_empty())
+    .collect();
+    if suites.is_empty() && !config.commands.test_all.is_empty() {
+        suites.push(("all", config.commands.test_all.as_str()));
+    }
+    if suites.is_empty() {
+        terminal::log_warn(&format!(
+            "No test commands configured — Task {} is DONE unverified.",
+            task
+        ));
+        return Ok(None);
+    }
+
+    terminal::log_info(&format!("Verifying Task {} (marked DONE)...", task));
+    let mut failures = Vec::new();
+    for (suite, cmd) in suites {
+        match process::run_shell(cmd, config) {
+            Ok(result) => match result.failure_output() {
+                None => terminal::log_success(&format!("  {} tests pass", suite)),
+                Some(output) => {
+                    let verdict = result.status.describe();
+                    terminal::log_warn(&format!("  {} tests {} (`{}`)", suite, verdict, cmd));
+                    failures.push(format!(
+                        "### {} tests {} (`{}`)\n\n```\n{}\n```",
+                        suite, verdict, cmd, output
+                    ));
+                }
+            },
+            Err(e) => {
+                terminal::log_warn(&format!("  {} tests could not run: {}", suite, e));
+                failures.push(format!(
+                    "### {} tests (`{}`)\n\nFailed to run: {}",
+                    suite, cmd, e
+                ));
+            }
+        }
+    }
+    // Killed test runs say nothing about the task
+    interrupt::check()?;
+    if failures.is_empty() {
+        terminal::log_success(&format!("Task {} verified.", task));
+        return Ok(None);
+    }
+
+    tasks::reopen_task(plan_path, task)?;
+    terminal::log_warn(&format!(
+        "Task {} marked DONE but tests fail — reopened as IN_PROGRESS.",
+        task
+    ));
+    Ok(Some(format!(
+        "\n\n## Verification Failed\n\n\
+         You marked Task {} DONE, but lisa's test run failed, so it is IN_PROGRESS again. \
+         Fix these failures before marking it DONE:\n\n{}",
+        task,
+        failures.join("\n\n")
+    )))
+}
+
 /// Run bounds derivation for a specific task (independent from implementation).
 fn run_bounds(
     config: &Config,
@@ -1470,17 +2251,102 @@ fn run_bounds(
         ));
     }
 
-    let input = prompt::build_agent_input(Phase::Bounds, config, &lisa_root, pass, Some(&extra));
-    let model = Phase::Bounds.model_key(config);
-    run_agent_with_tracking(
-        config,
-        &lisa_root,
-        &input,
-        &model,
-        &format!("Bounds: task {}", task.number),
-        "bounds",
-        pass,
-    )?;
+    let models = Phase::Bounds.models(config);
+    let policy = config.bounds.on_source_access;
+    let mut attempt = 1;
+    let sandbox = loop {
+        let sandbox = if config.bounds.sandbox {
+            let sandbox = Sandbox::create(config, project_root, pass, task.number)?;
+            terminal::log_info(&format!(
+                "Bounds agent sandboxed in {} (no {})",
+                sandbox.root().display(),
+                config.source_dirs_display()
+            ));
+            Some(sandbox)
+        } else {
+            None
+        };
+        let working_dir = sandbox.as_ref().map(Sandbox::root);
+        let sandboxed = sandbox.as_ref().map(|s| s.restrict(config));
+
+        let input =
+            prompt::build_agent_input(Phase::Bounds, config, &lisa_root, pass, Some(&extra));
+        let result = run_agent_with_tracking(
+            sandboxed.as_ref().unwrap_or(config),
+            project_root,
+            &input,
+            &models,
+            &Invocation::new(&format!("Bounds: task {}", task.number), "bounds", pass)
+                .task(task.number)
+                .working_dir(working_dir),
+        )?;
+
+        let violations =
+            independence::find_source_accesses(&result.tool_log, config, project_root, working_dir);
+        let outcome = match (violations.is_empty(), policy) {
+            (true, _) => BoundsOutcome::Verified,
+            (false, SourceAccessPolicy::Warn) => BoundsOutcome::Warned,
+            (false, SourceAccessPolicy::Rerun) if attempt == 1 => BoundsOutcome::Rerun,
+            (false, _) => BoundsOutcome::Failed,
+        };
+        independence::record_run(
+            &lisa_root,
+            pass,
+            independence::BoundsRun {
+                task: task.number,
+                attempt,
+                tool_calls: result.tool_log.len(),
+                violations: violations.clone(),
+                outcome,
+            },
+        )?;
+
+        if violations.is_empty() {
+            break sandbox;
+        }
+        let listing = violations
+            .iter()
+            .map(|v| format!("  - {}", v))
+            .collect::<Vec<_>>()
+            .join("\n");
+        terminal::log_warn(&format!(
+            "Bounds agent accessed implementation code (task {}):\n{}",
+            task.number, listing
+        ));
+        // A sandboxed attempt has not touched the project; dropping it discards it
+        let discard = || match sandbox {
+            Some(_) => Ok(()),
+            None => git::discard_changes(&config.paths.tests_bounds),
+        };
+        match outcome {
+            BoundsOutcome::Warned => break sandbox,
+            BoundsOutcome::Rerun => {
+                discard()?;
+                terminal::log_info("Discarded bounding tests — re-running Bounds phase.");
+                extra.push_str(&format!(
+                    "\n\nINDEPENDENCE VIOLATION: Your previous attempt accessed implementation \
+                     code and its tests were discarded:\n{}\n\
+                     Do NOT read, search or run anything under {}.\n",
+                    listing,
+                    config.source_dirs_display()
+                ));
+                attempt += 1;
+            }
+            _ => {
+                discard()?;
+                anyhow::bail!(
+                    "Bounds agent accessed implementation code for task {} \
+                     (bounds.on_source_access = \"{}\"); its tests were discarded.",
+                    task.number,
+                    policy
+                );
+            }
+        }
+    };
+
+    if let Some(sandbox) = sandbox {
+        sandbox.copy_back(config)?;
+    }
     git::commit_all(
         &format!(
             "bounds: pass {} task {} — bounding tests",
@@ -1488,6 +2354,10 @@ fn run_bounds(
         ),
         config,
     )?;
+    // The baseline the Build agent's changes are checked against
+    if config.git.auto_commit {
+        git::create_tag(&tamper::bounds_tag(pass, task.number))?;
+    }
 
     Ok(())
 }
@@ -1505,18 +2375,29 @@ fn run_audit(config: &Config, project_root: &Path, pass: u32) -> Result<()> {
 
     std::fs::create_dir_all(lisa_root.join(format!("spiral/pass-{}", pass)))?;
 
-    let extra = format!("Current spiral pass: {}", pass);
+    let mut extra = format!("Current spiral pass: {}", pass);
+    if let Some(results) = testresults::collect(config, project_root, pass)? {
+        extra.push_str(&format!(
+            "\nTest results measured by lisa (spiral/pass-{}/test-results.toml): {}",
+            pass,
+            results.summary_line()
+        ));
+        let failing = results.failing();
+        if !failing.is_empty() {
+            extra.push_str(&format!("\nFailing tests:\n- {}", failing.join("\n- ")));
+        }
+        extra.push_str("\nReport these numbers; do not re-count them from your own test runs.");
+    }
     let input = prompt::build_agent_input(Phase::Audit, config, &lisa_root, pass, Some(&extra));
-    let model = Phase::Audit.model_key(config);
+    let models = Phase::Audit.models(config);
     run_agent_with_tracking(
         config,
-        &lisa_root,
+        project_root,
         &input,
-        &model,
-        &format!("Audit: pass {}", pass),
-        "audit",
-        pass,
+        &models,
+        &Invocation::new(&format!("Audit: pass {}", pass), "audit", pass),
     )?;
+    independence::append_to_review_package(&lisa_root, pass, config)?;
     git::commit_all(&format!("audit: pass {}", pass), config)?;
     state::save_state(&lisa_root, &SpiralState::AuditComplete { pass })?;
     Ok(())
@@ -1531,14 +2412,18 @@ fn pass_review_loop(
     lisa_root: &Path,
 ) -> Result<ReviewDecision> {
     loop {
-        match review::review_gate(config, pass, lisa_root)? {
+        match review::review_gate(config, pass, project_root, lisa_root)? {
             ReviewDecision::Explore => {
                 let explore_id = next_explore_id(lisa_root, pass);
                 run_explore(config, project_root, pass, explore_id)?;
                 state::save_state(lisa_root, &SpiralState::PassReview { pass })?;
                 continue;
             }
-            other => return Ok(other),
+            ReviewDecision::Quit => return Ok(ReviewDecision::Quit),
+            other => {
+                writescope::accept_pending(lisa_root)?;
+                return Ok(other);
+            }
         }
     }
 }
@@ -1593,16 +2478,20 @@ fn run_explore(config: &Config, project_root: &Path, pass: u32, explore_id: u32)
             terminal::log_info(&format!("Resumed exploration question: {}", saved));
             saved
         } else if std::io::stdin().is_terminal() {
-            dialoguer::Input::new()
-                .with_prompt("  Exploration question")
-                .interact_text()?
+            interrupt::at_prompt(|| {
+                dialoguer::Input::new()
+                    .with_prompt("  Exploration question")
+                    .interact_text()
+            })?
         } else {
             anyhow::bail!("Exploration requires an interactive terminal for the question prompt");
         }
     } else if std::io::stdin().is_terminal() {
-        let q: String = dialoguer::Input::new()
-            .with_prompt("  Exploration question")
-            .interact_text()?;
+        let q: String = interrupt::at_prompt(|| {
+            dialoguer::Input::new()
+                .with_prompt("  Exploration question")
+                .interact_text()
+        })?;
         std::fs::write(&question_path, &q)?;
         q
     } else {
@@ -1618,15 +2507,17 @@ fn run_explore(config: &Config, project_root: &Path, pass: u32, explore_id: u32)
     );
 
     let input = prompt::build_agent_input(Phase::Explore, config, &lisa_root, pass, Some(&extra));
-    let model = Phase::Explore.model_key(config);
+    let models = Phase::Explore.models(config);
     run_agent_with_tracking(
         config,
-        &lisa_root,
+        project_root,
         &input,
-        &model,
-        &format!("Explore: pass {} #{}", pass, explore_id),
-        "explore",
-        pass,
+        &models,
+        &Invocation::new(
+            &format!("Explore: pass {} #{}", pass, explore_id),
+            "explore",
+            pass,
+        ),
     )?;
 
     // Commit exploration results
@@ -1677,15 +2568,13 @@ fn finalize(config: &Config, project_root: &Path, pass: u32) -> Result<()> {
     std::fs::create_dir_all(lisa_root.join("output"))?;
 
     let input = prompt::build_agent_input(Phase::Finalize, config, &lisa_root, pass, Some(&extra));
-    let model = Phase::Finalize.model_key(config);
+    let models = Phase::Finalize.models(config);
     run_agent_with_tracking(
         config,
-        &lisa_root,
+        project_root,
         &input,
-        &model,
-        "Finalize: output",
-        "finalize",
-        pass,
+        &models,
+        &Invocation::new("Finalize: output", "finalize", pass),
     )?;
     git::commit_all("final: generate output deliverables", config)?;
 
@@ -1793,6 +2682,44 @@ fn generate_audit_report(
         writeln!(report).unwrap();
     }
 
+    // Task graph
+    let plan = tasks::Plan::load(&lisa_root.join("methodology/plan.md"))?;
+    if !plan.tasks.is_empty() {
+        writeln!(report, "---\n").unwrap();
+        writeln!(report, "## Task Graph\n").unwrap();
+        writeln!(report, "{}", graph::mermaid_block(&plan)).unwrap();
+    }
+
+    // Bounds independence across all passes
+    let independence_logs: Vec<(u32, independence::IndependenceLog)> = (1..=final_pass)
+        .filter_map(|pass| {
+            independence::load_log(lisa_root, pass)
+                .ok()
+                .filter(|log| !log.runs.is_empty())
+                .map(|log| (pass, log))
+        })
+        .collect();
+    if !independence_logs.is_empty() {
+        writeln!(report, "---\n").unwrap();
+        writeln!(report, "## Bounds Independence\n").unwrap();
+        for (pass, log) in &independence_logs {
+            let verdict = if log.verified() {
+                "verified — no source access"
+            } else {
+                "NOT verified — source accessed"
+            };
+            writeln!(
+                report,
+                "- Pass {}: {} ({} Bounds runs)",
+                pass,
+                verdict,
+                log.runs.len()
+            )
+            .unwrap();
+        }
+        writeln!(report).unwrap();
+    }
+
     // Per-pass summaries
     writeln!(report, "---\n").unwrap();
     writeln!(report, "## Spiral Pass History\n").unwrap();
@@ -1899,7 +2826,7 @@ pub fn rollback(config: &Config, project_root: &Path, target_pass: u32, force: b
         print!("  Proceed? [y/N] ");
         let _ = std::io::Write::flush(&mut std::io::stdout());
         let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
+        interrupt::at_prompt(|| std::io::stdin().read_line(&mut input))?;
         if !input.trim().eq_ignore_ascii_case("y") {
             terminal::log_info("Rollback cancelled.");
             return Ok(());
@@ -2050,6 +2977,33 @@ fn count_follow_ups(content: &str) -> u32 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_transcript_base_naming() {
+        let dir = tempfile::tempdir().unwrap();
+        let lisa_root = dir.path();
+        let transcripts = lisa_root.join("spiral/pass-2/transcripts");
+
+        let build = Invocation::new("Build", "build", 2).task(3).iteration(1);
+        assert_eq!(
+            build.transcript_base(lisa_root),
+            transcripts.join("build-3-1")
+        );
+
+        let refine = Invocation::new("Refine", "refine_plan", 2);
+        assert_eq!(
+            refine.transcript_base(lisa_root),
+            transcripts.join("refine-plan")
+        );
+
+        // A repeated invocation gets its own transcript
+        std::fs::create_dir_all(&transcripts).unwrap();
+        std::fs::write(transcripts.join("build-3-1.jsonl"), "").unwrap();
+        assert_eq!(
+            build.transcript_base(lisa_root),
+            transcripts.join("build-3-1-r2")
+        );
+    }
+
     #[test]
     fn test_count_follow_ups_none() {
         let content = "# Assignment\n\n## Assignment\nSolve X.\n";
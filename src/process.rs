@@ -0,0 +1,671 @@
+//! Shell commands lisa runs itself (setup, build, lint, tests), and the
+//! process groups of agents.
+
+use std::cell::RefCell;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::interrupt;
+use crate::terminal;
+
+/// Lines of combined output kept in failure reports for agent context.
+const REPORT_LINES: usize = 80;
+/// How long to wait for output after the shell exits, in case a background
+/// process it left behind still holds the pipes.
+const DRAIN_GRACE: Duration = Duration::from_secs(2);
+/// How long a process group gets to exit after SIGTERM before SIGKILL.
+pub const TERM_GRACE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    Success,
+    /// Non-zero exit; `None` when killed by a signal.
+    Failed(Option<i32>),
+    /// Killed after exceeding the timeout.
+    TimedOut(Duration),
+}
+
+impl Status {
+    /// Short form for reports: `passed`, `failed (exit 1)`, `hung after 600s`.
+    pub fn describe(&self) -> String {
+        match self {
+            Status::Success => "passed".to_string(),
+            Status::Failed(Some(code)) => format!("failed (exit {})", code),
+            Status::Failed(None) => "failed (killed by a signal)".to_string(),
+            Status::TimedOut(after) => format!("hung after {}s", after.as_secs()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandResult {
+    pub status: Status,
+    pub stdout: String,
+    pub stderr: String,
+    /// The middle of stdout was dropped to stay under the output cap.
+    pub stdout_truncated: bool,
+}
+
+impl CommandResult {
+    pub fn success(&self) -> bool {
+        self.status == Status::Success
+    }
+
+    /// `None` on success, otherwise the last lines of the combined output,
+    /// headed by a notice if the command timed out.
+    pub fn failure_output(&self) -> Option<String> {
+        if self.success() {
+            return None;
+        }
+        let combined = format!("{}\n{}", self.stdout.trim_end(), self.stderr);
+        let lines: Vec<&str> = combined.lines().collect();
+        let mut report = if lines.len() > REPORT_LINES {
+            format!(
+                "[...truncated, showing last {} lines...]\n{}",
+                REPORT_LINES,
+                lines[lines.len() - REPORT_LINES..].join("\n")
+            )
+        } else {
+            combined.trim().to_string()
+        };
+        if let Status::TimedOut(after) = self.status {
+            report = format!(
+                "TIMED OUT: hung after {}s and was killed. Output before the timeout:\n{}",
+                after.as_secs(),
+                report
+            );
+        }
+        Some(report.trim().to_string())
+    }
+}
+
+/// Run `cmd` with the limits from `[limits]`, in the current directory.
+pub fn run_shell(cmd: &str, config: &Config) -> std::io::Result<CommandResult> {
+    run(
+        cmd,
+        None,
+        timeout(config),
+        config.limits.command_output_kb * 1024,
+    )
+}
+
+/// Like [`run_shell`], in `dir`.
+pub fn run_shell_in(cmd: &str, dir: &Path, config: &Config) -> std::io::Result<CommandResult> {
+    run(
+        cmd,
+        Some(dir),
+        timeout(config),
+        config.limits.command_output_kb * 1024,
+    )
+}
+
+/// Run `cmd` and return its failure report (see [`CommandResult::failure_output`]),
+/// or `None` if it succeeds.
+pub fn run_check(cmd: &str, config: &Config) -> std::io::Result<Option<String>> {
+    Ok(run_shell(cmd, config)?.failure_output())
+}
+
+fn timeout(config: &Config) -> Option<Duration> {
+    match config.limits.command_timeout_secs {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+thread_local! {
+    static WORKDIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with `dir` as the working directory of every command this thread
+/// starts without an explicit one: shell commands, git and agents. Parallel
+/// build lanes each run in their own worktree this way.
+pub fn in_dir<T>(dir: &Path, f: impl FnOnce() -> T) -> T {
+    let previous = WORKDIR.with(|w| w.replace(Some(dir.to_path_buf())));
+    let result = f();
+    WORKDIR.with(|w| *w.borrow_mut() = previous);
+    result
+}
+
+/// The working directory set by [`in_dir`] for this thread, if any.
+pub fn workdir() -> Option<PathBuf> {
+    WORKDIR.with(|w| w.borrow().clone())
+}
+
+/// Run `bash -c cmd` in its own process group, keeping at most `max_output`
+/// bytes per stream and killing the group after `timeout`.
+pub fn run(
+    cmd: &str,
+    dir: Option<&Path>,
+    timeout: Option<Duration>,
+    max_output: usize,
+) -> std::io::Result<CommandResult> {
+    let mut command = Command::new("bash");
+    command
+        .args(["-c", cmd])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = dir.map(Path::to_path_buf).or_else(workdir) {
+        command.current_dir(dir);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command.spawn()?;
+    interrupt::track(child.id());
+
+    let (tx, rx) = mpsc::channel();
+    let readers = [
+        child
+            .stdout
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|s| Box::new(s) as Box<dyn Read + Send>),
+    ];
+    for (idx, reader) in readers.into_iter().enumerate() {
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            let mut buf = CappedBuffer::new(max_output);
+            if let Some(mut reader) = reader {
+                let mut chunk = [0u8; 8192];
+                while let Ok(n) = reader.read(&mut chunk) {
+                    if n == 0 {
+                        break;
+                    }
+                    buf.push(&chunk[..n]);
+                }
+            }
+            let truncated = buf.dropped > 0;
+            let _ = tx.send((idx, buf.into_string(), truncated));
+        });
+    }
+    drop(tx);
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(exit) = child.try_wait()? {
+            break if exit.success() {
+                Status::Success
+            } else {
+                Status::Failed(exit.code())
+            };
+        }
+        if let Some(limit) = timeout {
+            if started.elapsed() >= limit {
+                kill_group(&mut child);
+                let _ = child.wait();
+                break Status::TimedOut(limit);
+            }
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+
+    let mut streams = [String::new(), String::new()];
+    let mut truncated = [false; 2];
+    let mut received = 0;
+    while received < 2 {
+        match rx.recv_timeout(DRAIN_GRACE) {
+            Ok((idx, text, dropped)) => {
+                streams[idx] = text;
+                truncated[idx] = dropped;
+                received += 1;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                // Leftover background processes still hold the pipes
+                kill_group(&mut child);
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    interrupt::untrack(child.id());
+    if interrupt::requested() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Interrupted,
+            interrupt::Interrupted,
+        ));
+    }
+    let [stdout, stderr] = streams;
+    Ok(CommandResult {
+        status,
+        stdout,
+        stderr,
+        stdout_truncated: truncated[0],
+    })
+}
+
+/// Kill the command's whole process group (just the shell off Unix).
+fn kill_group(child: &mut std::process::Child) {
+    #[cfg(unix)]
+    {
+        // The group id is the shell's pid (process_group(0))
+        unsafe {
+            libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = child.kill();
+    }
+}
+
+/// A process that outlived [`terminate_group`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Orphan {
+    pub pid: u32,
+    pub command: String,
+}
+
+/// Stop the process group `pgid`: SIGTERM, up to `grace` for it to exit, then
+/// SIGKILL. Returns the leader's descendants that are still running afterwards
+/// (they left the group, e.g. with `setsid`). Descendants are only tracked on
+/// Linux, through `/proc`.
+#[cfg(unix)]
+pub fn terminate_group(pgid: u32, grace: Duration) -> Vec<Orphan> {
+    let tree = descendants(pgid);
+    let pg = pgid as libc::pid_t;
+    unsafe {
+        libc::killpg(pg, libc::SIGTERM);
+    }
+    let deadline = Instant::now() + grace;
+    while group_running(pgid) && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    if group_running(pgid) {
+        unsafe {
+            libc::killpg(pg, libc::SIGKILL);
+        }
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while group_running(pgid) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+    tree.into_iter()
+        .filter(|&pid| proc_stat(pid).is_some_and(|p| p.state != 'Z'))
+        .map(|pid| Orphan {
+            pid,
+            command: proc_cmdline(pid),
+        })
+        .collect()
+}
+
+/// Warn about processes that survived stopping `owner`.
+pub fn report_orphans(owner: &str, orphans: &[Orphan]) {
+    if orphans.is_empty() {
+        return;
+    }
+    terminal::log_warn(&format!(
+        "{} process(es) started by {} are still running and must be stopped by hand:",
+        orphans.len(),
+        owner
+    ));
+    for orphan in orphans {
+        eprintln!("    pid {}: {}", orphan.pid, orphan.command);
+    }
+}
+
+/// Whether any non-zombie process is left in group `pgid`.
+#[cfg(unix)]
+pub fn group_running(pgid: u32) -> bool {
+    if unsafe { libc::killpg(pgid as libc::pid_t, 0) } != 0 {
+        return false;
+    }
+    // killpg also counts zombies; on Linux look for a member that still runs
+    !cfg!(target_os = "linux") || processes().iter().any(|p| p.pgrp == pgid && p.state != 'Z')
+}
+
+/// CPU time a process tree must use within one sample, as a fraction of the
+/// sample's length, to count as working.
+const ACTIVE_CPU_SHARE: f64 = 0.02;
+/// Bytes a process tree must read or write within one sample to count as working.
+const ACTIVE_IO_BYTES: u64 = 64 * 1024;
+
+/// What showed a silent process tree was still working.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activity {
+    Cpu(Duration),
+    Io(u64),
+}
+
+impl std::fmt::Display for Activity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Activity::Cpu(used) => write!(f, "{:.1}s of CPU", used.as_secs_f64()),
+            Activity::Io(bytes) => write!(f, "{} KB of I/O", bytes / 1024),
+        }
+    }
+}
+
+/// Samples CPU time and I/O of a process tree (the leader, its descendants and
+/// its process group) through `/proc`. Without `/proc` no activity is seen.
+pub struct ActivityMonitor {
+    root: u32,
+    last: (Duration, u64),
+    at: Instant,
+}
+
+impl ActivityMonitor {
+    pub fn new(root: u32) -> Self {
+        ActivityMonitor {
+            root,
+            last: tree_usage(root),
+            at: Instant::now(),
+        }
+    }
+
+    /// Activity since the previous sample, if enough to count as working.
+    /// CPU wins over I/O when both are present.
+    pub fn sample(&mut self) -> Option<Activity> {
+        let (cpu, io) = tree_usage(self.root);
+        let window = self.at.elapsed();
+        // Totals can drop when a finished child is not reaped into the tree
+        let cpu_used = cpu.saturating_sub(self.last.0);
+        let io_used = io.saturating_sub(self.last.1);
+        self.last = (cpu, io);
+        self.at = Instant::now();
+        if cpu_used.as_secs_f64() >= window.as_secs_f64() * ACTIVE_CPU_SHARE && !cpu_used.is_zero()
+        {
+            Some(Activity::Cpu(cpu_used))
+        } else if io_used >= ACTIVE_IO_BYTES {
+            Some(Activity::Io(io_used))
+        } else {
+            None
+        }
+    }
+}
+
+/// Total CPU time (including reaped children) and bytes read and written by
+/// the tree rooted at `root`.
+fn tree_usage(root: u32) -> (Duration, u64) {
+    let procs = processes();
+    let mut members = descendants_in(&procs, root);
+    members.push(root);
+    members.extend(procs.iter().filter(|p| p.pgrp == root).map(|p| p.pid));
+    members.sort_unstable();
+    members.dedup();
+
+    let tick = 1.0 / clock_ticks() as f64;
+    let mut cpu = 0.0;
+    let mut io = 0;
+    for pid in members {
+        if let Some(p) = procs.iter().find(|p| p.pid == pid) {
+            cpu += p.cpu_ticks as f64 * tick;
+        }
+        io += proc_io_bytes(pid);
+    }
+    (Duration::from_secs_f64(cpu), io)
+}
+
+#[cfg(unix)]
+fn clock_ticks() -> u64 {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        t if t > 0 => t as u64,
+        _ => 100,
+    }
+}
+
+#[cfg(not(unix))]
+fn clock_ticks() -> u64 {
+    100
+}
+
+/// `rchar + wchar` from `/proc/<pid>/io`.
+fn proc_io_bytes(pid: u32) -> u64 {
+    let Ok(io) = std::fs::read_to_string(format!("/proc/{}/io", pid)) else {
+        return 0;
+    };
+    io.lines()
+        .filter_map(|l| l.split_once(": "))
+        .filter(|(key, _)| matches!(*key, "rchar" | "wchar"))
+        .filter_map(|(_, value)| value.trim().parse::<u64>().ok())
+        .sum()
+}
+
+/// The fields of `/proc/<pid>/stat` lisa cares about.
+#[derive(Debug, Clone)]
+struct ProcStat {
+    pid: u32,
+    state: char,
+    ppid: u32,
+    pgrp: u32,
+    /// utime + stime + cutime + cstime, in clock ticks.
+    cpu_ticks: u64,
+}
+
+fn proc_stat(pid: u32) -> Option<ProcStat> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces and parentheses; fields follow the last ')'
+    let rest = stat.get(stat.rfind(')')? + 1..)?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let num = |i: usize| fields.get(i)?.parse::<u64>().ok();
+    Some(ProcStat {
+        pid,
+        state: fields.first()?.chars().next()?,
+        ppid: num(1)? as u32,
+        pgrp: num(2)? as u32,
+        cpu_ticks: (11..=14).filter_map(num).sum(),
+    })
+}
+
+fn proc_cmdline(pid: u32) -> String {
+    std::fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|raw| {
+            String::from_utf8_lossy(&raw)
+                .replace('\0', " ")
+                .trim()
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Every process visible in `/proc` (empty where there is no `/proc`).
+fn processes() -> Vec<ProcStat> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok()?.file_name().to_str()?.parse().ok())
+        .filter_map(proc_stat)
+        .collect()
+}
+
+/// All descendants of `root`, not including `root` itself.
+fn descendants(root: u32) -> Vec<u32> {
+    descendants_in(&processes(), root)
+}
+
+fn descendants_in(procs: &[ProcStat], root: u32) -> Vec<u32> {
+    let mut found = vec![root];
+    let mut i = 0;
+    while i < found.len() {
+        let parent = found[i];
+        found.extend(
+            procs
+                .iter()
+                .filter(|p| p.ppid == parent && !found.contains(&p.pid))
+                .map(|p| p.pid)
+                .collect::<Vec<_>>(),
+        );
+        i += 1;
+    }
+    found.remove(0);
+    found
+}
+
+/// Keeps the first and last `cap / 2` bytes written and counts what was dropped.
+struct CappedBuffer {
+    cap: usize,
+    head: Vec<u8>,
+    tail: std::collections::VecDeque<u8>,
+    dropped: usize,
+}
+
+impl CappedBuffer {
+    fn new(cap: usize) -> Self {
+        CappedBuffer {
+            cap,
+            head: Vec::new(),
+            tail: std::collections::VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, mut data: &[u8]) {
+        let head_cap = self.cap / 2;
+        if self.head.len() < head_cap {
+            let n = data.len().min(head_cap - self.head.len());
+            self.head.extend_from_slice(&data[..n]);
+            data = &data[n..];
+        }
+        let tail_cap = self.cap - head_cap;
+        self.tail.extend(data);
+        if self.tail.len() > tail_cap {
+            let excess = self.tail.len() - tail_cap;
+            self.tail.drain(..excess);
+            self.dropped += excess;
+        }
+    }
+
+    fn into_string(self) -> String {
+        let mut out = String::from_utf8_lossy(&self.head).to_string();
+        if self.dropped > 0 {
+            out.push_str(&format!(
+                "\n[... {} bytes of output omitted ...]\n",
+                self.dropped
+            ));
+        }
+        let tail: Vec<u8> = self.tail.into_iter().collect();
+        out.push_str(&String::from_utf8_lossy(&tail));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_success_and_failure() {
+        let ok = run("echo hello", None, None, 1024).unwrap();
+        assert!(ok.success());
+        assert_eq!(ok.stdout, "hello\n");
+        assert_eq!(ok.failure_output(), None);
+
+        let failed = run("echo out; echo err >&2; exit 3", None, None, 1024).unwrap();
+        assert_eq!(failed.status, Status::Failed(Some(3)));
+        assert_eq!(failed.status.describe(), "failed (exit 3)");
+        assert_eq!(failed.failure_output().unwrap(), "out\nerr");
+    }
+
+    #[test]
+    fn test_timeout_kills_process_group() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("survived");
+        // The background child would outlive a plain kill of the shell
+        let cmd = format!(
+            "echo started; (sleep 2; touch {}) & sleep 30",
+            marker.display()
+        );
+        let started = Instant::now();
+        let result = run(&cmd, None, Some(Duration::from_millis(300)), 1024).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(result.status, Status::TimedOut(Duration::from_millis(300)));
+        let report = result.failure_output().unwrap();
+        assert!(report.starts_with("TIMED OUT: hung after 0s"), "{}", report);
+        assert!(report.contains("started"));
+
+        std::thread::sleep(Duration::from_secs(3));
+        assert!(!marker.exists(), "background process survived the timeout");
+    }
+
+    #[test]
+    fn test_output_is_capped() {
+        let result = run("seq 1 100000", None, None, 1000).unwrap();
+        assert!(result.success());
+        assert!(result.stdout.starts_with("1\n2\n"));
+        assert!(result.stdout.ends_with("99999\n100000\n"));
+        assert!(result.stdout.contains("bytes of output omitted"));
+        assert!(result.stdout.len() < 1100);
+        assert!(result.stdout_truncated);
+        assert!(
+            !run("echo hello", None, None, 1000)
+                .unwrap()
+                .stdout_truncated
+        );
+    }
+
+    fn spawn_group(script: &str) -> std::process::Child {
+        use std::os::unix::process::CommandExt;
+        let child = Command::new("bash")
+            .args(["-c", script])
+            .process_group(0)
+            .spawn()
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(300));
+        child
+    }
+
+    #[test]
+    fn test_terminate_group_escalates_to_sigkill() {
+        // The shell and its child ignore SIGTERM
+        let mut child = spawn_group("trap '' TERM; sleep 30 & wait");
+        let pgid = child.id();
+        assert!(group_running(pgid));
+        let started = Instant::now();
+        let orphans = terminate_group(pgid, Duration::from_millis(200));
+        assert!(orphans.is_empty(), "{:?}", orphans);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let _ = child.wait();
+        assert!(!group_running(pgid));
+    }
+
+    #[test]
+    fn test_terminate_group_reports_escaped_descendants() {
+        let mut child = spawn_group("setsid sleep 31 & sleep 30");
+        let orphans = terminate_group(child.id(), Duration::from_secs(2));
+        let _ = child.wait();
+        for orphan in &orphans {
+            unsafe {
+                libc::kill(orphan.pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+        assert_eq!(orphans.len(), 1, "{:?}", orphans);
+        assert_eq!(orphans[0].command, "sleep 31");
+    }
+
+    #[test]
+    fn test_activity_monitor_sees_cpu_in_descendants() {
+        // The work happens in a grandchild; the leader only waits
+        let mut busy = spawn_group("bash -c 'while :; do :; done' & wait");
+        let mut monitor = ActivityMonitor::new(busy.id());
+        std::thread::sleep(Duration::from_millis(500));
+        let activity = monitor.sample();
+        terminate_group(busy.id(), Duration::from_millis(200));
+        let _ = busy.wait();
+        assert!(matches!(activity, Some(Activity::Cpu(_))), "{:?}", activity);
+
+        let mut idle = spawn_group("sleep 30");
+        let mut monitor = ActivityMonitor::new(idle.id());
+        std::thread::sleep(Duration::from_millis(500));
+        let activity = monitor.sample();
+        terminate_group(idle.id(), Duration::from_millis(200));
+        let _ = idle.wait();
+        assert_eq!(activity, None);
+    }
+
+    #[test]
+    fn test_runs_in_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("here.txt"), "x").unwrap();
+        let result = run("ls", Some(dir.path()), None, 1024).unwrap();
+        assert!(result.stdout.contains("here.txt"));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::Config;
+use crate::config::{Config, ModelSpec};
 use crate::terminal;
 use anyhow::Result;
 use std::path::Path;
@@ -76,21 +76,25 @@ pub enum Phase {
 }
 
 impl Phase {
-    pub fn model_key(&self, config: &Config) -> String {
-        match self {
+    /// The models to run this phase on, in fallback order.
+    pub fn models(&self, config: &Config) -> Vec<ModelSpec> {
+        let chain = match self {
             Phase::Init
             | Phase::Scope
             | Phase::Research
             | Phase::ValidationDesign
-            | Phase::Planning => config.models.scope.clone(),
-            Phase::Refine | Phase::RefineMethodology | Phase::RefinePlan => {
-                config.models.refine.clone()
-            }
-            Phase::Bounds => config.models.bounds.clone(),
-            Phase::Build => config.models.build.clone(),
-            Phase::Audit | Phase::Finalize => config.models.audit.clone(),
-            Phase::Explore => config.models.scope.clone(),
-        }
+            | Phase::Planning => &config.models.scope,
+            Phase::Refine | Phase::RefineMethodology | Phase::RefinePlan => &config.models.refine,
+            Phase::Bounds => &config.models.bounds,
+            Phase::Build => &config.models.build,
+            Phase::Audit | Phase::Finalize => &config.models.audit,
+            Phase::Explore => &config.models.scope,
+        };
+        chain
+            .models()
+            .iter()
+            .map(|m| ModelSpec::parse(m, config))
+            .collect()
     }
 }
 
@@ -243,13 +247,13 @@ pub fn build_context_preamble(
         ));
     }
 
-    // Idle timeout heartbeat guidance
+    // Idle timeout guidance
     ctx.push_str(&format!(
-        "\n### Heartbeat\n\
-         Lisa Loop will kill your process if no tool output is received for {} seconds.\n\
-         During long-running operations (compilation, test suites, complex analysis),\n\
-         emit periodic heartbeats by running: `echo \"[heartbeat] still working...\"`\n\
-         Do this every few minutes during operations that may take a while.\n",
+        "\n### Idle Timeout\n\
+         Lisa Loop will stop your process if for {} seconds it produces no output and\n\
+         neither it nor any process it started uses CPU or does I/O. Long compilations,\n\
+         test suites and simulations that are working are not affected; commands that\n\
+         block waiting for input or a network peer are.\n",
         config.limits.idle_timeout_secs
     ));
 
@@ -0,0 +1,606 @@
+//! Recording agent invocations, and replaying them instead of calling an agent.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::agent::AgentRequest;
+use crate::backend::{AgentBackend, AgentEvent, AgentProcess};
+use crate::snapshot::Snapshot;
+
+/// Metadata for one recorded invocation, stored as `meta.toml` in its directory.
+///
+/// A recording directory also holds `input.md` (the prompt), `stream.jsonl` (raw stdout),
+/// `stderr.txt`, and `effects/` (final contents of every file the agent created or changed,
+/// at the same relative path as in the project).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecordingMeta {
+    pub label: String,
+    pub backend: String,
+    pub model: String,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// The agent was killed by lisa (e.g. idle timeout) rather than exiting on its own.
+    #[serde(default)]
+    pub killed: bool,
+    /// Files created or modified, relative to the project root.
+    #[serde(default)]
+    pub changed: Vec<String>,
+    /// Files deleted, relative to the project root.
+    #[serde(default)]
+    pub deleted: Vec<String>,
+}
+
+/// Wraps another backend and saves every invocation under `.lisa/recordings/`.
+pub struct RecordingBackend {
+    inner: Box<dyn AgentBackend>,
+    paths: RecordingPaths,
+}
+
+#[derive(Clone)]
+struct RecordingPaths {
+    root: PathBuf,
+    project_root: PathBuf,
+    lisa_root: PathBuf,
+}
+
+impl RecordingPaths {
+    /// Project files plus `.lisa/`, excluding the recordings themselves.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::take(&self.project_root, &[&self.lisa_root], &[&self.root])
+    }
+
+    /// The same layout rooted at `working_dir`, for agents run outside the project root.
+    fn in_dir(&self, working_dir: Option<&Path>) -> RecordingPaths {
+        let Some(dir) = working_dir else {
+            return self.clone();
+        };
+        let lisa_rel = self
+            .lisa_root
+            .strip_prefix(&self.project_root)
+            .unwrap_or(&self.lisa_root);
+        RecordingPaths {
+            root: self.root.clone(),
+            project_root: dir.to_path_buf(),
+            lisa_root: dir.join(lisa_rel),
+        }
+    }
+}
+
+impl RecordingBackend {
+    pub fn new(
+        inner: Box<dyn AgentBackend>,
+        root: &Path,
+        project_root: &Path,
+        lisa_root: &Path,
+    ) -> Self {
+        Self {
+            inner,
+            paths: RecordingPaths {
+                root: root.to_path_buf(),
+                project_root: project_root.to_path_buf(),
+                lisa_root: lisa_root.to_path_buf(),
+            },
+        }
+    }
+}
+
+impl AgentBackend for RecordingBackend {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn spawn(&self, request: &AgentRequest) -> Result<Box<dyn AgentProcess>> {
+        let dir = next_recording_dir(&self.paths.root, request.label)?;
+        std::fs::write(dir.join("input.md"), request.input)?;
+
+        let meta = RecordingMeta {
+            label: request.label.to_string(),
+            backend: self.inner.name().to_string(),
+            model: request.model.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            ..Default::default()
+        };
+        let paths = self.paths.in_dir(request.working_dir);
+        let before = paths.snapshot();
+        let inner = self.inner.spawn(request)?;
+
+        Ok(Box::new(RecordingProcess {
+            inner,
+            dir,
+            meta,
+            before,
+            paths,
+        }))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<AgentEvent>> {
+        self.inner.parse_line(line)
+    }
+
+    fn supports_resume(&self) -> bool {
+        self.inner.supports_resume()
+    }
+}
+
+struct RecordingProcess {
+    inner: Box<dyn AgentProcess>,
+    dir: PathBuf,
+    meta: RecordingMeta,
+    before: Snapshot,
+    paths: RecordingPaths,
+}
+
+impl AgentProcess for RecordingProcess {
+    fn take_stdin(&mut self) -> Option<Box<dyn Write + Send>> {
+        self.inner.take_stdin()
+    }
+
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>> {
+        let pipe = self.inner.take_stdout()?;
+        tee(pipe, &self.dir.join("stream.jsonl"))
+    }
+
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>> {
+        let pipe = self.inner.take_stderr()?;
+        tee(pipe, &self.dir.join("stderr.txt"))
+    }
+
+    fn kill(&mut self) -> Result<()> {
+        self.meta.killed = true;
+        self.inner.kill()
+    }
+
+    fn wait(&mut self) -> Result<Option<i32>> {
+        let code = self.inner.wait()?;
+        self.meta.exit_code = code;
+
+        let diff = self.before.diff(&self.paths.snapshot());
+        let effects = self.dir.join("effects");
+        for rel in &diff.changed {
+            let dest = effects.join(rel);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(self.paths.project_root.join(rel), &dest)
+                .with_context(|| format!("Failed to record effect {}", rel.display()))?;
+        }
+        self.meta.changed = diff.changed.iter().map(|p| path_key(p)).collect();
+        self.meta.deleted = diff.deleted.iter().map(|p| path_key(p)).collect();
+
+        std::fs::write(
+            self.dir.join("meta.toml"),
+            toml::to_string_pretty(&self.meta)?,
+        )?;
+        Ok(code)
+    }
+
+    fn pid(&self) -> Option<u32> {
+        self.inner.pid()
+    }
+}
+
+/// Copy everything read from `pipe` into `path` as it streams.
+fn tee(pipe: Box<dyn Read + Send>, path: &Path) -> Option<Box<dyn Read + Send>> {
+    match File::create(path) {
+        Ok(file) => Some(Box::new(TeeReader { pipe, file })),
+        Err(e) => {
+            crate::terminal::log_warn(&format!("Cannot record {}: {}", path.display(), e));
+            Some(pipe)
+        }
+    }
+}
+
+struct TeeReader {
+    pipe: Box<dyn Read + Send>,
+    file: File,
+}
+
+impl Read for TeeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.pipe.read(buf)?;
+        let _ = self.file.write_all(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Create `NNNN-<label-slug>`, numbered after the highest recording in `root`.
+/// Creation is exclusive, so a number that is already taken is skipped.
+fn next_recording_dir(root: &Path, label: &str) -> Result<PathBuf> {
+    std::fs::create_dir_all(root)
+        .with_context(|| format!("Failed to create {}", root.display()))?;
+    let highest = std::fs::read_dir(root)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.split('-').next()?.parse::<u32>().ok()
+        })
+        .max()
+        .unwrap_or(0);
+    let slug = slugify(label);
+    for n in highest + 1.. {
+        let dir = root.join(format!("{:04}-{}", n, slug));
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("Failed to create {}", dir.display())),
+        }
+    }
+    unreachable!("recording numbers exhausted")
+}
+
+fn slugify(label: &str) -> String {
+    let mut slug = String::new();
+    for c in label.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Recorded paths always use `/`, so recordings made on one OS replay on another.
+fn path_key(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Plays back recordings instead of calling an agent.
+///
+/// Invocations are matched by label. The n-th invocation of a label replays the
+/// n-th recording of that label; once those run out, the last one is repeated.
+#[derive(Debug)]
+pub struct ReplayBackend {
+    dir: PathBuf,
+    project_root: PathBuf,
+    by_label: BTreeMap<String, Vec<(PathBuf, RecordingMeta)>>,
+    /// How many recordings of each label this backend has replayed.
+    cursor: Mutex<BTreeMap<String, usize>>,
+}
+
+/// The replay backend of one run, created on first use. Clones share it, so
+/// every invocation of the run advances the same cursor.
+#[derive(Debug, Clone, Default)]
+pub struct ReplaySlot(Arc<OnceLock<Arc<ReplayBackend>>>);
+
+impl ReplaySlot {
+    pub fn get_or_load(&self, dir: &Path, project_root: &Path) -> Result<Arc<ReplayBackend>> {
+        if let Some(backend) = self.0.get() {
+            return Ok(backend.clone());
+        }
+        let backend = Arc::new(ReplayBackend::new(dir, project_root)?);
+        Ok(self.0.get_or_init(|| backend).clone())
+    }
+}
+
+impl ReplayBackend {
+    pub fn new(dir: &Path, project_root: &Path) -> Result<Self> {
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+            .with_context(|| format!("Cannot read recordings in {}", dir.display()))?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.join("meta.toml").exists())
+            .collect();
+        entries.sort();
+
+        let mut by_label: BTreeMap<String, Vec<(PathBuf, RecordingMeta)>> = BTreeMap::new();
+        for entry in entries {
+            let content = std::fs::read_to_string(entry.join("meta.toml"))?;
+            let meta: RecordingMeta = toml::from_str(&content)
+                .with_context(|| format!("Invalid {}", entry.join("meta.toml").display()))?;
+            by_label
+                .entry(meta.label.clone())
+                .or_default()
+                .push((entry, meta));
+        }
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            project_root: project_root.to_path_buf(),
+            by_label,
+            cursor: Mutex::new(BTreeMap::new()),
+        })
+    }
+}
+
+impl AgentBackend for ReplayBackend {
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn spawn(&self, request: &AgentRequest) -> Result<Box<dyn AgentProcess>> {
+        let recordings = self.by_label.get(request.label).with_context(|| {
+            format!(
+                "No recording for agent '{}' in {}",
+                request.label,
+                self.dir.display()
+            )
+        })?;
+        let index = {
+            let mut cursor = self.cursor.lock().unwrap();
+            let n = cursor.entry(request.label.to_string()).or_insert(0);
+            let index = (*n).min(recordings.len() - 1);
+            *n += 1;
+            index
+        };
+        let (dir, meta) = &recordings[index];
+
+        let stdout = std::fs::read(dir.join("stream.jsonl")).unwrap_or_default();
+        let stderr = std::fs::read(dir.join("stderr.txt")).unwrap_or_default();
+        Ok(Box::new(ReplayProcess {
+            stdout: Some(stdout),
+            stderr: Some(stderr),
+            dir: dir.clone(),
+            meta: meta.clone(),
+            project_root: request
+                .working_dir
+                .unwrap_or(&self.project_root)
+                .to_path_buf(),
+        }))
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<AgentEvent>> {
+        crate::backend::parse_stream_json(line)
+    }
+}
+
+impl AgentBackend for Arc<ReplayBackend> {
+    fn name(&self) -> &str {
+        self.as_ref().name()
+    }
+
+    fn spawn(&self, request: &AgentRequest) -> Result<Box<dyn AgentProcess>> {
+        self.as_ref().spawn(request)
+    }
+
+    fn parse_line(&self, line: &str) -> Result<Vec<AgentEvent>> {
+        self.as_ref().parse_line(line)
+    }
+}
+
+struct ReplayProcess {
+    stdout: Option<Vec<u8>>,
+    stderr: Option<Vec<u8>>,
+    dir: PathBuf,
+    meta: RecordingMeta,
+    /// Where effects are applied: the working directory of the request, if any.
+    project_root: PathBuf,
+}
+
+impl ReplayProcess {
+    /// Reproduce the recorded file-system effects in the project.
+    fn apply_effects(&self) -> Result<()> {
+        for rel in &self.meta.changed {
+            let dest = self.project_root.join(rel);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::copy(self.dir.join("effects").join(rel), &dest)
+                .with_context(|| format!("Failed to replay effect {}", rel))?;
+        }
+        for rel in &self.meta.deleted {
+            let _ = std::fs::remove_file(self.project_root.join(rel));
+        }
+        Ok(())
+    }
+}
+
+impl AgentProcess for ReplayProcess {
+    fn take_stdin(&mut self) -> Option<Box<dyn Write + Send>> {
+        Some(Box::new(std::io::sink()))
+    }
+
+    fn take_stdout(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.stdout
+            .take()
+            .map(|b| Box::new(Cursor::new(b)) as Box<dyn Read + Send>)
+    }
+
+    fn take_stderr(&mut self) -> Option<Box<dyn Read + Send>> {
+        self.stderr
+            .take()
+            .map(|b| Box::new(Cursor::new(b)) as Box<dyn Read + Send>)
+    }
+
+    fn kill(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn wait(&mut self) -> Result<Option<i32>> {
+        self.apply_effects()?;
+        Ok(if self.meta.killed {
+            None
+        } else {
+            self.meta.exit_code
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::run_agent;
+
+    const STREAM: &str = concat!(
+        r#"{"type":"assistant","message":{"content":[{"type":"tool_use","name":"Write","input":{"file_path":"out.txt"}}]}}"#,
+        "\n",
+        r#"{"type":"result","result":"wrote out.txt","total_cost_usd":0.5,"usage":{"input_tokens":10,"output_tokens":3}}"#,
+        "\n",
+    );
+
+    /// Stands in for a real agent: writes `out.txt`, deletes `gone.txt`, emits `STREAM`.
+    struct ScriptedBackend {
+        root: PathBuf,
+    }
+
+    impl AgentBackend for ScriptedBackend {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn spawn(&self, _request: &AgentRequest) -> Result<Box<dyn AgentProcess>> {
+            std::fs::write(self.root.join("out.txt"), "agent output")?;
+            std::fs::remove_file(self.root.join("gone.txt"))?;
+            Ok(Box::new(ReplayProcess {
+                stdout: Some(STREAM.as_bytes().to_vec()),
+                stderr: Some(Vec::new()),
+                dir: self.root.clone(),
+                meta: RecordingMeta {
+                    exit_code: Some(0),
+                    ..Default::default()
+                },
+                project_root: self.root.clone(),
+            }))
+        }
+
+        fn parse_line(&self, line: &str) -> Result<Vec<AgentEvent>> {
+            crate::backend::parse_stream_json(line)
+        }
+    }
+
+    fn request<'a>(label: &'a str) -> AgentRequest<'a> {
+        AgentRequest {
+            input: "do the task",
+            model: "sonnet",
+            label,
+            collapse_output: true,
+            error_log_path: None,
+            idle_timeout_secs: 30,
+            transcript_path: None,
+            working_dir: None,
+            permissions: None,
+            limits: Default::default(),
+            price: None,
+            resume_session: None,
+            session: None,
+        }
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        let label = "Build: task 1 iter 1";
+
+        // Record
+        let recorded = tempfile::tempdir().unwrap();
+        let root = recorded.path();
+        let lisa_root = root.join(".lisa");
+        let recordings = lisa_root.join("recordings");
+        std::fs::create_dir_all(&lisa_root).unwrap();
+        std::fs::write(root.join("gone.txt"), "old").unwrap();
+
+        let backend = RecordingBackend::new(
+            Box::new(ScriptedBackend {
+                root: root.to_path_buf(),
+            }),
+            &recordings,
+            root,
+            &lisa_root,
+        );
+        let result = run_agent(&backend, &request(label)).unwrap();
+        assert_eq!(result.result_text, "wrote out.txt");
+
+        let dir = recordings.join("0001-build-task-1-iter-1");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("input.md")).unwrap(),
+            "do the task"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("stream.jsonl")).unwrap(),
+            STREAM
+        );
+        let meta: RecordingMeta =
+            toml::from_str(&std::fs::read_to_string(dir.join("meta.toml")).unwrap()).unwrap();
+        assert_eq!(meta.label, label);
+        assert_eq!(meta.backend, "scripted");
+        assert_eq!(meta.exit_code, Some(0));
+        assert_eq!(meta.changed, vec!["out.txt"]);
+        assert_eq!(meta.deleted, vec!["gone.txt"]);
+
+        // Replay into a fresh project
+        let replayed = tempfile::tempdir().unwrap();
+        let root2 = replayed.path();
+        std::fs::write(root2.join("gone.txt"), "old").unwrap();
+        let replay = ReplayBackend::new(&recordings, root2).unwrap();
+        let result = run_agent(&replay, &request(label)).unwrap();
+        assert_eq!(result.result_text, "wrote out.txt");
+        assert_eq!(result.tool_log.len(), 1);
+        assert!((result.usage.cost_usd - 0.5).abs() < 1e-9);
+        assert_eq!(
+            std::fs::read_to_string(root2.join("out.txt")).unwrap(),
+            "agent output"
+        );
+        assert!(!root2.join("gone.txt").exists());
+
+        // Unknown labels are an error, not a live call
+        assert!(run_agent(&replay, &request("Audit: pass 9")).is_err());
+    }
+
+    #[test]
+    fn test_replay_cursor_is_per_backend() {
+        let recorded = tempfile::tempdir().unwrap();
+        let root = recorded.path();
+        let recordings = root.join("recordings");
+        for text in ["first", "second"] {
+            let dir = next_recording_dir(&recordings, "Audit: pass 1").unwrap();
+            std::fs::write(
+                dir.join("stream.jsonl"),
+                format!("{{\"type\":\"result\",\"result\":\"{}\"}}\n", text),
+            )
+            .unwrap();
+            std::fs::write(
+                dir.join("meta.toml"),
+                "label = \"Audit: pass 1\"\nbackend = \"claude\"\nmodel = \"opus\"\ntimestamp = \"\"\nexit_code = 0\n",
+            )
+            .unwrap();
+        }
+
+        let slot = ReplaySlot::default();
+        let shared = slot.get_or_load(&recordings, root).unwrap();
+        let run = |backend: &dyn AgentBackend| {
+            run_agent(backend, &request("Audit: pass 1"))
+                .unwrap()
+                .result_text
+        };
+        assert_eq!(run(&shared), "first");
+        assert_eq!(
+            run(&slot.clone().get_or_load(&recordings, root).unwrap()),
+            "second"
+        );
+        // A separate replay starts from the first recording again
+        let other = ReplayBackend::new(&recordings, root).unwrap();
+        assert_eq!(run(&other), "first");
+    }
+
+    #[test]
+    fn test_recording_dirs_are_never_reused() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        assert!(next_recording_dir(root, "Build")
+            .unwrap()
+            .ends_with("0001-build"));
+        std::fs::create_dir(root.join("0003-audit")).unwrap();
+        assert!(next_recording_dir(root, "Build")
+            .unwrap()
+            .ends_with("0004-build"));
+        std::fs::remove_dir(root.join("0001-build")).unwrap();
+        assert!(next_recording_dir(root, "Build")
+            .unwrap()
+            .ends_with("0005-build"));
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Build: task 3 iter 2"), "build-task-3-iter-2");
+        assert_eq!(slugify("Explore: pass 1 #2"), "explore-pass-1-2");
+        assert_eq!(slugify("Finalize: output"), "finalize-output");
+    }
+}
@@ -1,15 +1,25 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::style::Color;
+use std::cell::RefCell;
 use std::io::{self, Write};
 use std::path::Path;
+use std::sync::mpsc;
 
+use crate::agent::LimitBreach;
 use crate::config::Config;
+use crate::interrupt;
 use crate::terminal;
+use crate::testresults::{self, TestResults};
+use crate::usage;
+use crate::writescope;
+
+/// Phases a pass's results hinge on; running them on a fallback model is flagged.
+const CRITICAL_PHASES: &[&str] = &["bounds", "audit"];
 
 /// Read a line from stdin, returning an error on EOF.
 /// Prevents infinite loops when stdin is closed or piped from an empty source.
 fn read_stdin_line(buf: &mut String) -> Result<()> {
-    let n = io::stdin().read_line(buf)?;
+    let n = interrupt::at_prompt(|| io::stdin().read_line(buf))?;
     if n == 0 {
         anyhow::bail!(
             "EOF on stdin — cannot read interactive input (is stdin connected to a terminal?)"
@@ -18,6 +28,37 @@ fn read_stdin_line(buf: &mut String) -> Result<()> {
     Ok(())
 }
 
+/// A gate a build lane reached, to be shown by the thread serving the lanes.
+pub type Prompt = Box<dyn FnOnce() + Send>;
+
+thread_local! {
+    static PROMPTS: RefCell<Option<mpsc::Sender<Prompt>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` with the gates this thread reaches sent to `prompts`, so that
+/// parallel build lanes ask the human one at a time, from the main thread.
+pub fn forward_prompts<T>(prompts: mpsc::Sender<Prompt>, f: impl FnOnce() -> T) -> T {
+    let previous = PROMPTS.with(|p| p.replace(Some(prompts)));
+    let result = f();
+    PROMPTS.with(|p| *p.borrow_mut() = previous);
+    result
+}
+
+/// Run the interactive part of a gate here, or on the thread this one
+/// forwards its prompts to, and wait for the decision.
+fn ask<T: Send + 'static>(prompt: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    let Some(prompts) = PROMPTS.with(|p| p.borrow().clone()) else {
+        return prompt();
+    };
+    let (reply, decision) = mpsc::channel();
+    prompts
+        .send(Box::new(move || {
+            let _ = reply.send(prompt());
+        }))
+        .map_err(|_| anyhow::anyhow!("No one is left to answer the gate"))?;
+    decision.recv().context("The gate was not answered")?
+}
+
 /// Show a file path to the user and wait for them to press Enter after editing.
 ///
 /// This replaces the old $EDITOR spawning pattern — users can edit with whatever
@@ -66,6 +107,13 @@ pub enum BlockDecision {
     Abort,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum TamperDecision {
+    Restore,
+    Accept,
+    Abort,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum RefineDecision {
     Approve,
@@ -94,6 +142,21 @@ pub enum BudgetDecision {
     Stop,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum SessionDecision {
+    /// Continue the saved agent session where it stopped.
+    Continue,
+    /// Start the invocation over with the full prompt.
+    Fresh,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LimitDecision {
+    /// Rerun the phase, told to take on less.
+    RetryNarrower,
+    Stop,
+}
+
 /// Methodology review gate — after Research phase, before Validation Design.
 /// Reviews methodology choice, acceptance criteria, and stack selection.
 pub fn methodology_review_gate(config: &Config, lisa_root: &Path) -> Result<MethodologyDecision> {
@@ -449,8 +512,25 @@ pub fn refine_review_gate(config: &Config, pass: u32, lisa_root: &Path) -> Resul
 }
 
 /// Pass review gate — after each pass's audit phase
-pub fn review_gate(config: &Config, pass: u32, lisa_root: &Path) -> Result<ReviewDecision> {
+pub fn review_gate(
+    config: &Config,
+    pass: u32,
+    project_root: &Path,
+    lisa_root: &Path,
+) -> Result<ReviewDecision> {
     if !config.review.pause {
+        if writescope::display_pending(lisa_root) {
+            terminal::log_warn(&format!(
+                "Out-of-scope writes kept (pause = false) — see {}/write-scope.toml",
+                lisa_root.display()
+            ));
+        }
+        if display_fallbacks(lisa_root, pass) {
+            terminal::log_warn(&format!(
+                "Pass {} ran critical phases on a fallback model (pause = false)",
+                pass
+            ));
+        }
         terminal::log_warn("Review gate skipped (pause = false) — defaulting to CONTINUE");
         return Ok(ReviewDecision::Continue);
     }
@@ -468,7 +548,7 @@ pub fn review_gate(config: &Config, pass: u32, lisa_root: &Path) -> Result<Revie
     let review_path = lisa_root.join(format!("spiral/pass-{}/review-package.md", pass));
     if review_path.exists() {
         if let Ok(content) = std::fs::read_to_string(&review_path) {
-            display_review_summary(&content, pass);
+            display_review_summary(&content, testresults::load(lisa_root, pass).as_ref());
         }
     } else {
         terminal::print_colored(
@@ -496,6 +576,9 @@ pub fn review_gate(config: &Config, pass: u32, lisa_root: &Path) -> Result<Revie
     );
     println!();
 
+    display_fallbacks(lisa_root, pass);
+    let mut has_violations = writescope::display_pending(lisa_root);
+
     terminal::print_colored("  [F]", Color::Green);
     println!(" FINALIZE — results are satisfactory, produce the final report");
     terminal::print_colored("  [C]", Color::Yellow);
@@ -506,12 +589,20 @@ pub fn review_gate(config: &Config, pass: u32, lisa_root: &Path) -> Result<Revie
     println!(" EXPLORE  — create a side-branch to investigate an alternative approach");
     terminal::print_colored("  [Q]", Color::Red);
     println!(" QUIT     — stop the spiral here (resume later with `lisa resume`)");
+    if has_violations {
+        terminal::print_colored("  [V]", Color::Red);
+        println!(" REVERT   — undo the out-of-scope writes listed above");
+    }
     println!();
     terminal::print_separator();
     println!();
 
     loop {
-        print!("  Your choice [F/C/R/E/Q]: ");
+        if has_violations {
+            print!("  Your choice [F/C/R/E/Q/V]: ");
+        } else {
+            print!("  Your choice [F/C/R/E/Q]: ");
+        }
         io::stdout().flush()?;
         let mut choice = String::new();
         read_stdin_line(&mut choice)?;
@@ -577,6 +668,11 @@ pub fn review_gate(config: &Config, pass: u32, lisa_root: &Path) -> Result<Revie
                 terminal::log_info("EXPLORE — creating a side-branch for investigation.");
                 return Ok(ReviewDecision::Explore);
             }
+            "V" if has_violations => {
+                writescope::revert_pending(config, project_root)?;
+                has_violations = false;
+            }
+            _ if has_violations => println!("  Please enter F, C, R, E, Q, or V."),
             _ => println!("  Please enter F, C, R, E, or Q."),
         }
     }
@@ -674,29 +770,18 @@ pub fn block_gate(
     println!();
 
     // Show blocked task names
-    if plan_path.exists() {
-        if let Ok(content) = std::fs::read_to_string(plan_path) {
-            println!("  Blocked tasks:");
-            let mut current_task = String::new();
-            let mut is_blocked = false;
-
-            for line in content.lines() {
-                if line.starts_with("### Task") {
-                    if is_blocked {
-                        println!("    • {}", current_task);
-                    }
-                    current_task = line.trim_start_matches("### ").to_string();
-                    is_blocked = false;
-                }
-                if line.contains("**Status:** BLOCKED") {
-                    is_blocked = true;
-                }
-            }
-            if is_blocked {
-                println!("    • {}", current_task);
-            }
-            println!();
+    let plan = crate::tasks::Plan::load(plan_path)?;
+    let blocked_tasks: Vec<_> = plan
+        .tasks
+        .iter()
+        .filter(|t| t.status == crate::tasks::Status::Blocked)
+        .collect();
+    if !blocked_tasks.is_empty() {
+        println!("  Blocked tasks:");
+        for task in blocked_tasks {
+            println!("    • Task {}: {}", task.number, task.name);
         }
+        println!();
     }
 
     // Show reconsideration files if any exist
@@ -736,7 +821,7 @@ pub fn block_gate(
         match choice.trim().to_uppercase().as_str() {
             "F" => {
                 wait_for_edit(
-                    "FIX — Edit the plan to resolve blocked tasks, then press Enter.",
+                    "FIX — Edit the plan (or use `lisa tasks unblock <task>`) to resolve blocked tasks, then press Enter.",
                     plan_path,
                 );
 
@@ -766,6 +851,81 @@ pub fn block_gate(
     }
 }
 
+/// Bounding-test tamper gate — the Build agent modified the bounding tests.
+pub fn tamper_gate(
+    config: &Config,
+    label: &str,
+    summary: &str,
+    diff: &str,
+    diff_path: &Path,
+) -> Result<TamperDecision> {
+    if !config.review.pause {
+        terminal::log_warn("Tamper gate skipped (pause = false) — defaulting to RESTORE");
+        return Ok(TamperDecision::Restore);
+    }
+
+    let (label, summary, diff, diff_path) = (
+        label.to_string(),
+        summary.to_string(),
+        diff.to_string(),
+        diff_path.to_path_buf(),
+    );
+    ask(move || {
+        println!();
+        terminal::print_separator();
+        terminal::print_colored("  ", Color::Red);
+        terminal::println_bold("BOUNDING TESTS MODIFIED BY BUILD");
+        terminal::print_separator();
+        println!();
+        println!(
+            "  {} changed the independently derived bounding tests:",
+            label
+        );
+        println!("{}", summary);
+        println!();
+        for line in diff.lines() {
+            let color = match line.chars().next() {
+                Some('+') => Color::Green,
+                Some('-') => Color::Red,
+                _ => Color::White,
+            };
+            terminal::print_colored(&format!("    {}\n", line), color);
+        }
+        println!();
+        println!("  Full diff: {}", diff_path.display());
+        println!();
+
+        terminal::print_colored("  [R]", Color::Green);
+        println!(" RESTORE — put the original bounding tests back and continue the build");
+        terminal::print_colored("  [A]", Color::Yellow);
+        println!(" ACCEPT  — keep the modified tests (recorded in the audit trail)");
+        terminal::print_colored("  [X]", Color::Red);
+        println!(" ABORT   — stop this spiral pass (resume later with `lisa resume`)");
+        println!();
+        terminal::print_separator();
+        println!();
+
+        loop {
+            print!("  Your choice [R/A/X]: ");
+            io::stdout().flush()?;
+            let mut choice = String::new();
+            read_stdin_line(&mut choice)?;
+            match choice.trim().to_uppercase().as_str() {
+                "R" => return Ok(TamperDecision::Restore),
+                "A" => {
+                    terminal::log_warn("ACCEPT — keeping the modified bounding tests.");
+                    return Ok(TamperDecision::Accept);
+                }
+                "X" => {
+                    terminal::log_error("ABORT — stopping spiral pass.");
+                    return Ok(TamperDecision::Abort);
+                }
+                _ => println!("  Please enter R, A, or X."),
+            }
+        }
+    })
+}
+
 /// Post-finalize confirmation gate
 pub fn finalize_gate(config: &Config, lisa_root: &Path, pass: u32) -> Result<FinalizeDecision> {
     if !config.review.pause {
@@ -841,37 +1001,167 @@ pub fn budget_gate(config: &Config, cumulative: f64, budget: f64) -> Result<Budg
         return Ok(BudgetDecision::Stop);
     }
 
-    println!();
-    terminal::print_separator();
-    terminal::print_colored("  ", Color::Red);
-    terminal::println_bold("BUDGET EXCEEDED");
-    terminal::print_separator();
-    println!();
-    terminal::print_colored("  Spent:  ", Color::White);
-    terminal::println_colored(&format!("${:.4}", cumulative), Color::Red);
-    terminal::print_colored("  Budget: ", Color::White);
-    println!("${:.2}", budget);
-    println!();
+    ask(move || {
+        println!();
+        terminal::print_separator();
+        terminal::print_colored("  ", Color::Red);
+        terminal::println_bold("BUDGET EXCEEDED");
+        terminal::print_separator();
+        println!();
+        terminal::print_colored("  Spent:  ", Color::White);
+        terminal::println_colored(&format!("${:.4}", cumulative), Color::Red);
+        terminal::print_colored("  Budget: ", Color::White);
+        println!("${:.2}", budget);
+        println!();
 
-    terminal::print_colored("  [C]", Color::Yellow);
-    println!(" CONTINUE — override the budget limit and keep going");
-    terminal::print_colored("  [S]", Color::Red);
-    println!(" STOP     — halt the spiral (resume later with `lisa resume`)");
-    println!();
-    terminal::print_separator();
-    println!();
+        terminal::print_colored("  [C]", Color::Yellow);
+        println!(" CONTINUE — override the budget limit and keep going");
+        terminal::print_colored("  [S]", Color::Red);
+        println!(" STOP     — halt the spiral (resume later with `lisa resume`)");
+        println!();
+        terminal::print_separator();
+        println!();
 
-    loop {
-        print!("  Choice: ");
-        io::stdout().flush()?;
-        let mut choice = String::new();
-        read_stdin_line(&mut choice)?;
-        match choice.trim().to_lowercase().as_str() {
-            "c" => return Ok(BudgetDecision::Continue),
-            "s" => return Ok(BudgetDecision::Stop),
-            _ => println!("  Invalid choice. Enter C or S."),
+        loop {
+            print!("  Choice: ");
+            io::stdout().flush()?;
+            let mut choice = String::new();
+            read_stdin_line(&mut choice)?;
+            match choice.trim().to_lowercase().as_str() {
+                "c" => return Ok(BudgetDecision::Continue),
+                "s" => return Ok(BudgetDecision::Stop),
+                _ => println!("  Invalid choice. Enter C or S."),
+            }
+        }
+    })
+}
+
+/// Invocation limit gate — an agent reached its `[limits.phase]` wall-clock or
+/// cost ceiling and was stopped. `retried` is set when the stopped run was
+/// already a narrower retry.
+pub fn limit_gate(
+    config: &Config,
+    label: &str,
+    breach: &LimitBreach,
+    retried: bool,
+) -> Result<LimitDecision> {
+    if !config.review.pause {
+        if retried {
+            terminal::log_warn("Limit gate skipped (pause = false) — narrower retry also stopped, defaulting to STOP");
+            return Ok(LimitDecision::Stop);
         }
+        terminal::log_warn(
+            "Limit gate skipped (pause = false) — defaulting to RETRY with a narrower scope",
+        );
+        return Ok(LimitDecision::RetryNarrower);
+    }
+
+    let (label, breach) = (label.to_string(), *breach);
+    ask(move || {
+        println!();
+        terminal::print_separator();
+        terminal::print_colored("  ", Color::Red);
+        terminal::println_bold("INVOCATION LIMIT REACHED");
+        terminal::print_separator();
+        println!();
+        terminal::print_colored("  Agent: ", Color::White);
+        println!("{}", label);
+        terminal::print_colored("  Limit: ", Color::White);
+        terminal::println_colored(&breach.to_string(), Color::Red);
+        if retried {
+            println!("  (this was already a retry with a narrower scope)");
+        }
+        println!();
+        println!("  The agent was stopped. Its changes so far are left in the working tree.");
+        println!();
+
+        terminal::print_colored("  [R]", Color::Yellow);
+        println!(" RETRY — rerun the phase, instructed to take on a smaller piece of the work");
+        terminal::print_colored("  [S]", Color::Red);
+        println!(" STOP  — halt the spiral (raise the limit in lisa.toml, then `lisa resume`)");
+        println!();
+        terminal::print_separator();
+        println!();
+
+        loop {
+            print!("  Choice: ");
+            io::stdout().flush()?;
+            let mut choice = String::new();
+            read_stdin_line(&mut choice)?;
+            match choice.trim().to_lowercase().as_str() {
+                "r" => return Ok(LimitDecision::RetryNarrower),
+                "s" => return Ok(LimitDecision::Stop),
+                _ => println!("  Invalid choice. Enter R or S."),
+            }
+        }
+    })
+}
+
+/// List the critical phases of `pass` that ran on a fallback model. Returns
+/// true if there were any.
+fn display_fallbacks(lisa_root: &Path, pass: u32) -> bool {
+    let ledger = usage::load_usage(lisa_root).unwrap_or_default();
+    let fallbacks: Vec<_> = ledger.fallbacks(pass, CRITICAL_PHASES).collect();
+    if fallbacks.is_empty() {
+        return false;
+    }
+    terminal::print_colored("  Critical phases ran on a fallback model:\n", Color::Red);
+    for r in fallbacks {
+        println!(
+            "    {}: {} instead of {}",
+            r.phase,
+            r.model,
+            r.fallback_from.as_deref().unwrap_or_default()
+        );
+    }
+    println!();
+    true
+}
+
+/// Session gate — an earlier run of this invocation stopped before finishing
+/// and its agent session can be continued.
+pub fn session_gate(config: &Config, label: &str) -> Result<SessionDecision> {
+    if !config.review.pause {
+        terminal::log_warn(
+            "Session gate skipped (pause = false) — continuing the interrupted agent session",
+        );
+        return Ok(SessionDecision::Continue);
     }
+
+    let label = label.to_string();
+    ask(move || {
+        println!();
+        terminal::print_separator();
+        terminal::println_bold("  INTERRUPTED AGENT SESSION");
+        terminal::print_separator();
+        println!();
+        terminal::print_colored("  Agent: ", Color::White);
+        println!("{}", label);
+        println!();
+        println!("  The last run of this agent stopped before it finished. Its session can be");
+        println!("  continued, keeping everything it already read and worked out.");
+        println!();
+
+        terminal::print_colored("  [C]", Color::Green);
+        println!(" CONTINUE — pick up the session where it stopped");
+        terminal::print_colored("  [F]", Color::Yellow);
+        println!(" FRESH    — start the agent over with the full prompt");
+        println!();
+        terminal::print_separator();
+        println!();
+
+        loop {
+            print!("  Choice: ");
+            io::stdout().flush()?;
+            let mut choice = String::new();
+            read_stdin_line(&mut choice)?;
+            match choice.trim().to_lowercase().as_str() {
+                "c" => return Ok(SessionDecision::Continue),
+                "f" => return Ok(SessionDecision::Fresh),
+                _ => println!("  Invalid choice. Enter C or F."),
+            }
+        }
+    })
 }
 
 /// Environment gate — check for missing runtimes after scope
@@ -1019,7 +1309,7 @@ fn extract_stack_info(agents_content: &str) -> Option<String> {
     None
 }
 
-fn display_review_summary(content: &str, _pass: u32) {
+fn display_review_summary(content: &str, results: Option<&TestResults>) {
     // Extract current answer
     if let Some(answer) = extract_section_first_line(content, "## Current Answer") {
         terminal::print_bold("  Answer: ");
@@ -1032,12 +1322,24 @@ fn display_review_summary(content: &str, _pass: u32) {
         println!("{}", progress);
     }
 
-    // Extract test summary
-    for line in content.lines() {
-        if line.starts_with("Bounds:") {
-            terminal::print_bold("  Tests: ");
-            println!("{}", line);
-            break;
+    // Test summary: lisa's own measurements when available, else the agent's line
+    if let Some(results) = results {
+        terminal::print_bold("  Tests: ");
+        println!("{} (measured by lisa)", results.summary_line());
+        let failing = results.failing();
+        for id in failing.iter().take(10) {
+            terminal::print_colored(&format!("    ✗ {}\n", id), Color::Red);
+        }
+        if failing.len() > 10 {
+            println!("    ... and {} more", failing.len() - 10);
+        }
+    } else {
+        for line in content.lines() {
+            if line.starts_with("Bounds:") {
+                terminal::print_bold("  Tests: ");
+                println!("{}", line);
+                break;
+            }
         }
     }
 
@@ -1113,6 +1415,24 @@ pub fn extract_section_first_line(content: &str, heading: &str) -> Option<String
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_forwarded_prompts_run_on_the_serving_thread() {
+        let (prompts, asked) = mpsc::channel::<Prompt>();
+        let lane = std::thread::spawn(move || {
+            forward_prompts(prompts, || ask(|| Ok(std::thread::current().id())))
+        });
+        for prompt in asked {
+            prompt();
+        }
+        let asked_on = lane.join().unwrap().unwrap();
+        assert_eq!(asked_on, std::thread::current().id());
+        // Without forwarding the gate is asked where it is reached
+        assert_eq!(
+            ask(|| Ok(std::thread::current().id())).unwrap(),
+            std::thread::current().id()
+        );
+    }
+
     #[test]
     fn test_extract_primary_question_from() {
         let content = "# Acceptance Criteria\n\n## Primary Question\n\nWhat is the Reynolds number?\n\n## Success Criteria\n";
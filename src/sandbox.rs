@@ -0,0 +1,285 @@
+//! Temporary worktree in which the Bounds agent runs without implementation code.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, DEFAULT_BACKEND};
+use crate::git;
+use crate::prompt::Phase;
+use crate::terminal;
+
+/// Process artifacts (relative to `lisa_root`) the Bounds agent needs.
+const LISA_INPUTS: &[&str] = &[
+    "methodology",
+    "skills",
+    "validation",
+    "references",
+    "STACK.md",
+    "spiral/pass-0/acceptance-criteria.md",
+];
+
+/// A checkout of HEAD without `paths.source`, the software and integration
+/// tests, or the `.git` link. Only the process artifacts the Bounds prompt
+/// reads are copied in, and only `paths.tests_bounds` is copied back.
+pub struct Sandbox {
+    root: PathBuf,
+    project_root: PathBuf,
+}
+
+impl Sandbox {
+    /// Create the Bounds sandbox for `pass`/`task` in the system temp directory.
+    pub fn create(config: &Config, project_root: &Path, pass: u32, task: u32) -> Result<Self> {
+        let root = std::env::temp_dir().join(format!(
+            "lisa-bounds-{}-pass{}-task{}",
+            std::process::id(),
+            pass,
+            task
+        ));
+        if root.exists() {
+            std::fs::remove_dir_all(&root)
+                .with_context(|| format!("Failed to remove stale sandbox {}", root.display()))?;
+            git::prune_worktrees()?;
+        }
+        git::add_worktree(&root)?;
+        let sandbox = Self {
+            root,
+            project_root: project_root.to_path_buf(),
+        };
+
+        // Cut the link to the repository so history cannot be read either
+        std::fs::remove_file(sandbox.root.join(".git"))
+            .context("Failed to unlink sandbox from the repository")?;
+
+        // Software and integration tests exercise the implementation, so they go too
+        let keep = kept_dirs(config);
+        let removed = config.paths.source.iter().chain([
+            &config.paths.tests_software,
+            &config.paths.tests_integration,
+        ]);
+        for dir in removed.filter(|d| !d.is_empty()) {
+            remove_except(&sandbox.root.join(dir), &sandbox.root, &keep)?;
+        }
+
+        // Uncommitted bounding tests carry over; they are context for the agent
+        if !config.paths.tests_bounds.is_empty() {
+            mirror(
+                &project_root.join(&config.paths.tests_bounds),
+                &sandbox.root.join(&config.paths.tests_bounds),
+            )?;
+        }
+
+        let lisa_root = config.lisa_root(project_root);
+        let sandbox_lisa = sandbox.root.join(&config.paths.lisa_root);
+        for rel in LISA_INPUTS {
+            copy_tree(&lisa_root.join(rel), &sandbox_lisa.join(rel))?;
+        }
+        std::fs::create_dir_all(sandbox_lisa.join(format!("spiral/pass-{}", pass)))?;
+
+        Ok(sandbox)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// `config` with the Bounds permissions extended to deny reading or editing
+    /// the project by absolute path. Bash stays allowed, so shell commands into
+    /// the project are only flagged by the access audit. Unchanged, with a
+    /// warning, when a backend of the Bounds model chain has no flags for deny
+    /// rules.
+    pub fn restrict(&self, config: &Config) -> Config {
+        let mut restricted = config.clone();
+        let enforceable = Phase::Bounds.models(config).iter().all(|spec| {
+            spec.backend == DEFAULT_BACKEND
+                || config.agent.backends.get(&spec.backend).is_some_and(|b| {
+                    !b.allowed_tools_flag.is_empty() && !b.disallowed_tools_flag.is_empty()
+                })
+        });
+        if !enforceable {
+            terminal::log_warn(
+                "The Bounds backend cannot take deny rules — reads of the project by \
+                 absolute path are only caught by the access audit.",
+            );
+            return restricted;
+        }
+        let mut permissions = config
+            .permissions_for("bounds")
+            .cloned()
+            .unwrap_or_default();
+        // Claude's rule syntax marks an absolute path with a leading `//`
+        let project = self.project_root.display().to_string();
+        let project = project.trim_start_matches('/');
+        for tool in ["Read", "Edit"] {
+            permissions
+                .disallowed_tools
+                .push(format!("{}(//{}/**)", tool, project));
+        }
+        restricted
+            .permissions
+            .insert("bounds".to_string(), permissions);
+        restricted
+    }
+
+    /// Bring the agent's bounding tests into the project, replacing its copy.
+    /// Nothing else the agent wrote leaves the sandbox.
+    pub fn copy_back(&self, config: &Config) -> Result<()> {
+        if config.paths.tests_bounds.is_empty() {
+            return Ok(());
+        }
+        mirror(
+            &self.root.join(&config.paths.tests_bounds),
+            &self.project_root.join(&config.paths.tests_bounds),
+        )
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.root) {
+            terminal::log_warn(&format!(
+                "Failed to remove sandbox {}: {}",
+                self.root.display(),
+                e
+            ));
+        }
+        let _ = git::prune_worktrees();
+    }
+}
+
+/// Directories that survive inside a removed dir: bounding tests and process artifacts.
+fn kept_dirs(config: &Config) -> Vec<PathBuf> {
+    [&config.paths.lisa_root, &config.paths.tests_bounds]
+        .into_iter()
+        .filter(|d| !d.is_empty())
+        .map(|d| normalize(Path::new(d)))
+        .collect()
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+/// Remove `path` recursively, sparing anything under `keep` (relative to `root`).
+fn remove_except(path: &Path, root: &Path, keep: &[PathBuf]) -> Result<()> {
+    let rel = normalize(path.strip_prefix(root).unwrap_or(path));
+    if keep.iter().any(|k| rel.starts_with(k)) {
+        return Ok(());
+    }
+    if !path.is_dir() {
+        if path.exists() {
+            std::fs::remove_file(path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        return Ok(());
+    }
+    if !keep.iter().any(|k| k.starts_with(&rel)) {
+        return std::fs::remove_dir_all(path)
+            .with_context(|| format!("Failed to remove {}", path.display()));
+    }
+    for entry in std::fs::read_dir(path)?.filter_map(|e| e.ok()) {
+        remove_except(&entry.path(), root, keep)?;
+    }
+    Ok(())
+}
+
+/// Copy a file or directory tree, overwriting files that exist at `dest`.
+pub fn copy_tree(src: &Path, dest: &Path) -> Result<()> {
+    if src.is_dir() {
+        std::fs::create_dir_all(dest)?;
+        for entry in std::fs::read_dir(src)?.filter_map(|e| e.ok()) {
+            copy_tree(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+    } else if src.is_file() {
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(src, dest)
+            .with_context(|| format!("Failed to copy {} to {}", src.display(), dest.display()))?;
+    }
+    Ok(())
+}
+
+/// Make `dest` an exact copy of `src`, deleting files that only exist in `dest`.
+fn mirror(src: &Path, dest: &Path) -> Result<()> {
+    if dest.exists() {
+        std::fs::remove_dir_all(dest)
+            .with_context(|| format!("Failed to replace {}", dest.display()))?;
+    }
+    copy_tree(src, dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_except_spares_nested_test_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for file in [
+            "pkg/solver.py",
+            "pkg/sub/helpers.py",
+            "pkg/tests/bounds/test_limits.py",
+            "pkg/tests/software/test_solver.py",
+            "docs/notes.md",
+        ] {
+            std::fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            std::fs::write(root.join(file), "x").unwrap();
+        }
+        let keep = vec![
+            PathBuf::from(".lisa"),
+            PathBuf::from("pkg/tests/bounds"),
+            PathBuf::from("pkg/tests/software"),
+        ];
+
+        remove_except(&root.join("./pkg"), root, &keep).unwrap();
+
+        assert!(!root.join("pkg/solver.py").exists());
+        assert!(!root.join("pkg/sub").exists());
+        assert!(root.join("pkg/tests/bounds/test_limits.py").exists());
+        assert!(root.join("pkg/tests/software/test_solver.py").exists());
+        assert!(root.join("docs/notes.md").exists());
+    }
+
+    #[test]
+    fn test_restrict_denies_absolute_project_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = Sandbox {
+            root: dir.path().join("sandbox"),
+            project_root: PathBuf::from("/work/project"),
+        };
+        let config: Config = toml::from_str("[project]\nname = \"t\"\n").unwrap();
+
+        let restricted = sandbox.restrict(&config);
+        let denied = &restricted
+            .permissions_for("bounds")
+            .unwrap()
+            .disallowed_tools;
+        assert_eq!(
+            denied,
+            &["Read(//work/project/**)", "Edit(//work/project/**)"]
+        );
+        assert!(config.permissions_for("bounds").is_none());
+    }
+
+    #[test]
+    fn test_mirror_replaces_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        let dest = dir.path().join("dest");
+        std::fs::create_dir_all(src.join("phenomenon")).unwrap();
+        std::fs::write(src.join("phenomenon/test_drag.py"), "new").unwrap();
+        std::fs::create_dir_all(&dest).unwrap();
+        std::fs::write(dest.join("test_removed.py"), "old").unwrap();
+
+        mirror(&src, &dest).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dest.join("phenomenon/test_drag.py")).unwrap(),
+            "new"
+        );
+        assert!(!dest.join("test_removed.py").exists());
+    }
+}
@@ -0,0 +1,159 @@
+//! File snapshots that tell what an agent invocation changed.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::SystemTime;
+
+/// Modification time and size of every file lisa or an agent may touch,
+/// keyed by path relative to the project root.
+#[derive(Debug, Default, Clone)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+/// Files that differ between two snapshots, relative to the project root.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SnapshotDiff {
+    /// Created or modified files.
+    pub changed: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+}
+
+impl Snapshot {
+    /// Capture the project's tracked and untracked-but-not-ignored files (via git),
+    /// plus everything under `extra_roots` (e.g. the gitignored `.lisa/`).
+    /// Paths under any of `exclude` are skipped.
+    pub fn take(project_root: &Path, extra_roots: &[&Path], exclude: &[&Path]) -> Snapshot {
+        let mut rel_paths = match git_files(project_root) {
+            Some(paths) => paths,
+            None => {
+                let mut paths = Vec::new();
+                walk(project_root, project_root, &mut paths);
+                paths
+            }
+        };
+        for root in extra_roots {
+            walk(project_root, root, &mut rel_paths);
+        }
+
+        let exclude: Vec<PathBuf> = exclude
+            .iter()
+            .filter_map(|p| p.strip_prefix(project_root).ok().map(Path::to_path_buf))
+            .collect();
+
+        let mut files = BTreeMap::new();
+        for rel in rel_paths {
+            if exclude.iter().any(|ex| rel.starts_with(ex)) {
+                continue;
+            }
+            if let Ok(meta) = std::fs::metadata(project_root.join(&rel)) {
+                if meta.is_file() {
+                    files.insert(rel, (meta.modified().ok(), meta.len()));
+                }
+            }
+        }
+        Snapshot { files }
+    }
+
+    /// Every captured file, relative to the project root.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path)
+    }
+
+    /// Files created, modified or deleted between `self` and `after`.
+    pub fn diff(&self, after: &Snapshot) -> SnapshotDiff {
+        let mut diff = SnapshotDiff::default();
+        for (path, stamp) in &after.files {
+            if self.files.get(path) != Some(stamp) {
+                diff.changed.push(path.clone());
+            }
+        }
+        for path in self.files.keys() {
+            if !after.files.contains_key(path) {
+                diff.deleted.push(path.clone());
+            }
+        }
+        diff
+    }
+}
+
+/// List files git considers part of the working tree. `None` outside a git repo.
+pub fn git_files(project_root: &Path) -> Option<Vec<PathBuf>> {
+    let output = Command::new("git")
+        .args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])
+        .current_dir(project_root)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(
+        output
+            .stdout
+            .split(|b| *b == 0)
+            .filter(|s| !s.is_empty())
+            .map(|s| PathBuf::from(String::from_utf8_lossy(s).to_string()))
+            .collect(),
+    )
+}
+
+/// Recursively collect files under `dir` as paths relative to `project_root`.
+pub fn walk(project_root: &Path, dir: &Path, out: &mut Vec<PathBuf>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        if path.is_dir() {
+            walk(project_root, &path, out);
+        } else if let Ok(rel) = path.strip_prefix(project_root) {
+            out.push(rel.to_path_buf());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_diff_detects_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join(".lisa/recordings")).unwrap();
+        std::fs::write(root.join("src/keep.rs"), "keep").unwrap();
+        std::fs::write(root.join("src/edit.rs"), "old").unwrap();
+        std::fs::write(root.join("src/gone.rs"), "gone").unwrap();
+
+        let lisa = root.join(".lisa");
+        let recordings = lisa.join("recordings");
+        let before = Snapshot::take(root, &[&lisa], &[&recordings]);
+
+        std::fs::write(root.join("src/edit.rs"), "newer content").unwrap();
+        std::fs::remove_file(root.join("src/gone.rs")).unwrap();
+        std::fs::write(root.join(".lisa/state.toml"), "x").unwrap();
+        std::fs::write(recordings.join("ignored.txt"), "x").unwrap();
+
+        let after = Snapshot::take(root, &[&lisa], &[&recordings]);
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff.changed,
+            vec![
+                PathBuf::from(".lisa/state.toml"),
+                PathBuf::from("src/edit.rs")
+            ]
+        );
+        assert_eq!(diff.deleted, vec![PathBuf::from("src/gone.rs")]);
+        assert_eq!(before.diff(&before), SnapshotDiff::default());
+    }
+}
@@ -36,10 +36,32 @@ pub enum PassPhase {
         #[serde(default)]
         task_id: u32,
         iteration: u32,
+        /// Tasks being built side by side in lanes; `task_id` is then 0.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        lanes: Vec<u32>,
     },
     Audit,
 }
 
+impl SpiralState {
+    /// The spiral pass this state belongs to; `None` before the first pass.
+    pub fn pass(&self) -> Option<u32> {
+        match self {
+            SpiralState::InPass { pass, .. }
+            | SpiralState::RefineMethodologyComplete { pass }
+            | SpiralState::RefineComplete { pass }
+            | SpiralState::RefineReview { pass }
+            | SpiralState::BuildComplete { pass }
+            | SpiralState::AuditComplete { pass }
+            | SpiralState::PassReview { pass }
+            | SpiralState::Exploring { pass, .. }
+            | SpiralState::ExploreReview { pass, .. } => Some(*pass),
+            SpiralState::Complete { final_pass } => Some(*final_pass),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for SpiralState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -79,9 +101,19 @@ impl std::fmt::Display for PassPhase {
             PassPhase::Refine => write!(f, "Refine"),
             PassPhase::Bounds { task_id } => write!(f, "Bounds (task {})", task_id),
             PassPhase::Build {
-                task_id, iteration, ..
+                task_id,
+                iteration,
+                lanes,
             } => {
-                if *task_id > 0 {
+                if !lanes.is_empty() {
+                    let tasks: Vec<String> = lanes.iter().map(u32::to_string).collect();
+                    write!(
+                        f,
+                        "Build (tasks {} in parallel, iteration {})",
+                        tasks.join(", "),
+                        iteration
+                    )
+                } else if *task_id > 0 {
                     write!(f, "Build (task {}, iteration {})", task_id, iteration)
                 } else {
                     write!(f, "Build (iteration {})", iteration)
@@ -92,36 +124,76 @@ impl std::fmt::Display for PassPhase {
     }
 }
 
+/// Backend session of an agent invocation that did not finish, so `lisa resume`
+/// or a retry can continue it instead of starting over.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AgentSession {
+    /// The invocation it belongs to: `pass-N/<phase>[-<task>[-<iter>]]`.
+    pub invocation: String,
+    /// `backend:model` it ran on; a session only continues on the same one.
+    pub model: String,
+    pub id: String,
+}
+
 /// Wrapper struct for TOML serialization
 #[derive(Debug, Serialize, Deserialize)]
 struct StateFile {
     #[serde(flatten)]
     state: SpiralState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    session: Option<AgentSession>,
 }
 
-pub fn load_state(lisa_root: &Path) -> Result<SpiralState> {
+fn load_file(lisa_root: &Path) -> Result<Option<StateFile>> {
     let state_path = lisa_root.join("state.toml");
     if !state_path.exists() {
-        return Ok(SpiralState::NotStarted);
+        return Ok(None);
     }
     let content = std::fs::read_to_string(&state_path)
         .with_context(|| format!("Failed to read {}", state_path.display()))?;
     let file: StateFile = toml::from_str(&content).with_context(|| "Failed to parse state.toml")?;
-    Ok(file.state)
+    Ok(Some(file))
 }
 
-pub fn save_state(lisa_root: &Path, state: &SpiralState) -> Result<()> {
+fn save_file(lisa_root: &Path, file: &StateFile) -> Result<()> {
     let state_path = lisa_root.join("state.toml");
     std::fs::create_dir_all(lisa_root)?;
-    let file = StateFile {
-        state: state.clone(),
-    };
-    let content = toml::to_string_pretty(&file).with_context(|| "Failed to serialize state")?;
+    let content = toml::to_string_pretty(file).with_context(|| "Failed to serialize state")?;
     std::fs::write(&state_path, content)
         .with_context(|| format!("Failed to write {}", state_path.display()))?;
     Ok(())
 }
 
+pub fn load_state(lisa_root: &Path) -> Result<SpiralState> {
+    Ok(load_file(lisa_root)?.map_or(SpiralState::NotStarted, |f| f.state))
+}
+
+/// Fails once Ctrl-C has been pressed, so an interrupted phase is never
+/// recorded as finished. A saved agent session is kept.
+pub fn save_state(lisa_root: &Path, state: &SpiralState) -> Result<()> {
+    crate::interrupt::check()?;
+    let session = load_file(lisa_root).ok().flatten().and_then(|f| f.session);
+    save_file(
+        lisa_root,
+        &StateFile {
+            state: state.clone(),
+            session,
+        },
+    )
+}
+
+pub fn load_session(lisa_root: &Path) -> Result<Option<AgentSession>> {
+    Ok(load_file(lisa_root)?.and_then(|f| f.session))
+}
+
+/// Record (or with `None`, forget) the session of the running invocation.
+/// Unlike [`save_state`] this also works after Ctrl-C: it is what lets the
+/// interrupted invocation continue.
+pub fn save_session(lisa_root: &Path, session: Option<AgentSession>) -> Result<()> {
+    let state = load_state(lisa_root)?;
+    save_file(lisa_root, &StateFile { state, session })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,6 +203,7 @@ mod tests {
         let state = SpiralState::NotStarted;
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -142,6 +215,7 @@ mod tests {
         let state = SpiralState::Scoping;
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -155,10 +229,12 @@ mod tests {
             phase: PassPhase::Build {
                 task_id: 0,
                 iteration: 7,
+                lanes: Vec::new(),
             },
         };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -170,6 +246,7 @@ mod tests {
         let state = SpiralState::ScopeComplete;
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -181,6 +258,7 @@ mod tests {
         let state = SpiralState::Complete { final_pass: 4 };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -192,6 +270,7 @@ mod tests {
         let state = SpiralState::RefineReview { pass: 3 };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -203,6 +282,7 @@ mod tests {
         let state = SpiralState::RefineComplete { pass: 2 };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -214,6 +294,7 @@ mod tests {
         let state = SpiralState::BuildComplete { pass: 3 };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -225,6 +306,7 @@ mod tests {
         let state = SpiralState::AuditComplete { pass: 1 };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -239,6 +321,7 @@ mod tests {
         };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -253,6 +336,7 @@ mod tests {
         };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -285,7 +369,8 @@ mod tests {
                     pass: 2,
                     phase: PassPhase::Build {
                         task_id: 0,
-                        iteration: 1
+                        iteration: 1,
+                        lanes: Vec::new(),
                     }
                 }
             ),
@@ -298,12 +383,27 @@ mod tests {
                     pass: 2,
                     phase: PassPhase::Build {
                         task_id: 3,
-                        iteration: 1
+                        iteration: 1,
+                        lanes: Vec::new(),
                     }
                 }
             ),
             "Pass 2 — Build (task 3, iteration 1)"
         );
+        assert_eq!(
+            format!(
+                "{}",
+                SpiralState::InPass {
+                    pass: 2,
+                    phase: PassPhase::Build {
+                        task_id: 0,
+                        iteration: 4,
+                        lanes: vec![2, 5],
+                    }
+                }
+            ),
+            "Pass 2 — Build (tasks 2, 5 in parallel, iteration 4)"
+        );
         assert_eq!(
             format!(
                 "{}",
@@ -337,6 +437,7 @@ mod tests {
         let state = SpiralState::ScopeResearch;
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -348,6 +449,7 @@ mod tests {
         let state = SpiralState::ScopeResearchReview;
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -359,6 +461,7 @@ mod tests {
         let state = SpiralState::ScopeValidation;
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -370,6 +473,7 @@ mod tests {
         let state = SpiralState::ScopePlanning;
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -384,6 +488,7 @@ mod tests {
         };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -397,21 +502,48 @@ mod tests {
             phase: PassPhase::Build {
                 task_id: 3,
                 iteration: 2,
+                lanes: Vec::new(),
             },
         };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
         assert_eq!(parsed.state, state);
     }
 
+    #[test]
+    fn test_state_roundtrip_in_pass_build_with_lanes() {
+        let state = SpiralState::InPass {
+            pass: 1,
+            phase: PassPhase::Build {
+                task_id: 0,
+                iteration: 2,
+                lanes: vec![1, 4],
+            },
+        };
+        let file = StateFile {
+            state: state.clone(),
+            session: None,
+        };
+        let toml_str = toml::to_string_pretty(&file).unwrap();
+        assert!(
+            toml_str.contains("lanes = [\n    1,\n    4,\n]"),
+            "{}",
+            toml_str
+        );
+        let parsed: StateFile = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed.state, state);
+    }
+
     #[test]
     fn test_state_roundtrip_refine_methodology_complete() {
         let state = SpiralState::RefineMethodologyComplete { pass: 2 };
         let file = StateFile {
             state: state.clone(),
+            session: None,
         };
         let toml_str = toml::to_string_pretty(&file).unwrap();
         let parsed: StateFile = toml::from_str(&toml_str).unwrap();
@@ -436,9 +568,46 @@ iteration = 5
                 pass: 2,
                 phase: PassPhase::Build {
                     task_id: 0,
-                    iteration: 5
+                    iteration: 5,
+                    lanes: Vec::new(),
                 }
             }
         );
     }
+
+    #[test]
+    fn test_session_kept_alongside_pass_phase() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let build = SpiralState::InPass {
+            pass: 2,
+            phase: PassPhase::Build {
+                task_id: 3,
+                iteration: 1,
+                lanes: Vec::new(),
+            },
+        };
+        save_state(root, &build).unwrap();
+        assert_eq!(load_session(root).unwrap(), None);
+
+        let session = AgentSession {
+            invocation: "pass-2/build-3-1".to_string(),
+            model: "claude:opus".to_string(),
+            id: "4f1c".to_string(),
+        };
+        save_session(root, Some(session.clone())).unwrap();
+        assert_eq!(load_state(root).unwrap(), build);
+        assert_eq!(load_session(root).unwrap(), Some(session.clone()));
+
+        // Advancing the state keeps the session until it is cleared
+        let audit = SpiralState::InPass {
+            pass: 2,
+            phase: PassPhase::Audit,
+        };
+        save_state(root, &audit).unwrap();
+        assert_eq!(load_session(root).unwrap(), Some(session));
+        save_session(root, None).unwrap();
+        assert_eq!(load_session(root).unwrap(), None);
+        assert_eq!(load_state(root).unwrap(), audit);
+    }
 }
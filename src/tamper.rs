@@ -0,0 +1,378 @@
+//! Guard the bounding tests against the Build agent.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::git;
+use crate::snapshot;
+
+/// Lines of unchanged context shown around each change.
+const CONTEXT_LINES: usize = 2;
+/// Files longer than this (in lines) are reported without a line diff.
+const MAX_DIFF_LINES: usize = 5000;
+
+/// Contents of the bounding tests, keyed by path relative to the project root.
+#[derive(Debug, Default)]
+pub struct BoundsGuard {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dir: Option<PathBuf>,
+}
+
+/// Bounding-test files the Build agent changed.
+#[derive(Debug, Default, PartialEq)]
+pub struct Tampering {
+    pub modified: Vec<PathBuf>,
+    pub added: Vec<PathBuf>,
+    pub deleted: Vec<PathBuf>,
+}
+
+impl Tampering {
+    pub fn is_empty(&self) -> bool {
+        self.modified.is_empty() && self.added.is_empty() && self.deleted.is_empty()
+    }
+
+    /// One `  - path (modified)` line per file.
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        for (paths, kind) in [
+            (&self.modified, "modified"),
+            (&self.added, "added"),
+            (&self.deleted, "deleted"),
+        ] {
+            for p in paths {
+                lines.push(format!("  - {} ({})", p.display(), kind));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Tag on the commit with the bounding tests `task`'s Bounds phase wrote.
+pub fn bounds_tag(pass: u32, task: u32) -> String {
+    format!("lisa/bounds/pass-{}-task-{}", pass, task)
+}
+
+impl BoundsGuard {
+    /// The bounding tests as the Bounds phase committed them: at `task`'s own
+    /// tag, otherwise at the closest bounds tag behind HEAD. Without either
+    /// (or without `auto_commit`), the bounding tests on disk.
+    pub fn baseline(config: &Config, project_root: &Path, pass: u32, task: u32) -> BoundsGuard {
+        let tag = git::rev_parse(&bounds_tag(pass, task))
+            .or_else(|| git::nearest_tag("lisa/bounds/*"))
+            .filter(|_| config.git.auto_commit);
+        match tag.map(|rev| Self::at_commit(config, &rev)) {
+            Some(Ok(guard)) => guard,
+            _ => Self::capture(config, project_root),
+        }
+    }
+
+    /// Copy the bounding tests committed at `rev`.
+    pub fn at_commit(config: &Config, rev: &str) -> Result<BoundsGuard> {
+        let dir = match bounds_dir(config) {
+            Some(d) => d,
+            None => return Ok(BoundsGuard::default()),
+        };
+        let mut files = BTreeMap::new();
+        for rel in git::ls_tree(rev, &dir.to_string_lossy())? {
+            let content = git::read_blob(&format!("{}:{}", rev, rel))?;
+            files.insert(PathBuf::from(rel), content);
+        }
+        Ok(BoundsGuard {
+            files,
+            dir: Some(dir),
+        })
+    }
+
+    /// Copy the current bounding tests. Without `paths.tests_bounds` the guard is empty.
+    pub fn capture(config: &Config, project_root: &Path) -> BoundsGuard {
+        let dir = match bounds_dir(config) {
+            Some(d) => d,
+            None => return BoundsGuard::default(),
+        };
+        let files = list_files(project_root, &dir)
+            .into_iter()
+            .filter_map(|rel| {
+                std::fs::read(project_root.join(&rel))
+                    .ok()
+                    .map(|content| (rel, content))
+            })
+            .collect();
+        BoundsGuard {
+            files,
+            dir: Some(dir),
+        }
+    }
+
+    /// Compare the bounding tests on disk with the captured copies.
+    pub fn check(&self, project_root: &Path) -> Tampering {
+        let mut tampering = Tampering::default();
+        let dir = match &self.dir {
+            Some(d) => d,
+            None => return tampering,
+        };
+        let current = list_files(project_root, dir);
+        for rel in &current {
+            match self.files.get(rel) {
+                Some(original) => {
+                    if std::fs::read(project_root.join(rel)).ok().as_ref() != Some(original) {
+                        tampering.modified.push(rel.clone());
+                    }
+                }
+                None => tampering.added.push(rel.clone()),
+            }
+        }
+        for rel in self.files.keys() {
+            if !project_root.join(rel).exists() {
+                tampering.deleted.push(rel.clone());
+            }
+        }
+        tampering
+    }
+
+    /// Put back the captured copies and remove files that were added.
+    pub fn restore(&self, project_root: &Path, tampering: &Tampering) -> Result<()> {
+        for rel in tampering.modified.iter().chain(&tampering.deleted) {
+            let path = project_root.join(rel);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, &self.files[rel])
+                .with_context(|| format!("Failed to restore {}", path.display()))?;
+        }
+        for rel in &tampering.added {
+            let path = project_root.join(rel);
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Line diff of every changed file against the captured copy.
+    pub fn diff(&self, project_root: &Path, tampering: &Tampering) -> String {
+        let read = |rel: &Path| {
+            std::fs::read(project_root.join(rel))
+                .map(|b| String::from_utf8_lossy(&b).to_string())
+                .unwrap_or_default()
+        };
+        let original = |rel: &Path| String::from_utf8_lossy(&self.files[rel]).to_string();
+
+        let mut out = String::new();
+        for rel in &tampering.modified {
+            out.push_str(&file_diff(rel, &original(rel), &read(rel)));
+        }
+        for rel in &tampering.added {
+            out.push_str(&file_diff(rel, "", &read(rel)));
+        }
+        for rel in &tampering.deleted {
+            out.push_str(&file_diff(rel, &original(rel), ""));
+        }
+        out
+    }
+}
+
+/// Keep the diff under `spiral/pass-N/bounds-tamper/` for the audit trail.
+pub fn save_diff(
+    lisa_root: &Path,
+    pass: u32,
+    task: u32,
+    iteration: u32,
+    diff: &str,
+) -> Result<PathBuf> {
+    let dir = lisa_root.join(format!("spiral/pass-{}/bounds-tamper", pass));
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("task-{}-iter-{}.diff", task, iteration));
+    std::fs::write(&path, diff).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn bounds_dir(config: &Config) -> Option<PathBuf> {
+    let dir = config.paths.tests_bounds.trim_end_matches('/');
+    if dir.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(dir))
+    }
+}
+
+/// Files under `dir`, skipping anything git ignores (e.g. `__pycache__`).
+fn list_files(project_root: &Path, dir: &Path) -> Vec<PathBuf> {
+    let mut files = match snapshot::git_files(project_root) {
+        Some(paths) => paths.into_iter().filter(|p| p.starts_with(dir)).collect(),
+        None => {
+            let mut paths = Vec::new();
+            snapshot::walk(project_root, &project_root.join(dir), &mut paths);
+            paths
+        }
+    };
+    files.retain(|p| project_root.join(p).is_file());
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Unified-style diff of one file: changed lines with a little context.
+fn file_diff(path: &Path, old: &str, new: &str) -> String {
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path.display(), path.display());
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if old.len() > MAX_DIFF_LINES || new.len() > MAX_DIFF_LINES {
+        out.push_str("(file too large to diff)\n");
+        return out;
+    }
+
+    // Longest common subsequence table, filled from the end
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut ops: Vec<(char, &str)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', old[i]));
+            i += 1;
+        } else {
+            ops.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    let near_change = |k: usize| {
+        let lo = k.saturating_sub(CONTEXT_LINES);
+        let hi = (k + CONTEXT_LINES + 1).min(ops.len());
+        ops[lo..hi].iter().any(|(op, _)| *op != ' ')
+    };
+    let mut skipped = false;
+    for (k, (op, line)) in ops.iter().enumerate() {
+        if *op == ' ' && !near_change(k) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            out.push_str("@@\n");
+            skipped = false;
+        }
+        out.push_str(&format!("{}{}\n", op, line));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        toml::from_str(
+            "[project]\nname = \"t\"\n[paths]\nsource = [\"src\"]\ntests_bounds = \"tests/bounds\"\n",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_detects_and_restores_tampering() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let bounds = root.join("tests/bounds");
+        std::fs::create_dir_all(bounds.join("system")).unwrap();
+        std::fs::write(bounds.join("test_drag.py"), "assert drag < 10\n").unwrap();
+        std::fs::write(bounds.join("system/test_total.py"), "assert total > 0\n").unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+
+        let guard = BoundsGuard::capture(&config(), root);
+        assert!(guard.check(root).is_empty());
+
+        std::fs::write(bounds.join("test_drag.py"), "assert drag < 1000\n").unwrap();
+        std::fs::remove_file(bounds.join("system/test_total.py")).unwrap();
+        std::fs::write(bounds.join("test_new.py"), "pass\n").unwrap();
+        std::fs::write(root.join("src/model.py"), "x = 1\n").unwrap();
+
+        let tampering = guard.check(root);
+        assert_eq!(
+            tampering,
+            Tampering {
+                modified: vec![PathBuf::from("tests/bounds/test_drag.py")],
+                added: vec![PathBuf::from("tests/bounds/test_new.py")],
+                deleted: vec![PathBuf::from("tests/bounds/system/test_total.py")],
+            }
+        );
+        let diff = guard.diff(root, &tampering);
+        assert!(diff.contains("-assert drag < 10\n+assert drag < 1000\n"));
+        assert!(diff.contains("+pass\n"));
+        assert!(diff.contains("-assert total > 0\n"));
+
+        guard.restore(root, &tampering).unwrap();
+        assert!(guard.check(root).is_empty());
+        assert_eq!(
+            std::fs::read_to_string(bounds.join("test_drag.py")).unwrap(),
+            "assert drag < 10\n"
+        );
+        assert!(!bounds.join("test_new.py").exists());
+    }
+
+    #[test]
+    fn test_baseline_is_the_bounds_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let run = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+                .args(args)
+                .current_dir(root)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        let bounds = root.join("tests/bounds");
+        std::fs::create_dir_all(&bounds).unwrap();
+        std::fs::write(bounds.join("test_drag.py"), "assert drag < 10\n").unwrap();
+        run(&["init", "-q"]);
+        run(&["add", "-A"]);
+        run(&["commit", "-q", "-m", "bounds"]);
+        run(&["tag", &bounds_tag(1, 1)]);
+        // A modification accepted and committed by a build iteration
+        std::fs::write(bounds.join("test_drag.py"), "assert drag < 1000\n").unwrap();
+        run(&["commit", "-q", "-am", "build"]);
+
+        crate::process::in_dir(root, || {
+            let guard = BoundsGuard::baseline(&config(), root, 1, 1);
+            assert_eq!(
+                guard.check(root).modified,
+                vec![PathBuf::from("tests/bounds/test_drag.py")]
+            );
+            // A task without its own Bounds phase uses the closest tag
+            let guard = BoundsGuard::baseline(&config(), root, 1, 2);
+            assert_eq!(guard.check(root).modified.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_file_diff_elides_unchanged_lines() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\n";
+        let diff = file_diff(Path::new("t.py"), old, new);
+        assert_eq!(diff, "--- a/t.py\n+++ b/t.py\n@@\n c\n d\n-e\n+E\n f\n g\n");
+    }
+
+    #[test]
+    fn test_no_bounds_dir_disables_guard() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Config = toml::from_str("[project]\nname = \"t\"\n").unwrap();
+        let guard = BoundsGuard::capture(&config, dir.path());
+        std::fs::write(dir.path().join("test_x.py"), "x").unwrap();
+        assert!(guard.check(dir.path()).is_empty());
+    }
+}
@@ -1,35 +1,686 @@
-use anyhow::Result;
+//! The task plan (`methodology/plan.md`) as a typed model.
+
+use anyhow::{Context, Result};
 use regex::Regex;
+use serde::Serialize;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use std::path::Path;
+use std::sync::LazyLock;
 
 use crate::terminal;
 
+static TASK_HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^#{2,4}\s+Task\s+(\d+)(?::\s*(.*))?").unwrap());
+static FIELD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s*(?:[-*]\s+)?\*\*([A-Za-z ]+):\*\*\s*)(.*?)\s*$").unwrap());
+static CHECK_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\s*[-*]\s+\[)([ xX])\]\s?(.*?)\s*$").unwrap());
+static DEP_NUM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)Task\s+(\d+)").unwrap());
+static SECTION_NUM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[^\w.])(\d+(?:\.\d+)*)\b").unwrap());
+
+/// A task's `**Status:**`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Status {
+    Todo,
+    InProgress,
+    Done,
+    Blocked,
+    /// Anything else, as written.
+    Unknown(String),
+}
+
+impl Status {
+    /// Parse the first word of a status value, ignoring case.
+    pub fn parse(value: &str) -> Status {
+        let word: String = value
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        match word.to_ascii_uppercase().as_str() {
+            "TODO" => Status::Todo,
+            "IN_PROGRESS" => Status::InProgress,
+            "DONE" => Status::Done,
+            "BLOCKED" => Status::Blocked,
+            _ => Status::Unknown(word),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Status::Todo => "TODO",
+            Status::InProgress => "IN_PROGRESS",
+            Status::Done => "DONE",
+            Status::Blocked => "BLOCKED",
+            Status::Unknown(s) => s,
+        }
+    }
+
+    /// TODO or IN_PROGRESS: work the build loop still has to do.
+    pub fn is_open(&self) -> bool {
+        matches!(self, Status::Todo | Status::InProgress)
+    }
+}
+
+impl Serialize for Status {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChecklistItem {
+    pub done: bool,
+    pub text: String,
+}
+
+/// The fields of a task, in the order the plan template lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Status,
+    Pass,
+    Methodology,
+    Validation,
+    BoundingChecks,
+    Checklist,
+    Dependencies,
+}
+
+impl Field {
+    const ALL: [Field; 7] = [
+        Field::Status,
+        Field::Pass,
+        Field::Methodology,
+        Field::Validation,
+        Field::BoundingChecks,
+        Field::Checklist,
+        Field::Dependencies,
+    ];
+
+    fn from_label(label: &str) -> Option<Field> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "status" => Some(Field::Status),
+            "pass" => Some(Field::Pass),
+            "methodology" => Some(Field::Methodology),
+            "validation" => Some(Field::Validation),
+            "bounding checks" => Some(Field::BoundingChecks),
+            "checklist" => Some(Field::Checklist),
+            "dependencies" => Some(Field::Dependencies),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Field::Status => "Status",
+            Field::Pass => "Pass",
+            Field::Methodology => "Methodology",
+            Field::Validation => "Validation",
+            Field::BoundingChecks => "Bounding Checks",
+            Field::Checklist => "Checklist",
+            Field::Dependencies => "Dependencies",
+        }
+    }
+}
+
+/// One source line of a task, with what was parsed from it.
+#[derive(Debug, Clone)]
+enum Line {
+    Heading {
+        raw: String,
+        number: u32,
+        name: String,
+    },
+    Field {
+        raw: String,
+        /// Everything up to the value, e.g. `- **Status:** `.
+        prefix: String,
+        field: Field,
+        value: String,
+    },
+    Check {
+        raw: String,
+        /// Everything up to the box, e.g. `  - [`.
+        prefix: String,
+        index: usize,
+        item: ChecklistItem,
+    },
+    Prose(String),
+}
+
+/// A `### Task N: name` section of the plan.
+#[derive(Debug, Clone, Serialize)]
+pub struct Task {
+    pub number: u32,
+    pub name: String,
+    /// TODO when the task has no status line.
+    pub status: Status,
+    /// 1 when the task has no pass line.
+    pub pass: u32,
+    pub methodology: String,
+    pub validation: String,
+    pub bounding_checks: String,
+    pub checklist: Vec<ChecklistItem>,
+    pub dependencies: Vec<u32>,
+    #[serde(skip)]
+    lines: Vec<Line>,
+}
+
+impl Task {
+    fn new(number: u32, name: String, heading: &str) -> Task {
+        Task {
+            number,
+            name: name.clone(),
+            status: Status::Todo,
+            pass: 1,
+            methodology: String::new(),
+            validation: String::new(),
+            bounding_checks: String::new(),
+            checklist: Vec::new(),
+            dependencies: Vec::new(),
+            lines: vec![Line::Heading {
+                raw: heading.to_string(),
+                number,
+                name,
+            }],
+        }
+    }
+
+    /// Whether the Bounds phase derives tests for this task.
+    pub fn needs_bounds(&self) -> bool {
+        !self.bounding_checks.is_empty() && !self.bounding_checks.eq_ignore_ascii_case("none")
+    }
+
+    /// Whether the task has a line for `field` in the plan.
+    pub fn declares(&self, field: Field) -> bool {
+        self.lines
+            .iter()
+            .any(|l| matches!(l, Line::Field { field: f, .. } if *f == field))
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        if let Some(caps) = CHECK_ITEM.captures(line) {
+            let item = ChecklistItem {
+                done: &caps[2] != " ",
+                text: caps[3].to_string(),
+            };
+            self.lines.push(Line::Check {
+                raw: line.to_string(),
+                prefix: caps[1].to_string(),
+                index: self.checklist.len(),
+                item: item.clone(),
+            });
+            self.checklist.push(item);
+            return;
+        }
+        if let Some(caps) = FIELD.captures(line) {
+            if let Some(field) = Field::from_label(&caps[2]) {
+                let value = caps[3].to_string();
+                self.set_field(field, &value);
+                self.lines.push(Line::Field {
+                    raw: line.to_string(),
+                    prefix: caps[1].to_string(),
+                    field,
+                    value: self.field_value(field),
+                });
+                return;
+            }
+        }
+        self.lines.push(Line::Prose(line.to_string()));
+    }
+
+    fn set_field(&mut self, field: Field, value: &str) {
+        match field {
+            Field::Status => self.status = Status::parse(value),
+            Field::Pass => {
+                if let Some(p) = value
+                    .split(|c: char| !c.is_ascii_digit())
+                    .find(|s| !s.is_empty())
+                    .and_then(|s| s.parse().ok())
+                {
+                    self.pass = p;
+                }
+            }
+            Field::Methodology => self.methodology = value.to_string(),
+            Field::Validation => self.validation = value.to_string(),
+            Field::BoundingChecks => self.bounding_checks = value.to_string(),
+            Field::Checklist => {}
+            Field::Dependencies => {
+                // "None" or "none" means no dependencies
+                if !value.eq_ignore_ascii_case("none") {
+                    self.dependencies = DEP_NUM
+                        .captures_iter(value)
+                        .filter_map(|c| c[1].parse().ok())
+                        .collect();
+                }
+            }
+        }
+    }
+
+    /// The value of `field` as the writer renders it.
+    fn field_value(&self, field: Field) -> String {
+        match field {
+            Field::Status => self.status.to_string(),
+            Field::Pass => self.pass.to_string(),
+            Field::Methodology => self.methodology.clone(),
+            Field::Validation => self.validation.clone(),
+            Field::BoundingChecks => self.bounding_checks.clone(),
+            Field::Checklist => String::new(),
+            Field::Dependencies if self.dependencies.is_empty() => "None".to_string(),
+            Field::Dependencies => self
+                .dependencies
+                .iter()
+                .map(|d| format!("Task {}", d))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    /// Whether a task without a line for `field` needs one to say what it holds.
+    fn needs_line(&self, field: Field) -> bool {
+        match field {
+            Field::Status => self.status != Status::Todo,
+            Field::Pass => self.pass != 1,
+            Field::Methodology => !self.methodology.is_empty(),
+            Field::Validation => !self.validation.is_empty(),
+            Field::BoundingChecks => !self.bounding_checks.is_empty(),
+            Field::Checklist => !self.checklist.is_empty(),
+            Field::Dependencies => !self.dependencies.is_empty(),
+        }
+    }
+
+    fn render_check(item: &ChecklistItem, prefix: &str) -> String {
+        format!(
+            "{}{}] {}",
+            prefix,
+            if item.done { "x" } else { " " },
+            item.text
+        )
+    }
+
+    fn render(&self, out: &mut Vec<String>) {
+        let declared: Vec<Field> = Field::ALL
+            .into_iter()
+            .filter(|f| self.declares(*f))
+            .collect();
+        let parsed_checks = self
+            .lines
+            .iter()
+            .filter(|l| matches!(l, Line::Check { .. }))
+            .count();
+        // New fields and checklist items go after the last field or item
+        let insert_after = self
+            .lines
+            .iter()
+            .rposition(|l| matches!(l, Line::Field { .. } | Line::Check { .. }))
+            .unwrap_or(0);
+        let last_check = self
+            .lines
+            .iter()
+            .rposition(|l| matches!(l, Line::Check { .. }));
+        let checklist_line = self.lines.iter().position(|l| {
+            matches!(
+                l,
+                Line::Field {
+                    field: Field::Checklist,
+                    ..
+                }
+            )
+        });
+        // Added items follow the style of the existing ones
+        let item_prefix = match last_check.map(|i| &self.lines[i]) {
+            Some(Line::Check { prefix, .. }) => prefix.as_str(),
+            _ => "  - [",
+        };
+        let new_items = || {
+            self.checklist
+                .iter()
+                .skip(parsed_checks)
+                .map(|item| Task::render_check(item, item_prefix))
+        };
+
+        for (i, line) in self.lines.iter().enumerate() {
+            match line {
+                Line::Heading { raw, number, name } => {
+                    if *number == self.number && *name == self.name {
+                        out.push(raw.clone());
+                    } else {
+                        out.push(format!("### Task {}: {}", self.number, self.name));
+                    }
+                }
+                Line::Field {
+                    raw,
+                    prefix,
+                    field,
+                    value,
+                } => {
+                    let current = self.field_value(*field);
+                    if current == *value {
+                        out.push(raw.clone());
+                    } else {
+                        out.push(format!("{}{}", prefix, current));
+                    }
+                }
+                Line::Check {
+                    raw,
+                    prefix,
+                    index,
+                    item,
+                } => match self.checklist.get(*index) {
+                    Some(current) if current == item => out.push(raw.clone()),
+                    Some(current) => out.push(Task::render_check(current, prefix)),
+                    None => {} // Removed
+                },
+                Line::Prose(raw) => out.push(raw.clone()),
+            }
+            if Some(i) == last_check || (last_check.is_none() && Some(i) == checklist_line) {
+                out.extend(new_items());
+            }
+            if i == insert_after {
+                for field in Field::ALL {
+                    if declared.contains(&field) || !self.needs_line(field) {
+                        continue;
+                    }
+                    if field == Field::Checklist {
+                        out.push(format!("- **{}:**", field.label()));
+                        out.extend(new_items());
+                    } else {
+                        out.push(format!(
+                            "- **{}:** {}",
+                            field.label(),
+                            self.field_value(field)
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The parsed `plan.md`: text before the first task, then the tasks in order.
+/// Each task keeps the lines it was parsed from, so [`Plan::render`] writes
+/// back unchanged fields and the prose between them exactly as they were.
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    preamble: Vec<String>,
+    pub tasks: Vec<Task>,
+    trailing_newline: bool,
+}
+
+impl Plan {
+    pub fn parse(content: &str) -> Plan {
+        let mut plan = Plan {
+            trailing_newline: content.ends_with('\n'),
+            ..Plan::default()
+        };
+        for line in content.lines() {
+            if let Some(caps) = TASK_HEADING.captures(line) {
+                let number = caps[1].parse().unwrap_or(0);
+                let name = caps
+                    .get(2)
+                    .map(|m| m.as_str().trim().to_string())
+                    .unwrap_or_default();
+                plan.tasks.push(Task::new(number, name, line));
+            } else if let Some(task) = plan.tasks.last_mut() {
+                task.parse_line(line);
+            } else {
+                plan.preamble.push(line.to_string());
+            }
+        }
+
+        // Warn about duplicate task numbers
+        let mut seen_numbers = HashSet::new();
+        for task in &plan.tasks {
+            if !seen_numbers.insert(task.number) {
+                terminal::log_warn(&format!(
+                    "Duplicate task number {} found in plan.md — behavior may be unpredictable",
+                    task.number
+                ));
+            }
+        }
+        plan
+    }
+
+    /// Load a plan file. A missing file is an empty plan.
+    pub fn load(plan_path: &Path) -> Result<Plan> {
+        if !plan_path.exists() {
+            return Ok(Plan::default());
+        }
+        let content = std::fs::read_to_string(plan_path)
+            .with_context(|| format!("Failed to read {}", plan_path.display()))?;
+        Ok(Plan::parse(&content))
+    }
+
+    pub fn save(&self, plan_path: &Path) -> Result<()> {
+        std::fs::write(plan_path, self.render())
+            .with_context(|| format!("Failed to write {}", plan_path.display()))
+    }
+
+    /// The plan as markdown. Unedited plans come back exactly as parsed.
+    pub fn render(&self) -> String {
+        let mut lines = self.preamble.clone();
+        for task in &self.tasks {
+            task.render(&mut lines);
+        }
+        let mut output = lines.join("\n");
+        if self.trailing_newline {
+            output.push('\n');
+        }
+        output
+    }
+
+    pub fn task(&self, number: u32) -> Option<&Task> {
+        self.tasks.iter().find(|t| t.number == number)
+    }
+
+    pub fn task_mut(&mut self, number: u32) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|t| t.number == number)
+    }
+
+    /// Every TODO or IN_PROGRESS task up to `current_pass` whose dependencies
+    /// are all DONE, in plan order. None of them depends on another.
+    pub fn ready(&self, current_pass: u32) -> Vec<&Task> {
+        let done_ids: HashSet<u32> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == Status::Done)
+            .map(|t| t.number)
+            .collect();
+        self.tasks
+            .iter()
+            .filter(|task| {
+                task.status.is_open()
+                    && task.pass <= current_pass
+                    && task.dependencies.iter().all(|d| done_ids.contains(d))
+            })
+            .collect()
+    }
+
+    /// Circular dependencies, as `Task 1 -> Task 2 -> Task 1`.
+    pub fn cycles(&self) -> Vec<String> {
+        find_cycles(&self.tasks)
+    }
+
+    /// Counts by status of the tasks `filter` selects.
+    pub fn counts(&self, filter: impl Fn(&Task) -> bool) -> TaskCounts {
+        let mut counts = TaskCounts::default();
+        for task in self.tasks.iter().filter(|t| filter(t)) {
+            counts.total += 1;
+            match task.status {
+                Status::Todo => counts.todo += 1,
+                Status::InProgress => counts.in_progress += 1,
+                Status::Done => counts.done += 1,
+                Status::Blocked => counts.blocked += 1,
+                Status::Unknown(_) => {}
+            }
+        }
+        counts
+    }
+
+    /// Structural problems the parser would otherwise paper over, in plan order.
+    /// `methodology` is the text of methodology.md; without it, section
+    /// references are not checked.
+    pub fn lint(&self, methodology: Option<&str>, max_passes: u32) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut seen = HashSet::new();
+        let sections = methodology.map(Sections::parse);
+
+        for task in &self.tasks {
+            let mut report = |message: String| {
+                diagnostics.push(Diagnostic {
+                    task: task.number,
+                    message,
+                })
+            };
+            if !seen.insert(task.number) {
+                report("task number is used more than once".to_string());
+            }
+            if !task.declares(Field::Status) {
+                report("has no **Status:** line".to_string());
+            } else if let Status::Unknown(status) = &task.status {
+                report(format!(
+                    "unknown status '{}' (expected TODO, IN_PROGRESS, DONE or BLOCKED)",
+                    status
+                ));
+            }
+            if !task.declares(Field::Pass) {
+                report("has no **Pass:** line".to_string());
+            } else if task.pass > max_passes {
+                report(format!(
+                    "is scheduled for pass {}, beyond max_spiral_passes ({})",
+                    task.pass, max_passes
+                ));
+            }
+            for &dep in &task.dependencies {
+                match self.task(dep) {
+                    None => report(format!("depends on Task {}, which does not exist", dep)),
+                    Some(other) if other.pass > task.pass => report(format!(
+                        "is scheduled for pass {} but depends on Task {}, scheduled for pass {}",
+                        task.pass, dep, other.pass
+                    )),
+                    Some(_) => {}
+                }
+            }
+            if let Some(sections) = &sections {
+                for reference in sections.unresolved(&task.methodology) {
+                    report(format!(
+                        "methodology reference '{}' matches no heading in methodology.md",
+                        reference
+                    ));
+                }
+            }
+        }
+
+        for cycle in self.cycles() {
+            let task = DEP_NUM
+                .captures(&cycle)
+                .map_or(0, |c| c[1].parse().unwrap_or(0));
+            diagnostics.push(Diagnostic {
+                task,
+                message: format!("is part of a circular dependency: {}", cycle),
+            });
+        }
+        diagnostics
+    }
+}
+
+/// A problem [`Plan::lint`] found with one task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub task: u32,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Task {} {}", self.task, self.message)
+    }
+}
+
+/// The headings of methodology.md that `**Methodology:**` references can
+/// point to, outside fenced code blocks.
+#[derive(Debug, Default)]
+struct Sections {
+    /// Section numbers, such as `2` or `2.1`.
+    numbers: HashSet<String>,
+    /// Lowercase heading text without its number. The document title is left
+    /// out, so `# Methodology` does not resolve every reference naming it.
+    titles: Vec<String>,
+}
+
+impl Sections {
+    fn parse(methodology: &str) -> Self {
+        let mut sections = Sections::default();
+        let mut fenced = false;
+        let mut first = true;
+        for line in methodology.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                fenced = !fenced;
+                continue;
+            }
+            if fenced || !line.starts_with('#') {
+                continue;
+            }
+            let level = line.chars().take_while(|&c| c == '#').count();
+            let mut heading = line[level..].trim().to_lowercase();
+            let number = SECTION_NUM.captures(&heading).map(|c| c[1].to_string());
+            let title = first && level == 1 && number.is_none();
+            first = false;
+            if let Some(number) = number {
+                heading = heading.replacen(&number, "", 1);
+                sections.numbers.insert(number);
+            }
+            let heading = heading.trim_matches(|c: char| c.is_whitespace() || c == '.');
+            if !title && !heading.is_empty() {
+                sections.titles.push(heading.to_string());
+            }
+        }
+        sections
+    }
+
+    /// The comma- or semicolon-separated parts of a `**Methodology:**` value
+    /// that match no heading. A reference with a section number (`2.1`,
+    /// `Section 2.1`, `§2.1`) needs a heading with that number; any other
+    /// needs a heading containing its text, or contained in it.
+    fn unresolved(&self, methodology: &str) -> Vec<String> {
+        methodology
+            .split([',', ';'])
+            .map(str::trim)
+            .filter(|r| !r.is_empty() && !["n/a", "none", "-"].contains(&r.to_lowercase().as_str()))
+            .filter(|reference| match SECTION_NUM.captures(reference) {
+                Some(caps) => !self.numbers.contains(&caps[1]),
+                None => {
+                    let text = reference.to_lowercase();
+                    !self
+                        .titles
+                        .iter()
+                        .any(|t| t.contains(&text) || text.contains(t.as_str()))
+                }
+            })
+            .map(str::to_string)
+            .collect()
+    }
+}
+
 /// Parse methodology/plan.md and count tasks by status for a given max pass
 pub fn count_uncompleted_tasks(plan_path: &Path, max_pass: u32) -> Result<u32> {
-    if !plan_path.exists() {
-        return Ok(0);
-    }
-    let content = std::fs::read_to_string(plan_path)?;
-    let tasks = parse_tasks(&content);
-    Ok(tasks
-        .iter()
-        .filter(|t| t.pass <= max_pass && (t.status == "TODO" || t.status == "IN_PROGRESS"))
-        .count() as u32)
+    let counts = Plan::load(plan_path)?.counts(|t| t.pass <= max_pass);
+    Ok(counts.todo + counts.in_progress)
 }
 
 pub fn count_blocked_tasks(plan_path: &Path, max_pass: u32) -> Result<u32> {
-    if !plan_path.exists() {
-        return Ok(0);
-    }
-    let content = std::fs::read_to_string(plan_path)?;
-    let tasks = parse_tasks(&content);
-    Ok(tasks
-        .iter()
-        .filter(|t| t.pass <= max_pass && t.status == "BLOCKED")
-        .count() as u32)
+    Ok(Plan::load(plan_path)?
+        .counts(|t| t.pass <= max_pass)
+        .blocked)
 }
 
 pub fn all_tasks_done(plan_path: &Path, max_pass: u32) -> Result<bool> {
@@ -41,37 +692,11 @@ pub fn has_blocked_tasks(plan_path: &Path, max_pass: u32) -> Result<bool> {
 }
 
 pub fn count_tasks_by_status(plan_path: &Path) -> Result<TaskCounts> {
-    if !plan_path.exists() {
-        return Ok(TaskCounts::default());
-    }
-    let content = std::fs::read_to_string(plan_path)?;
-    let tasks = parse_tasks(&content);
-    Ok(TaskCounts {
-        total: tasks.len() as u32,
-        todo: tasks.iter().filter(|t| t.status == "TODO").count() as u32,
-        in_progress: tasks.iter().filter(|t| t.status == "IN_PROGRESS").count() as u32,
-        done: tasks.iter().filter(|t| t.status == "DONE").count() as u32,
-        blocked: tasks.iter().filter(|t| t.status == "BLOCKED").count() as u32,
-    })
+    Ok(Plan::load(plan_path)?.counts(|_| true))
 }
 
 pub fn count_tasks_by_status_for_pass(plan_path: &Path, pass: u32) -> Result<TaskCounts> {
-    if !plan_path.exists() {
-        return Ok(TaskCounts::default());
-    }
-    let content = std::fs::read_to_string(plan_path)?;
-    let tasks = parse_tasks(&content);
-    let filtered: Vec<&Task> = tasks.iter().filter(|t| t.pass == pass).collect();
-    Ok(TaskCounts {
-        total: filtered.len() as u32,
-        todo: filtered.iter().filter(|t| t.status == "TODO").count() as u32,
-        in_progress: filtered
-            .iter()
-            .filter(|t| t.status == "IN_PROGRESS")
-            .count() as u32,
-        done: filtered.iter().filter(|t| t.status == "DONE").count() as u32,
-        blocked: filtered.iter().filter(|t| t.status == "BLOCKED").count() as u32,
-    })
+    Ok(Plan::load(plan_path)?.counts(|t| t.pass == pass))
 }
 
 #[derive(Debug, Default)]
@@ -86,13 +711,9 @@ pub struct TaskCounts {
 /// Hash only the (index, status) pairs from plan.md tasks.
 /// Ignores descriptions, checklists, and prose — only status transitions change the hash.
 pub fn hash_task_statuses(plan_path: &Path) -> Result<u64> {
-    let content = if plan_path.exists() {
-        std::fs::read_to_string(plan_path)?
-    } else {
-        String::new()
-    };
-    let tasks = parse_tasks(&content);
-    let pairs: Vec<(usize, &str)> = tasks
+    let plan = Plan::load(plan_path)?;
+    let pairs: Vec<(usize, &str)> = plan
+        .tasks
         .iter()
         .enumerate()
         .map(|(i, t)| (i, t.status.as_str()))
@@ -111,212 +732,104 @@ pub struct TaskInfo {
     pub needs_bounds: bool,
 }
 
+impl From<&Task> for TaskInfo {
+    fn from(task: &Task) -> Self {
+        TaskInfo {
+            number: task.number,
+            name: task.name.clone(),
+            methodology_ref: task.methodology.clone(),
+            needs_bounds: task.needs_bounds(),
+        }
+    }
+}
+
 /// Find the next eligible task: status == TODO or IN_PROGRESS, pass <= current_pass, all deps satisfied.
 /// IN_PROGRESS tasks are included to allow retrying interrupted work after a crash/resume.
 pub fn find_next_task(plan_path: &Path, current_pass: u32) -> Result<Option<TaskInfo>> {
-    if !plan_path.exists() {
-        return Ok(None);
-    }
-    let content = std::fs::read_to_string(plan_path)?;
-    let tasks = parse_tasks(&content);
-
-    let done_ids: HashSet<u32> = tasks
-        .iter()
-        .filter(|t| t.status == "DONE")
-        .map(|t| t.number)
-        .collect();
-
-    for task in &tasks {
-        if (task.status != "TODO" && task.status != "IN_PROGRESS") || task.pass > current_pass {
-            continue;
-        }
-        let deps_met = task.dependencies.iter().all(|d| done_ids.contains(d));
-        if deps_met {
-            if task.status == "IN_PROGRESS" {
-                terminal::log_warn(&format!(
-                    "Retrying interrupted task {} (was IN_PROGRESS from a previous run)",
-                    task.number
-                ));
-            }
-            let needs_bounds = !task.bounding_checks.eq_ignore_ascii_case("none")
-                && !task.bounding_checks.is_empty();
-            return Ok(Some(TaskInfo {
-                number: task.number,
-                name: task.name.clone(),
-                methodology_ref: task.methodology.clone(),
-                needs_bounds,
-            }));
-        }
+    let plan = Plan::load(plan_path)?;
+    let task = match plan.ready(current_pass).first() {
+        Some(t) => *t,
+        None => return Ok(None),
+    };
+    if task.status == Status::InProgress {
+        terminal::log_warn(&format!(
+            "Retrying interrupted task {} (was IN_PROGRESS from a previous run)",
+            task.number
+        ));
     }
+    Ok(Some(task.into()))
+}
 
-    Ok(None)
+/// Every task [`Plan::ready`] returns, for building side by side.
+pub fn find_ready_tasks(plan_path: &Path, current_pass: u32) -> Result<Vec<TaskInfo>> {
+    let plan = Plan::load(plan_path)?;
+    Ok(plan
+        .ready(current_pass)
+        .into_iter()
+        .map(Into::into)
+        .collect())
 }
 
-/// Mark a task as IN_PROGRESS in plan.md by task number.
+/// Mark a task as IN_PROGRESS in plan.md by task number, if it is TODO.
 pub fn mark_task_in_progress(plan_path: &Path, task_number: u32) -> Result<()> {
-    let content = std::fs::read_to_string(plan_path)?;
-    let task_heading_re =
-        Regex::new(&format!(r"(?im)^(#{{2,4}}\s+Task\s+{}\b)", task_number)).unwrap();
-    let status_re = Regex::new(r"(?m)^(- \*\*Status:\*\*\s+)TODO\s*$").unwrap();
-
-    // Find the line range for this task
-    let any_task_re = Regex::new(r"(?i)^#{2,4}\s+Task\s+\d").unwrap();
-    let mut in_target = false;
-    let mut result_lines: Vec<String> = Vec::new();
-    let mut replaced = false;
-
-    for line in content.lines() {
-        if task_heading_re.is_match(line) {
-            in_target = true;
-            result_lines.push(line.to_string());
-        } else if in_target && any_task_re.is_match(line) {
-            // Hit next task heading — stop targeting
-            in_target = false;
-            result_lines.push(line.to_string());
-        } else if in_target && !replaced {
-            if let Some(caps) = status_re.captures(line) {
-                let prefix = caps.get(1).unwrap().as_str();
-                result_lines.push(format!("{}IN_PROGRESS", prefix));
-                replaced = true;
-                continue;
-            }
-            result_lines.push(line.to_string());
-        } else {
-            result_lines.push(line.to_string());
-        }
-    }
-
-    if replaced {
-        let mut output = result_lines.join("\n");
-        // Preserve trailing newline if original had one
-        if content.ends_with('\n') && !output.ends_with('\n') {
-            output.push('\n');
-        }
-        std::fs::write(plan_path, output)?;
-    }
-
+    update_status(
+        plan_path,
+        task_number,
+        Some(Status::Todo),
+        Status::InProgress,
+    )?;
     Ok(())
 }
 
-#[derive(Debug)]
-struct Task {
-    number: u32,
-    name: String,
-    pass: u32,
-    status: String,
-    methodology: String,
-    bounding_checks: String,
-    dependencies: Vec<u32>,
-}
-
-fn parse_tasks(content: &str) -> Vec<Task> {
-    let task_re = Regex::new(r"(?i)^#{2,4}\s+Task\s+(\d+)(?::\s*(.*))?").unwrap();
-    let status_re = Regex::new(r"\*\*Status:\*\*\s+(\w+)").unwrap();
-    let pass_re = Regex::new(r"\*\*Pass:\*\*\s*(\d+)").unwrap();
-    let methodology_re = Regex::new(r"\*\*Methodology:\*\*\s*(.+)").unwrap();
-    let bounding_re = Regex::new(r"\*\*Bounding Checks:\*\*\s*(.+)").unwrap();
-    let deps_re = Regex::new(r"\*\*Dependencies:\*\*\s*(.+)").unwrap();
-    let dep_num_re = Regex::new(r"(?i)Task\s+(\d+)").unwrap();
-
-    let mut tasks = Vec::new();
-    let mut current_number: u32 = 0;
-    let mut current_name = String::new();
-    let mut current_status = None;
-    let mut current_pass = None;
-    let mut current_methodology = String::new();
-    let mut current_bounding = String::new();
-    let mut current_deps: Vec<u32> = Vec::new();
-    let mut in_task = false;
-
-    for line in content.lines() {
-        if let Some(caps) = task_re.captures(line) {
-            // Save previous task if any
-            if in_task {
-                tasks.push(Task {
-                    number: current_number,
-                    name: current_name.clone(),
-                    pass: current_pass.unwrap_or(1),
-                    status: current_status.unwrap_or_else(|| "TODO".to_string()),
-                    methodology: current_methodology.clone(),
-                    bounding_checks: current_bounding.clone(),
-                    dependencies: current_deps.clone(),
-                });
-            }
-            in_task = true;
-            current_number = caps[1].parse().unwrap_or(0);
-            current_name = caps
-                .get(2)
-                .map(|m| m.as_str().trim().to_string())
-                .unwrap_or_default();
-            current_status = None;
-            current_pass = None;
-            current_methodology = String::new();
-            current_bounding = String::new();
-            current_deps = Vec::new();
-        } else if in_task {
-            if let Some(caps) = status_re.captures(line) {
-                current_status = Some(caps[1].to_string());
-            }
-            if let Some(caps) = pass_re.captures(line) {
-                if let Ok(p) = caps[1].parse::<u32>() {
-                    current_pass = Some(p);
-                }
-            }
-            if let Some(caps) = methodology_re.captures(line) {
-                current_methodology = caps[1].trim().to_string();
-            }
-            if let Some(caps) = bounding_re.captures(line) {
-                current_bounding = caps[1].trim().to_string();
-            }
-            if let Some(caps) = deps_re.captures(line) {
-                let deps_str = &caps[1];
-                // "None" or "none" means no dependencies
-                if !deps_str.trim().eq_ignore_ascii_case("none") {
-                    for dep_cap in dep_num_re.captures_iter(deps_str) {
-                        if let Ok(n) = dep_cap[1].parse::<u32>() {
-                            current_deps.push(n);
-                        }
-                    }
-                }
-            }
-        }
-    }
+/// Move a task the agent marked DONE back to IN_PROGRESS (verification failed).
+/// Returns false if the task was not DONE.
+pub fn reopen_task(plan_path: &Path, task_number: u32) -> Result<bool> {
+    update_status(
+        plan_path,
+        task_number,
+        Some(Status::Done),
+        Status::InProgress,
+    )
+}
 
-    // Don't forget the last task
-    if in_task {
-        tasks.push(Task {
-            number: current_number,
-            name: current_name,
-            pass: current_pass.unwrap_or(1),
-            status: current_status.unwrap_or_else(|| "TODO".to_string()),
-            methodology: current_methodology,
-            bounding_checks: current_bounding,
-            dependencies: current_deps,
-        });
-    }
+/// Set a task's status whatever it was. Returns false if the plan has no such task.
+pub fn set_task_status(plan_path: &Path, task_number: u32, status: Status) -> Result<bool> {
+    update_status(plan_path, task_number, None, status)
+}
 
-    // Warn about duplicate task numbers
-    let mut seen_numbers = HashSet::new();
-    for task in &tasks {
-        if !seen_numbers.insert(task.number) {
-            terminal::log_warn(&format!(
-                "Duplicate task number {} found in plan.md — behavior may be unpredictable",
-                task.number
-            ));
-        }
-    }
+/// Current status of a task, or `None` if the plan has no such task.
+pub fn task_status(plan_path: &Path, task_number: u32) -> Result<Option<Status>> {
+    Ok(Plan::load(plan_path)?
+        .task(task_number)
+        .map(|t| t.status.clone()))
+}
 
-    tasks
+/// Set a task's status to `to` if it is `from` (any status for `None`).
+/// Returns false if the task is missing or had another status.
+fn update_status(
+    plan_path: &Path,
+    task_number: u32,
+    from: Option<Status>,
+    to: Status,
+) -> Result<bool> {
+    let mut plan = Plan::load(plan_path)?;
+    let task = match plan.task_mut(task_number) {
+        Some(t) if from.as_ref().is_none_or(|f| *f == t.status) => t,
+        _ => return Ok(false),
+    };
+    if task.status != to {
+        task.status = to;
+        plan.save(plan_path)?;
+    }
+    Ok(true)
 }
 
 /// Detect circular dependencies among tasks. Returns a list of cycle descriptions.
 pub fn detect_dependency_cycles(plan_path: &Path) -> Result<Vec<String>> {
-    if !plan_path.exists() {
-        return Ok(vec![]);
-    }
-    let content = std::fs::read_to_string(plan_path)?;
-    let tasks = parse_tasks(&content);
+    Ok(Plan::load(plan_path)?.cycles())
+}
 
+fn find_cycles(tasks: &[Task]) -> Vec<String> {
     let task_numbers: HashSet<u32> = tasks.iter().map(|t| t.number).collect();
     let mut cycles = Vec::new();
 
@@ -360,12 +873,12 @@ pub fn detect_dependency_cycles(plan_path: &Path) -> Result<Vec<String>> {
         in_stack.remove(&node);
     }
 
-    for task in &tasks {
+    for task in tasks {
         if !visited.contains(&task.number) {
             let mut path = Vec::new();
             dfs(
                 task.number,
-                &tasks,
+                tasks,
                 &task_numbers,
                 &mut visited,
                 &mut in_stack,
@@ -375,13 +888,17 @@ pub fn detect_dependency_cycles(plan_path: &Path) -> Result<Vec<String>> {
         }
     }
 
-    Ok(cycles)
+    cycles
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse_tasks(content: &str) -> Vec<Task> {
+        Plan::parse(content).tasks
+    }
+
     #[test]
     fn test_parse_tasks() {
         let content = r#"# Implementation Plan
@@ -410,23 +927,200 @@ mod tests {
 "#;
         let tasks = parse_tasks(content);
         assert_eq!(tasks.len(), 3);
-        assert_eq!(tasks[0].status, "DONE");
+        assert_eq!(tasks[0].status, Status::Done);
         assert_eq!(tasks[0].pass, 1);
         assert_eq!(tasks[0].number, 1);
         assert_eq!(tasks[0].name, "Setup infrastructure");
         assert_eq!(tasks[0].methodology, "N/A");
         assert!(tasks[0].dependencies.is_empty());
-        assert_eq!(tasks[1].status, "TODO");
+        assert_eq!(tasks[1].status, Status::Todo);
         assert_eq!(tasks[1].pass, 1);
         assert_eq!(tasks[1].number, 2);
         assert_eq!(tasks[1].name, "Implement core");
         assert_eq!(tasks[1].methodology, "Section 2.1");
-        assert_eq!(tasks[2].status, "BLOCKED");
+        assert_eq!(tasks[2].status, Status::Blocked);
         assert_eq!(tasks[2].pass, 2);
         assert_eq!(tasks[2].number, 3);
         assert_eq!(tasks[2].dependencies, vec![2]);
     }
 
+    const ROUND_TRIP_PLAN: &str = "# Implementation Plan
+
+Intro prose the agents wrote.
+
+## Tasks
+
+### Task 1: Drag model
+- **Status:**  DONE (verified)
+- **Pass:** 1
+- **Methodology:** Section 2.1
+- **Validation:** sanity-checks, limiting-cases
+- **Checklist:**
+  - [x] Implement drag
+  - [ ] Software tests
+- **Dependencies:** None
+
+Notes: the Reynolds range is narrow.
+
+### Task 2: Lift model
+- **Status:** TODO
+- **Pass:** 2
+- **Checklist:**
+  * [ ] Implement lift
+- **Dependencies:** Task 1 (drag coefficients)
+- **Owner:** whoever
+";
+
+    #[test]
+    fn test_plan_round_trips_unedited() {
+        let plan = Plan::parse(ROUND_TRIP_PLAN);
+        assert_eq!(plan.render(), ROUND_TRIP_PLAN);
+
+        let drag = plan.task(1).unwrap();
+        assert_eq!(drag.status, Status::Done);
+        assert_eq!(drag.validation, "sanity-checks, limiting-cases");
+        assert_eq!(
+            drag.checklist,
+            vec![
+                ChecklistItem {
+                    done: true,
+                    text: "Implement drag".to_string()
+                },
+                ChecklistItem {
+                    done: false,
+                    text: "Software tests".to_string()
+                },
+            ]
+        );
+        let lift = plan.task(2).unwrap();
+        assert_eq!(lift.pass, 2);
+        assert_eq!(lift.dependencies, vec![1]);
+        assert!(!lift.declares(Field::Methodology));
+    }
+
+    #[test]
+    fn test_plan_edits_rewrite_only_changed_fields() {
+        let mut plan = Plan::parse(ROUND_TRIP_PLAN);
+        let drag = plan.task_mut(1).unwrap();
+        drag.checklist[1].done = true;
+        let lift = plan.task_mut(2).unwrap();
+        lift.status = Status::Blocked;
+        lift.methodology = "Section 3".to_string();
+        lift.checklist.push(ChecklistItem {
+            done: false,
+            text: "Software tests".to_string(),
+        });
+
+        let rendered = plan.render();
+        let expected = ROUND_TRIP_PLAN
+            .replace("  - [ ] Software tests", "  - [x] Software tests")
+            .replace(
+                "- **Status:** TODO\n- **Pass:** 2\n- **Checklist:**\n  * [ ] Implement lift\n",
+                "- **Status:** BLOCKED\n- **Pass:** 2\n- **Checklist:**\n  * [ ] Implement lift\n  * [ ] Software tests\n",
+            )
+            // New fields go after the last one the plan format knows
+            .replace(
+                "(drag coefficients)\n",
+                "(drag coefficients)\n- **Methodology:** Section 3\n",
+            );
+        assert_eq!(rendered, expected);
+        // The unparsed status note stays with the unchanged status
+        assert!(rendered.contains("- **Status:**  DONE (verified)"));
+
+        let reparsed = Plan::parse(&rendered);
+        assert_eq!(reparsed.task(2).unwrap().status, Status::Blocked);
+        assert_eq!(reparsed.task(2).unwrap().methodology, "Section 3");
+    }
+
+    #[test]
+    fn test_unknown_status_is_kept() {
+        let plan = Plan::parse("### Task 1: Odd\n- **Status:** WAITING\n");
+        let task = plan.task(1).unwrap();
+        assert_eq!(task.status, Status::Unknown("WAITING".to_string()));
+        assert!(!task.status.is_open());
+        assert_eq!(plan.counts(|_| true).total, 1);
+        assert_eq!(plan.counts(|_| true).todo, 0);
+    }
+
+    const LINT_METHODOLOGY: &str =
+        "# Methodology\n\n## 2 Forces\n\n### 2.1 Drag model\n\n## 3. Integration\n";
+
+    #[test]
+    fn test_lint_clean_plan() {
+        let plan = Plan::parse(
+            "### Task 1: Drag\n- **Status:** DONE\n- **Pass:** 1\n\
+             - **Methodology:** Section 2.1\n- **Dependencies:** None\n\n\
+             ### Task 2: Step\n- **Status:** TODO\n- **Pass:** 2\n\
+             - **Methodology:** §3, Drag model\n- **Dependencies:** Task 1\n\n\
+             ### Task 3: Glue\n- **Status:** TODO\n- **Pass:** 2\n\
+             - **Methodology:** N/A\n- **Dependencies:** Task 2\n",
+        );
+        assert!(plan.lint(Some(LINT_METHODOLOGY), 5).is_empty());
+    }
+
+    #[test]
+    fn test_lint_reports_each_problem() {
+        let plan = Plan::parse(
+            "### Task 1: Drag\n- **Status:** WAITING\n- **Pass:** 2\n\
+             - **Methodology:** 2.4\n- **Dependencies:** None\n\n\
+             ### Task 2: Step\n- **Status:** TODO\n- **Pass:** 1\n\
+             - **Methodology:** Turbulence closure\n- **Dependencies:** Task 1, Task 9\n\n\
+             ### Task 3: Later\n- **Pass:** 7\n\n\
+             ### Task 4: Loop\n- **Status:** TODO\n- **Dependencies:** Task 4\n",
+        );
+        let messages: Vec<String> = plan
+            .lint(Some(LINT_METHODOLOGY), 5)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Task 1 unknown status 'WAITING' (expected TODO, IN_PROGRESS, DONE or BLOCKED)",
+                "Task 1 methodology reference '2.4' matches no heading in methodology.md",
+                "Task 2 is scheduled for pass 1 but depends on Task 1, scheduled for pass 2",
+                "Task 2 depends on Task 9, which does not exist",
+                "Task 2 methodology reference 'Turbulence closure' matches no heading in methodology.md",
+                "Task 3 has no **Status:** line",
+                "Task 3 is scheduled for pass 7, beyond max_spiral_passes (5)",
+                "Task 4 has no **Pass:** line",
+                "Task 4 is part of a circular dependency: Task 4 -> Task 4",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_skips_sections_without_methodology() {
+        let plan = Plan::parse(
+            "### Task 1: Drag\n- **Status:** TODO\n- **Pass:** 1\n- **Methodology:** 9.9\n",
+        );
+        assert!(plan.lint(None, 5).is_empty());
+        assert_eq!(plan.lint(Some(LINT_METHODOLOGY), 5).len(), 1);
+    }
+
+    #[test]
+    fn test_lint_sections_need_a_real_heading() {
+        let methodology = "# Methodology\n\n## 2.1 Drag model\n\n\
+                           ```python\n# 4.2 Solver\n```\n\n## 5 Appendix\n";
+        let plan = Plan::parse(
+            "### Task 1: A\n- **Status:** TODO\n- **Pass:** 1\n\
+             - **Methodology:** 4.2, Methodology overview, 2, Section 2.1, The drag model, 5\n",
+        );
+        let messages: Vec<String> = plan
+            .lint(Some(methodology), 5)
+            .iter()
+            .map(|d| d.message.clone())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "methodology reference '4.2' matches no heading in methodology.md",
+                "methodology reference 'Methodology overview' matches no heading in methodology.md",
+                "methodology reference '2' matches no heading in methodology.md",
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_tasks_empty() {
         let content = "# Implementation Plan\n\n## Tasks\n";
@@ -451,11 +1145,11 @@ mod tests {
 "#;
         let tasks = parse_tasks(content);
         assert_eq!(tasks.len(), 3);
-        assert_eq!(tasks[0].status, "TODO");
+        assert_eq!(tasks[0].status, Status::Todo);
         assert_eq!(tasks[0].pass, 1);
-        assert_eq!(tasks[1].status, "DONE");
+        assert_eq!(tasks[1].status, Status::Done);
         assert_eq!(tasks[1].pass, 2);
-        assert_eq!(tasks[2].status, "IN_PROGRESS");
+        assert_eq!(tasks[2].status, Status::InProgress);
         assert_eq!(tasks[2].pass, 1); // default when missing
     }
 
@@ -619,6 +1313,59 @@ mod tests {
         std::fs::remove_dir_all(&dir).ok();
     }
 
+    #[test]
+    fn test_find_ready_tasks_returns_independent_tasks() {
+        let dir = tempfile::tempdir().unwrap();
+        let plan = dir.path().join("plan.md");
+
+        let content = r#"# Plan
+
+### Task 1: Setup
+- **Status:** DONE
+- **Pass:** 1
+- **Dependencies:** None
+
+### Task 2: Drag
+- **Status:** IN_PROGRESS
+- **Pass:** 1
+- **Dependencies:** Task 1
+
+### Task 3: Lift
+- **Status:** TODO
+- **Pass:** 1
+- **Dependencies:** Task 1
+
+### Task 4: Combined
+- **Status:** TODO
+- **Pass:** 1
+- **Dependencies:** Task 2, Task 3
+
+### Task 5: Later
+- **Status:** TODO
+- **Pass:** 2
+- **Dependencies:** None
+"#;
+        std::fs::write(&plan, content).unwrap();
+
+        let ready: Vec<u32> = find_ready_tasks(&plan, 1)
+            .unwrap()
+            .iter()
+            .map(|t| t.number)
+            .collect();
+        assert_eq!(ready, vec![2, 3]);
+
+        // A lane's outcome is copied over whatever the status was
+        assert!(set_task_status(&plan, 2, Status::Done).unwrap());
+        assert!(set_task_status(&plan, 3, Status::Todo).unwrap());
+        assert_eq!(task_status(&plan, 2).unwrap(), Some(Status::Done));
+        let ready: Vec<u32> = find_ready_tasks(&plan, 1)
+            .unwrap()
+            .iter()
+            .map(|t| t.number)
+            .collect();
+        assert_eq!(ready, vec![3]);
+    }
+
     #[test]
     fn test_find_next_task_none_when_all_done() {
         let dir = std::env::temp_dir().join("lisa_test_find_none");
@@ -690,6 +1437,24 @@ mod tests {
         std::fs::remove_dir_all(&dir).ok();
     }
 
+    #[test]
+    fn test_reopen_task() {
+        let dir = std::env::temp_dir().join("lisa_test_reopen");
+        std::fs::create_dir_all(&dir).unwrap();
+        let plan = dir.join("plan.md");
+
+        let content = "### Task 1: First\n- **Status:** DONE\n- **Pass:** 1\n\n### Task 2: Second\n- **Status:** DONE\n- **Pass:** 1\n";
+        std::fs::write(&plan, content).unwrap();
+
+        assert!(reopen_task(&plan, 2).unwrap());
+        assert!(!reopen_task(&plan, 2).unwrap());
+        assert_eq!(task_status(&plan, 1).unwrap(), Some(Status::Done));
+        assert_eq!(task_status(&plan, 2).unwrap(), Some(Status::InProgress));
+        assert_eq!(task_status(&plan, 3).unwrap(), None);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_parse_tasks_multiple_deps() {
         let content = "### Task 5: Integration\n- **Status:** TODO\n- **Pass:** 2\n- **Dependencies:** Task 1, Task 3, Task 4\n";
@@ -734,7 +1499,7 @@ mod tests {
         let content = "### Task 1: No status\n- **Pass:** 1\n- **Dependencies:** None\n";
         let tasks = parse_tasks(content);
         assert_eq!(tasks.len(), 1);
-        assert_eq!(tasks[0].status, "TODO");
+        assert_eq!(tasks[0].status, Status::Todo);
     }
 
     #[test]
@@ -6,8 +6,9 @@ pub fn ts() -> String {
     chrono::Local::now().format("%H:%M:%S").to_string()
 }
 
+// Locked for the whole line, so build lanes running in parallel don't interleave
 pub fn log_info(msg: &str) {
-    let mut stdout = io::stdout();
+    let mut stdout = io::stdout().lock();
     let _ = crossterm::execute!(stdout, SetForegroundColor(Color::Blue));
     print!("[lisa {}] ", ts());
     let _ = crossterm::execute!(stdout, ResetColor);
@@ -15,7 +16,7 @@ pub fn log_info(msg: &str) {
 }
 
 pub fn log_success(msg: &str) {
-    let mut stdout = io::stdout();
+    let mut stdout = io::stdout().lock();
     let _ = crossterm::execute!(stdout, SetForegroundColor(Color::Green));
     print!("[lisa {}] ", ts());
     let _ = crossterm::execute!(stdout, ResetColor);
@@ -23,7 +24,7 @@ pub fn log_success(msg: &str) {
 }
 
 pub fn log_warn(msg: &str) {
-    let mut stdout = io::stdout();
+    let mut stdout = io::stdout().lock();
     let _ = crossterm::execute!(stdout, SetForegroundColor(Color::Yellow));
     print!("[lisa {}] ", ts());
     let _ = crossterm::execute!(stdout, ResetColor);
@@ -31,7 +32,7 @@ pub fn log_warn(msg: &str) {
 }
 
 pub fn log_error(msg: &str) {
-    let mut stdout = io::stdout();
+    let mut stdout = io::stdout().lock();
     let _ = crossterm::execute!(stdout, SetForegroundColor(Color::Red));
     print!("[lisa {}] ", ts());
     let _ = crossterm::execute!(stdout, ResetColor);
@@ -39,7 +40,7 @@ pub fn log_error(msg: &str) {
 }
 
 pub fn log_phase(msg: &str) {
-    let mut stdout = io::stdout();
+    let mut stdout = io::stdout().lock();
     let _ = crossterm::execute!(stdout, SetForegroundColor(Color::Cyan));
     print!("[lisa {}] ", ts());
     let _ = crossterm::execute!(stdout, ResetColor);
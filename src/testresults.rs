@@ -0,0 +1,499 @@
+//! Test results measured by lisa, parsed from the test runners' own output.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::{Config, ResultFormat, ResultSource};
+use crate::process;
+use crate::terminal;
+
+/// Counts for one test category.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuiteResult {
+    pub passed: u32,
+    pub failed: u32,
+    pub skipped: u32,
+    /// Ids of failing (or erroring) tests, as the runner names them.
+    #[serde(default)]
+    pub failing: Vec<String>,
+    /// Set when the results could not be read; counts are then zero.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub error: String,
+}
+
+impl SuiteResult {
+    /// `passed/total`, or `error` if the results could not be read.
+    pub fn ratio(&self) -> String {
+        if !self.error.is_empty() {
+            return "error".to_string();
+        }
+        format!("{}/{}", self.passed, self.passed + self.failed)
+    }
+
+    /// [`ratio`](Self::ratio) with skips noted, e.g. `7/8 (+1 skipped)`.
+    pub fn fraction(&self) -> String {
+        let mut s = self.ratio();
+        if self.skipped > 0 {
+            s.push_str(&format!(" (+{} skipped)", self.skipped));
+        }
+        s
+    }
+
+    fn add(&mut self, outcome: Outcome, id: &str) {
+        match outcome {
+            Outcome::Passed => self.passed += 1,
+            Outcome::Skipped => self.skipped += 1,
+            Outcome::Failed => {
+                self.failed += 1;
+                self.failing.push(id.to_string());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Outcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// Results of one pass, per category.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TestResults {
+    #[serde(default)]
+    pub bounds: Option<SuiteResult>,
+    #[serde(default)]
+    pub software: Option<SuiteResult>,
+    #[serde(default)]
+    pub integration: Option<SuiteResult>,
+}
+
+impl TestResults {
+    fn categories(&self) -> [(&'static str, &Option<SuiteResult>); 3] {
+        [
+            ("Bounds", &self.bounds),
+            ("Software", &self.software),
+            ("Integration", &self.integration),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories().iter().all(|(_, r)| r.is_none())
+    }
+
+    /// `Bounds: 8/8 | Software: 15/15`, covering the measured categories.
+    pub fn summary_line(&self) -> String {
+        self.categories()
+            .iter()
+            .filter_map(|(name, r)| r.as_ref().map(|r| format!("{}: {}", name, r.fraction())))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    /// Failing test ids, prefixed by category.
+    pub fn failing(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        for (name, r) in self.categories() {
+            if let Some(r) = r {
+                out.extend(r.failing.iter().map(|id| format!("{}: {}", name, id)));
+            }
+        }
+        out
+    }
+}
+
+fn results_path(lisa_root: &Path, pass: u32) -> std::path::PathBuf {
+    lisa_root.join(format!("spiral/pass-{}/test-results.toml", pass))
+}
+
+/// Results stored for `pass`, if lisa measured any.
+pub fn load(lisa_root: &Path, pass: u32) -> Option<TestResults> {
+    let content = std::fs::read_to_string(results_path(lisa_root, pass)).ok()?;
+    toml::from_str::<TestResults>(&content)
+        .ok()
+        .filter(|r| !r.is_empty())
+}
+
+/// Run every category that has both a command and a result format, parse the
+/// results and write `spiral/pass-N/test-results.toml`. Returns `None` when no
+/// category is configured.
+pub fn collect(config: &Config, project_root: &Path, pass: u32) -> Result<Option<TestResults>> {
+    let commands = &config.commands;
+    let results_config = &commands.results;
+    let mut results = TestResults::default();
+    for (name, cmd, source, slot) in [
+        (
+            "bounds",
+            &commands.test_bounds,
+            &results_config.bounds,
+            &mut results.bounds,
+        ),
+        (
+            "software",
+            &commands.test_software,
+            &results_config.software,
+            &mut results.software,
+        ),
+        (
+            "integration",
+            &commands.test_integration,
+            &results_config.integration,
+            &mut results.integration,
+        ),
+    ] {
+        let source = match source {
+            Some(s) if !cmd.is_empty() => s,
+            _ => continue,
+        };
+        terminal::log_info(&format!("Collecting {} test results: {}", name, cmd));
+        let result = run_and_parse(cmd, source, project_root, config);
+        if result.error.is_empty() {
+            terminal::log_info(&format!("  {} tests: {}", name, result.fraction()));
+        } else {
+            terminal::log_warn(&format!("  {} test results: {}", name, result.error));
+        }
+        *slot = Some(result);
+    }
+    if results.is_empty() {
+        return Ok(None);
+    }
+
+    let path = results_path(&config.lisa_root(project_root), pass);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, toml::to_string_pretty(&results)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(Some(results))
+}
+
+fn run_and_parse(
+    cmd: &str,
+    source: &ResultSource,
+    project_root: &Path,
+    config: &Config,
+) -> SuiteResult {
+    let report = project_root.join(&source.report);
+    if source.format.needs_report() && report.exists() {
+        // Never read a stale report from an earlier run
+        let _ = std::fs::remove_file(&report);
+    }
+    let output = match process::run_shell_in(cmd, project_root, config) {
+        Ok(o) => o,
+        Err(e) => {
+            return SuiteResult {
+                error: format!("failed to run `{}`: {}", cmd, e),
+                ..Default::default()
+            }
+        }
+    };
+    if let process::Status::TimedOut(after) = output.status {
+        return SuiteResult {
+            error: format!("`{}` hung after {}s and was killed", cmd, after.as_secs()),
+            ..Default::default()
+        };
+    }
+
+    let parsed = if source.format.needs_report() {
+        std::fs::read_to_string(&report)
+            .map_err(|e| {
+                anyhow::anyhow!("report {} not written by `{}`: {}", source.report, cmd, e)
+            })
+            .and_then(|content| parse(source.format, &content))
+    } else if output.stdout_truncated {
+        // Counts from what is left would look complete but are not
+        Err(anyhow::anyhow!(
+            "output of `{}` exceeded limits.command_output_kb ({} KB), so the counts \
+             are unknown — raise the limit or use a report-based format",
+            cmd,
+            config.limits.command_output_kb
+        ))
+    } else {
+        parse(source.format, &output.stdout)
+    };
+    parsed.unwrap_or_else(|e| SuiteResult {
+        error: e.to_string(),
+        ..Default::default()
+    })
+}
+
+/// Parse a runner's output in the given format.
+pub fn parse(format: ResultFormat, content: &str) -> Result<SuiteResult> {
+    match format {
+        ResultFormat::Junit => parse_junit(content),
+        ResultFormat::PytestJson => parse_pytest_json(content),
+        ResultFormat::Libtest => parse_libtest(content),
+        ResultFormat::Ctest => parse_ctest(content),
+    }
+}
+
+/// JUnit XML: one `<testcase>` per test, failing if it has a `<failure>` or
+/// `<error>` child, skipped with `<skipped>`.
+fn parse_junit(content: &str) -> Result<SuiteResult> {
+    let case_re = Regex::new(r"(?s)<testcase\b([^>]*?)(?:/>|>(.*?)</testcase>)").unwrap();
+    let attr_re = Regex::new(r#"(\w+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    if !content.contains("<testsuite") && !content.contains("<testcase") {
+        anyhow::bail!("not a JUnit XML report");
+    }
+
+    let mut result = SuiteResult::default();
+    for caps in case_re.captures_iter(content) {
+        let mut name = String::new();
+        let mut classname = String::new();
+        for attr in attr_re.captures_iter(&caps[1]) {
+            let value = attr
+                .get(2)
+                .or_else(|| attr.get(3))
+                .map(|m| xml_unescape(m.as_str()))
+                .unwrap_or_default();
+            match &attr[1] {
+                "name" => name = value,
+                "classname" => classname = value,
+                _ => {}
+            }
+        }
+        let id = if classname.is_empty() {
+            name
+        } else {
+            format!("{}::{}", classname, name)
+        };
+        let body = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+        let outcome = if body.contains("<failure") || body.contains("<error") {
+            Outcome::Failed
+        } else if body.contains("<skipped") {
+            Outcome::Skipped
+        } else {
+            Outcome::Passed
+        };
+        result.add(outcome, &id);
+    }
+    Ok(result)
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// pytest-json-report: `tests[].nodeid` and `tests[].outcome`.
+fn parse_pytest_json(content: &str) -> Result<SuiteResult> {
+    let report: serde_json::Value =
+        serde_json::from_str(content).context("invalid pytest JSON report")?;
+    let tests = report
+        .get("tests")
+        .and_then(|t| t.as_array())
+        .context("pytest JSON report has no `tests` (run pytest with --json-report)")?;
+
+    let mut result = SuiteResult::default();
+    for test in tests {
+        let id = test.get("nodeid").and_then(|v| v.as_str()).unwrap_or("?");
+        let outcome = match test.get("outcome").and_then(|v| v.as_str()) {
+            Some("passed") | Some("xfailed") => Outcome::Passed,
+            Some("skipped") => Outcome::Skipped,
+            _ => Outcome::Failed,
+        };
+        result.add(outcome, id);
+    }
+    // Collection errors never reach `tests`
+    if let Some(errors) = report.get("collectors").and_then(|c| c.as_array()) {
+        for c in errors {
+            if c.get("outcome").and_then(|v| v.as_str()) == Some("failed") {
+                let id = c.get("nodeid").and_then(|v| v.as_str()).unwrap_or("?");
+                result.add(Outcome::Failed, &format!("{} (collection)", id));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// libtest JSON lines: `{"type": "test", "event": "ok" | "failed" | "ignored", "name": ...}`.
+/// Non-JSON lines (cargo's own output) are skipped.
+fn parse_libtest(content: &str) -> Result<SuiteResult> {
+    let mut result = SuiteResult::default();
+    let mut seen = false;
+    for line in content.lines() {
+        let event: serde_json::Value = match serde_json::from_str(line.trim()) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        match event.get("type").and_then(|v| v.as_str()) {
+            Some("test") => seen = true,
+            Some("suite") => {
+                seen = true;
+                continue;
+            }
+            _ => continue,
+        }
+        let name = event.get("name").and_then(|v| v.as_str()).unwrap_or("?");
+        let outcome = match event.get("event").and_then(|v| v.as_str()) {
+            Some("ok") => Outcome::Passed,
+            Some("ignored") => Outcome::Skipped,
+            Some("failed") | Some("timeout") => Outcome::Failed,
+            _ => continue,
+        };
+        result.add(outcome, name);
+    }
+    if !seen {
+        anyhow::bail!("no libtest JSON events (pass `-- -Z unstable-options --format json`)");
+    }
+    Ok(result)
+}
+
+/// CTest console output: `1/3 Test #1: name ....   Passed    0.01 sec`.
+fn parse_ctest(content: &str) -> Result<SuiteResult> {
+    let line_re =
+        Regex::new(r"Test\s+#\d+:\s+(\S+)\s+\.*\s*(?:\*+)?([A-Za-z][A-Za-z ]*?)\s+[\d.]+\s+sec")
+            .unwrap();
+    let mut result = SuiteResult::default();
+    let mut seen = false;
+    for line in content.lines() {
+        let caps = match line_re.captures(line) {
+            Some(c) => c,
+            None => continue,
+        };
+        seen = true;
+        let outcome = match caps[2].trim() {
+            "Passed" => Outcome::Passed,
+            "Skipped" | "Disabled" => Outcome::Skipped,
+            _ => Outcome::Failed,
+        };
+        result.add(outcome, &caps[1]);
+    }
+    if !seen && !content.contains("No tests were found") {
+        anyhow::bail!("no CTest results in output");
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_junit() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<testsuites><testsuite name="pytest" errors="0" failures="1" skipped="1" tests="4">
+<testcase classname="tests.bounds.test_drag" name="test_lower" time="0.01" />
+<testcase classname="tests.bounds.test_drag" name="test_upper[a&lt;b]" time="0.01">
+  <failure message="assert 12 &lt; 10">AssertionError</failure>
+</testcase>
+<testcase classname="tests.bounds.test_drag" name="test_limit" time="0.0">
+  <skipped type="pytest.skip" message="slow" />
+</testcase>
+<testcase name="test_plain" time="0.0"></testcase>
+</testsuite></testsuites>"#;
+        let r = parse(ResultFormat::Junit, xml).unwrap();
+        assert_eq!((r.passed, r.failed, r.skipped), (2, 1, 1));
+        assert_eq!(r.failing, vec!["tests.bounds.test_drag::test_upper[a<b]"]);
+        assert_eq!(r.fraction(), "2/3 (+1 skipped)");
+        assert!(parse(ResultFormat::Junit, "hello").is_err());
+    }
+
+    #[test]
+    fn test_parse_pytest_json() {
+        let json = r#"{
+  "summary": {"passed": 1, "failed": 1, "skipped": 1, "total": 3},
+  "collectors": [{"nodeid": "tests/software/test_io.py", "outcome": "failed"}],
+  "tests": [
+    {"nodeid": "tests/software/test_a.py::test_ok", "outcome": "passed"},
+    {"nodeid": "tests/software/test_a.py::test_bad", "outcome": "failed"},
+    {"nodeid": "tests/software/test_a.py::test_skip", "outcome": "skipped"}
+  ]
+}"#;
+        let r = parse(ResultFormat::PytestJson, json).unwrap();
+        assert_eq!((r.passed, r.failed, r.skipped), (1, 2, 1));
+        assert_eq!(
+            r.failing,
+            vec![
+                "tests/software/test_a.py::test_bad",
+                "tests/software/test_io.py (collection)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_libtest() {
+        let out = r#"   Compiling model v0.1.0
+{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::a" }
+{ "type": "test", "name": "tests::a", "event": "ok" }
+{ "type": "test", "name": "tests::b", "event": "failed", "stdout": "panicked" }
+{ "type": "test", "name": "tests::c", "event": "ignored" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1 }
+"#;
+        let r = parse(ResultFormat::Libtest, out).unwrap();
+        assert_eq!((r.passed, r.failed, r.skipped), (1, 1, 1));
+        assert_eq!(r.failing, vec!["tests::b"]);
+        assert!(parse(ResultFormat::Libtest, "test result: ok.").is_err());
+    }
+
+    #[test]
+    fn test_parse_ctest() {
+        let out = "Test project /build
+    Start 1: drag_bounds
+1/4 Test #1: drag_bounds ......................   Passed    0.01 sec
+    Start 2: mass_balance
+2/4 Test #2: mass_balance .....................***Failed    0.02 sec
+3/4 Test #3: slow_case ........................***Timeout  600.01 sec
+4/4 Test #4: optional .........................   Skipped    0.00 sec
+
+50% tests passed, 2 tests failed out of 4
+";
+        let r = parse(ResultFormat::Ctest, out).unwrap();
+        assert_eq!((r.passed, r.failed, r.skipped), (1, 2, 1));
+        assert_eq!(r.failing, vec!["mass_balance", "slow_case"]);
+    }
+
+    #[test]
+    fn test_truncated_output_is_not_counted() {
+        let dir = tempfile::tempdir().unwrap();
+        let config: Config =
+            toml::from_str("[project]\nname = \"t\"\n[limits]\ncommand_output_kb = 1\n").unwrap();
+        let source = ResultSource {
+            format: ResultFormat::Libtest,
+            report: String::new(),
+        };
+        let line = r#"echo '{ "type": "test", "name": "t", "event": "ok" }'"#;
+        let r = run_and_parse(line, &source, dir.path(), &config);
+        assert_eq!((r.passed, r.error.as_str()), (1, ""));
+
+        let many = format!("for i in $(seq 1 100); do {}; done", line);
+        let r = run_and_parse(&many, &source, dir.path(), &config);
+        assert_eq!(r.passed, 0);
+        assert!(r.error.contains("command_output_kb"), "{}", r.error);
+        assert_eq!(r.ratio(), "error");
+    }
+
+    #[test]
+    fn test_summary_line_and_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let results = TestResults {
+            bounds: Some(SuiteResult {
+                passed: 8,
+                ..Default::default()
+            }),
+            software: Some(SuiteResult {
+                passed: 14,
+                failed: 1,
+                failing: vec!["test_io".to_string()],
+                ..Default::default()
+            }),
+            integration: None,
+        };
+        assert_eq!(results.summary_line(), "Bounds: 8/8 | Software: 14/15");
+        assert_eq!(results.failing(), vec!["Software: test_io"]);
+
+        let path = results_path(dir.path(), 2);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, toml::to_string_pretty(&results).unwrap()).unwrap();
+        let loaded = load(dir.path(), 2).unwrap();
+        assert_eq!(loaded.software, results.software);
+        assert!(load(dir.path(), 3).is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! Transcripts of agent invocations, raw and as readable markdown.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::backend::AgentEvent;
+use crate::terminal;
+
+/// Header fields for the markdown rendering of a transcript.
+pub struct TranscriptSummary<'a> {
+    pub label: &'a str,
+    pub model: &'a str,
+    pub backend: &'a str,
+    /// "OK", "FAILED exit N", "IDLE TIMEOUT (...)".
+    pub outcome: &'a str,
+    pub elapsed_secs: u64,
+    pub input: &'a str,
+    pub stderr: &'a str,
+}
+
+/// Full record of one agent invocation: the raw stream as `<base>.jsonl`,
+/// written as it arrives, and a readable `<base>.md` written by [`Transcript::finish`].
+pub struct Transcript {
+    base: PathBuf,
+    jsonl: Option<File>,
+    events: Vec<AgentEvent>,
+}
+
+impl Transcript {
+    /// Start a transcript at `base` (a path without extension). Failures to
+    /// create the file are logged and the transcript keeps events in memory only.
+    pub fn create(base: &Path) -> Self {
+        if let Some(parent) = base.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let jsonl = match File::create(base.with_extension("jsonl")) {
+            Ok(f) => Some(f),
+            Err(e) => {
+                terminal::log_warn(&format!(
+                    "Cannot write transcript {}: {}",
+                    base.with_extension("jsonl").display(),
+                    e
+                ));
+                None
+            }
+        };
+        Self {
+            base: base.to_path_buf(),
+            jsonl,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn record_line(&mut self, line: &str) {
+        if let Some(f) = self.jsonl.as_mut() {
+            let _ = writeln!(f, "{}", line);
+        }
+    }
+
+    pub fn record_event(&mut self, event: &AgentEvent) {
+        self.events.push(event.clone());
+    }
+
+    /// Write the markdown rendering. Returns its path.
+    pub fn finish(self, summary: &TranscriptSummary) -> PathBuf {
+        let path = self.base.with_extension("md");
+        if let Err(e) = std::fs::write(&path, render_markdown(summary, &self.events)) {
+            terminal::log_warn(&format!(
+                "Cannot write transcript {}: {}",
+                path.display(),
+                e
+            ));
+        }
+        path
+    }
+}
+
+/// Fence that cannot collide with backtick runs inside `text`.
+fn fence_for(text: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        if c == '`' {
+            run += 1;
+            longest = longest.max(run);
+        } else {
+            run = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn fenced(out: &mut String, lang: &str, text: &str) {
+    let fence = fence_for(text);
+    out.push_str(&format!(
+        "{}{}\n{}\n{}\n",
+        fence,
+        lang,
+        text.trim_end(),
+        fence
+    ));
+}
+
+/// Render a transcript as markdown: header, chronological activity, result, stderr, input.
+pub fn render_markdown(summary: &TranscriptSummary, events: &[AgentEvent]) -> String {
+    let mut out = format!("# Transcript: {}\n\n", summary.label);
+    out.push_str(&format!(
+        "- **Model:** {} (backend: {})\n",
+        summary.model, summary.backend
+    ));
+    out.push_str(&format!("- **Outcome:** {}\n", summary.outcome));
+    out.push_str(&format!("- **Elapsed:** {}s\n", summary.elapsed_secs));
+
+    let tool_count = events
+        .iter()
+        .filter(|e| matches!(e, AgentEvent::ToolUse { .. }))
+        .count();
+    out.push_str(&format!("- **Tool calls:** {}\n", tool_count));
+
+    let mut result = None;
+    out.push_str("\n## Activity\n\n");
+    let mut step = 0;
+    for event in events {
+        match event {
+            AgentEvent::Thinking(thought) => {
+                out.push_str("**Thinking**\n\n");
+                for line in thought.lines() {
+                    out.push_str(&format!("> {}\n", line));
+                }
+                out.push('\n');
+            }
+            AgentEvent::ToolUse { name, input } => {
+                step += 1;
+                out.push_str(&format!("**{}. {}**\n\n", step, name));
+                match (name.as_str(), input.get("command").and_then(|c| c.as_str())) {
+                    ("Bash", Some(cmd)) => fenced(&mut out, "bash", cmd),
+                    _ => fenced(
+                        &mut out,
+                        "json",
+                        &serde_json::to_string_pretty(input).unwrap_or_default(),
+                    ),
+                }
+                out.push('\n');
+            }
+            AgentEvent::Result { text, usage, .. } => {
+                result = Some((text.clone().unwrap_or_default(), usage.cost_usd));
+            }
+            AgentEvent::Usage { .. } | AgentEvent::Session(_) => {}
+        }
+    }
+    if events.is_empty() {
+        out.push_str("_No activity recorded._\n\n");
+    }
+
+    if let Some((text, cost)) = result {
+        out.push_str("## Result\n\n");
+        if cost > 0.0 {
+            out.push_str(&format!("_Cost: ${:.4}_\n\n", cost));
+        }
+        out.push_str(text.trim_end());
+        out.push_str("\n\n");
+    }
+
+    if !summary.stderr.trim().is_empty() {
+        out.push_str("## Stderr\n\n");
+        fenced(&mut out, "", summary.stderr);
+        out.push('\n');
+    }
+
+    out.push_str("## Input\n\n<details>\n<summary>Prompt sent to the agent</summary>\n\n");
+    fenced(&mut out, "markdown", summary.input);
+    out.push_str("\n</details>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::UsageInfo;
+
+    fn summary<'a>(outcome: &'a str, stderr: &'a str) -> TranscriptSummary<'a> {
+        TranscriptSummary {
+            label: "Audit: pass 2",
+            model: "opus",
+            backend: "claude",
+            outcome,
+            elapsed_secs: 42,
+            input: "Audit the code.\n```\nfenced\n```",
+            stderr,
+        }
+    }
+
+    #[test]
+    fn test_render_markdown_in_order() {
+        let events = vec![
+            AgentEvent::Thinking("Check bounds first.\nThen tests.".to_string()),
+            AgentEvent::ToolUse {
+                name: "Read".to_string(),
+                input: serde_json::json!({"file_path": "tests/bounds/test_a.py"}),
+            },
+            AgentEvent::ToolUse {
+                name: "Bash".to_string(),
+                input: serde_json::json!({"command": "pytest tests/"}),
+            },
+            AgentEvent::Result {
+                text: Some("All checks pass.".to_string()),
+                usage: UsageInfo {
+                    cost_usd: 0.5,
+                    ..Default::default()
+                },
+                subtype: Some("success".to_string()),
+                is_error: false,
+            },
+        ];
+        let md = render_markdown(&summary("OK", ""), &events);
+        assert!(md.starts_with("# Transcript: Audit: pass 2\n"));
+        assert!(md.contains("- **Tool calls:** 2"));
+        assert!(md.contains("> Check bounds first.\n> Then tests."));
+        let read = md.find("**1. Read**").unwrap();
+        let bash = md.find("**2. Bash**").unwrap();
+        let result = md.find("## Result").unwrap();
+        assert!(read < bash && bash < result);
+        assert!(md.contains("```bash\npytest tests/\n```"));
+        assert!(md.contains("_Cost: $0.5000_"));
+        assert!(!md.contains("## Stderr"));
+        // Input containing ``` gets a longer fence
+        assert!(md.contains("````markdown\nAudit the code."));
+    }
+
+    #[test]
+    fn test_render_markdown_failure() {
+        let md = render_markdown(&summary("FAILED exit 1", "boom"), &[]);
+        assert!(md.contains("- **Outcome:** FAILED exit 1"));
+        assert!(md.contains("_No activity recorded._"));
+        assert!(md.contains("## Stderr\n\n```\nboom\n```"));
+        assert!(!md.contains("## Result"));
+    }
+
+    #[test]
+    fn test_transcript_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("transcripts/build-3-1");
+        let mut t = Transcript::create(&base);
+        t.record_line(r#"{"type":"system"}"#);
+        t.record_event(&AgentEvent::Thinking("hmm".to_string()));
+        let md = t.finish(&summary("OK", ""));
+        assert_eq!(md, dir.path().join("transcripts/build-3-1.md"));
+        assert_eq!(
+            std::fs::read_to_string(base.with_extension("jsonl")).unwrap(),
+            "{\"type\":\"system\"}\n"
+        );
+        assert!(std::fs::read_to_string(md).unwrap().contains("> hmm"));
+    }
+}
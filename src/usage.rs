@@ -1,14 +1,25 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use crate::agent::UsageInfo;
+use crate::config::ModelPrice;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InvocationRecord {
     pub phase: String,
     pub pass: u32,
+    /// The model that actually ran.
     pub model: String,
+    /// The configured model it replaced, when that one was unavailable or rate-limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_from: Option<String>,
+    /// The `[models]` model it replaced to save budget (`[limits.downgrade]`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub downgraded_from: Option<String>,
     pub input_tokens: u64,
     pub output_tokens: u64,
     pub cache_creation_input_tokens: u64,
@@ -16,6 +27,9 @@ pub struct InvocationRecord {
     pub cost_usd: f64,
     pub elapsed_secs: u64,
     pub timestamp: String,
+    /// Markdown transcript of the invocation, relative to the lisa root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -48,10 +62,68 @@ impl UsageLedger {
     pub fn invocation_count(&self) -> usize {
         self.invocations.len()
     }
+
+    /// Budget downgrades so far: `(phase, from, to)` with how many invocations ran so.
+    pub fn downgrades(&self) -> Vec<((&str, &str, &str), usize)> {
+        let mut counts: BTreeMap<(&str, &str, &str), usize> = BTreeMap::new();
+        for r in &self.invocations {
+            if let Some(from) = &r.downgraded_from {
+                *counts
+                    .entry((r.phase.as_str(), from.as_str(), r.model.as_str()))
+                    .or_default() += 1;
+            }
+        }
+        counts.into_iter().collect()
+    }
+
+    /// Invocations of `phases` in `pass` that ran on a fallback model.
+    pub fn fallbacks<'a>(
+        &'a self,
+        pass: u32,
+        phases: &'a [&str],
+    ) -> impl Iterator<Item = &'a InvocationRecord> + 'a {
+        self.invocations.iter().filter(move |r| {
+            r.pass == pass && r.fallback_from.is_some() && phases.contains(&r.phase.as_str())
+        })
+    }
+}
+
+/// The model an invocation ran on.
+#[derive(Debug, Clone, Copy)]
+pub struct ModelRun<'a> {
+    pub model: &'a str,
+    /// The configured model, if `model` is a fallback for it.
+    pub fallback_from: Option<&'a str>,
+    /// The `[models]` model, if the budget switched the phase to a cheaper list.
+    pub downgraded_from: Option<&'a str>,
+}
+
+thread_local! {
+    static SHARED: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Held while an invocation is added, so lanes recording at once keep each other's.
+static RECORDING: Mutex<()> = Mutex::new(());
+
+/// Run `f` with the ledger of `lisa_root` standing in for whichever one this
+/// thread loads, saves or records to. Parallel build lanes have their own
+/// lisa root but one budget: every lane's spend counts in the checks of the others.
+pub fn share_ledger<T>(lisa_root: &Path, f: impl FnOnce() -> T) -> T {
+    let previous = SHARED.with(|s| s.replace(Some(lisa_root.to_path_buf())));
+    let result = f();
+    SHARED.with(|s| *s.borrow_mut() = previous);
+    result
+}
+
+fn ledger_path(lisa_root: &Path) -> PathBuf {
+    SHARED
+        .with(|s| s.borrow().clone())
+        .unwrap_or_else(|| lisa_root.to_path_buf())
+        .join("usage.toml")
 }
 
 pub fn load_usage(lisa_root: &Path) -> Result<UsageLedger> {
-    let path = lisa_root.join("usage.toml");
+    let path = ledger_path(lisa_root);
     if !path.exists() {
         return Ok(UsageLedger::default());
     }
@@ -63,8 +135,10 @@ pub fn load_usage(lisa_root: &Path) -> Result<UsageLedger> {
 }
 
 pub fn save_usage(lisa_root: &Path, ledger: &UsageLedger) -> Result<()> {
-    let path = lisa_root.join("usage.toml");
-    std::fs::create_dir_all(lisa_root)?;
+    let path = ledger_path(lisa_root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
     let content = toml::to_string_pretty(ledger).with_context(|| "Failed to serialize usage")?;
     std::fs::write(&path, content)
         .with_context(|| format!("Failed to write {}", path.display()))?;
@@ -76,15 +150,19 @@ pub fn record_invocation(
     lisa_root: &Path,
     phase: &str,
     pass: u32,
-    model: &str,
+    model: ModelRun,
     usage: &UsageInfo,
     elapsed_secs: u64,
+    transcript: Option<&Path>,
 ) -> Result<f64> {
+    let _recording = RECORDING.lock().unwrap_or_else(|e| e.into_inner());
     let mut ledger = load_usage(lisa_root)?;
     ledger.invocations.push(InvocationRecord {
         phase: phase.to_string(),
         pass,
-        model: model.to_string(),
+        model: model.model.to_string(),
+        fallback_from: model.fallback_from.map(String::from),
+        downgraded_from: model.downgraded_from.map(String::from),
         input_tokens: usage.input_tokens,
         output_tokens: usage.output_tokens,
         cache_creation_input_tokens: usage.cache_creation_input_tokens,
@@ -92,6 +170,12 @@ pub fn record_invocation(
         cost_usd: usage.cost_usd,
         elapsed_secs,
         timestamp: chrono::Local::now().to_rfc3339(),
+        transcript: transcript.map(|p| {
+            p.strip_prefix(lisa_root)
+                .unwrap_or(p)
+                .to_string_lossy()
+                .replace('\\', "/")
+        }),
     });
     save_usage(lisa_root, &ledger)?;
     Ok(ledger.total_cost())
@@ -122,6 +206,16 @@ pub fn check_budget(cumulative_cost: f64, budget_usd: f64, budget_warn_pct: u32)
     BudgetStatus::Ok
 }
 
+/// Estimated cost of `usage` at `price`. Agents report the actual cost only
+/// when they finish.
+pub fn estimate_cost(price: &ModelPrice, usage: &UsageInfo) -> f64 {
+    let tokens = |n: u64| n as f64 / 1_000_000.0;
+    tokens(usage.input_tokens) * price.input
+        + tokens(usage.cache_creation_input_tokens) * price.input * 1.25
+        + tokens(usage.cache_read_input_tokens) * price.input * 0.1
+        + tokens(usage.output_tokens) * price.output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,6 +227,8 @@ mod tests {
                     phase: "scope".to_string(),
                     pass: 0,
                     model: "opus".to_string(),
+                    fallback_from: None,
+                    downgraded_from: None,
                     input_tokens: 1000,
                     output_tokens: 500,
                     cache_creation_input_tokens: 0,
@@ -140,11 +236,14 @@ mod tests {
                     cost_usd: 0.05,
                     elapsed_secs: 30,
                     timestamp: "2025-01-01T00:00:00+00:00".to_string(),
+                    transcript: None,
                 },
                 InvocationRecord {
                     phase: "build".to_string(),
                     pass: 1,
                     model: "sonnet".to_string(),
+                    fallback_from: Some("opus".to_string()),
+                    downgraded_from: None,
                     input_tokens: 2000,
                     output_tokens: 1000,
                     cache_creation_input_tokens: 100,
@@ -152,6 +251,7 @@ mod tests {
                     cost_usd: 0.03,
                     elapsed_secs: 45,
                     timestamp: "2025-01-01T00:01:00+00:00".to_string(),
+                    transcript: Some("spiral/pass-1/transcripts/build-1-1.md".to_string()),
                 },
             ],
         }
@@ -178,6 +278,30 @@ mod tests {
         assert!((ledger.pass_cost(99)).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_fallbacks() {
+        let ledger = sample_ledger();
+        let build: Vec<_> = ledger.fallbacks(1, &["build"]).collect();
+        assert_eq!(build.len(), 1);
+        assert_eq!(build[0].model, "sonnet");
+        assert_eq!(ledger.fallbacks(1, &["bounds", "audit"]).count(), 0);
+        assert_eq!(ledger.fallbacks(0, &["scope"]).count(), 0);
+    }
+
+    #[test]
+    fn test_downgrades() {
+        let mut ledger = sample_ledger();
+        assert!(ledger.downgrades().is_empty());
+        for _ in 0..2 {
+            let mut r = ledger.invocations[1].clone();
+            r.model = "haiku".to_string();
+            r.fallback_from = None;
+            r.downgraded_from = Some("sonnet".to_string());
+            ledger.invocations.push(r);
+        }
+        assert_eq!(ledger.downgrades(), vec![(("build", "sonnet", "haiku"), 2)]);
+    }
+
     #[test]
     fn test_empty_ledger() {
         let ledger = UsageLedger::default();
@@ -194,6 +318,80 @@ mod tests {
         let parsed: UsageLedger = toml::from_str(&toml_str).unwrap();
         assert_eq!(parsed.invocations.len(), 2);
         assert!((parsed.total_cost() - 0.08).abs() < 1e-10);
+        assert_eq!(parsed.invocations[0].transcript, None);
+        assert_eq!(parsed.invocations[0].fallback_from, None);
+        assert_eq!(parsed.invocations[1].fallback_from.as_deref(), Some("opus"));
+        assert_eq!(
+            parsed.invocations[1].transcript.as_deref(),
+            Some("spiral/pass-1/transcripts/build-1-1.md")
+        );
+    }
+
+    #[test]
+    fn test_record_invocation_links_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let lisa_root = dir.path();
+        let transcript = lisa_root.join("spiral/pass-2/transcripts/audit.md");
+        record_invocation(
+            lisa_root,
+            "audit",
+            2,
+            ModelRun {
+                model: "opus",
+                fallback_from: None,
+                downgraded_from: None,
+            },
+            &UsageInfo::default(),
+            10,
+            Some(&transcript),
+        )
+        .unwrap();
+        let ledger = load_usage(lisa_root).unwrap();
+        assert_eq!(
+            ledger.invocations[0].transcript.as_deref(),
+            Some("spiral/pass-2/transcripts/audit.md")
+        );
+    }
+
+    #[test]
+    fn test_lanes_record_to_the_shared_ledger() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main");
+        let record = |lisa_root: &Path| {
+            let usage = UsageInfo {
+                cost_usd: 0.5,
+                ..Default::default()
+            };
+            let run = ModelRun {
+                model: "opus",
+                fallback_from: None,
+                downgraded_from: None,
+            };
+            let transcript = lisa_root.join("spiral/pass-1/transcripts/build.md");
+            record_invocation(lisa_root, "build", 1, run, &usage, 1, Some(&transcript)).unwrap()
+        };
+        std::thread::scope(|scope| {
+            for lane in 0..4 {
+                let lane_root = dir.path().join(format!("lane{}", lane));
+                let main = &main;
+                scope.spawn(move || {
+                    share_ledger(main, || {
+                        for _ in 0..5 {
+                            record(&lane_root);
+                        }
+                    })
+                });
+            }
+        });
+        let ledger = load_usage(&main).unwrap();
+        assert_eq!(ledger.invocation_count(), 20);
+        assert_eq!(
+            ledger.invocations[0].transcript.as_deref(),
+            Some("spiral/pass-1/transcripts/build.md")
+        );
+        assert!(!dir.path().join("lane0/usage.toml").exists());
+        // The next lane's budget check sees everyone's spend
+        assert!((record(&main) - 10.5).abs() < 1e-10);
     }
 
     #[test]
@@ -215,4 +413,21 @@ mod tests {
     fn test_check_budget_warning() {
         assert_eq!(check_budget(0.85, 1.0, 80), BudgetStatus::Warning);
     }
+
+    #[test]
+    fn test_estimate_cost() {
+        let usage = UsageInfo {
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 1_000_000,
+            cost_usd: 0.0,
+        };
+        let sonnet = ModelPrice {
+            input: 3.0,
+            output: 15.0,
+        };
+        let cost = estimate_cost(&sonnet, &usage);
+        assert!((cost - (3.0 + 0.3 + 1.5)).abs() < 1e-9, "{}", cost);
+    }
 }
@@ -0,0 +1,560 @@
+//! Per-phase write scopes: which paths each phase may change, and what happens
+//! to changes outside them.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::config::Config;
+use crate::git;
+use crate::snapshot::{Snapshot, SnapshotDiff};
+use crate::terminal;
+
+/// Path prefixes (relative to the project root, `/`-separated) a phase may write.
+/// `None` means the phase is unrestricted.
+pub fn allowed_paths(phase: &str, pass: u32, config: &Config) -> Option<Vec<String>> {
+    let lisa = config.paths.lisa_root.trim_end_matches('/');
+    let pass_dir = format!("{}/spiral/pass-{}/", lisa, pass);
+    let dir = |d: &str| format!("{}/", d.trim_end_matches('/'));
+    let mut allowed = match phase {
+        // Research may lay out a greenfield project and edit lisa.toml; exploration
+        // happens on a side branch.
+        "research" | "explore" => return None,
+        "validation_design" | "planning" => vec![format!("{}/", lisa)],
+        "refine" | "refine_methodology" | "refine_plan" => vec![
+            format!("{}/methodology/", lisa),
+            format!("{}/validation/", lisa),
+            format!("{}/references/", lisa),
+            format!("{}/STACK.md", lisa),
+            pass_dir,
+        ],
+        "bounds" => vec![
+            dir(&config.paths.tests_bounds),
+            format!("{}plots/", pass_dir),
+        ],
+        "build" => {
+            let mut paths: Vec<String> = config.paths.source.iter().map(|s| dir(s)).collect();
+            paths.extend([
+                dir(&config.paths.tests_software),
+                dir(&config.paths.tests_integration),
+                format!("{}/methodology/plan.md", lisa),
+                format!("{}/methodology/derivations/", lisa),
+                pass_dir,
+            ]);
+            paths
+        }
+        // The audit prompt allows filling bounding-test gaps
+        "audit" => vec![pass_dir, dir(&config.paths.tests_bounds)],
+        "finalize" => vec![format!("{}/output/", lisa), pass_dir],
+        _ => return None,
+    };
+    allowed.extend(config.write_scope.allow.iter().cloned());
+    allowed.retain(|p| !p.is_empty() && p != "/");
+    Some(allowed)
+}
+
+/// Changed or deleted files outside `allowed`, as `/`-separated relative paths.
+pub fn out_of_scope(diff: &SnapshotDiff, allowed: &[String]) -> Vec<String> {
+    let mut paths: Vec<String> = diff
+        .changed
+        .iter()
+        .chain(&diff.deleted)
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .filter(|p| !allowed.iter().any(|a| in_scope(p, a)))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    paths
+}
+
+/// Blob ids of the files under `lisa_root` that are outside `allowed`, keyed
+/// by relative path, so writes to them can be reverted without git history.
+pub fn backup(
+    config: &Config,
+    project_root: &Path,
+    snapshot: &Snapshot,
+    allowed: &[String],
+) -> Result<BTreeMap<String, String>> {
+    let lisa = Path::new(&config.paths.lisa_root);
+    let paths: Vec<&Path> = snapshot
+        .paths()
+        .filter(|p| p.starts_with(lisa))
+        .filter(|p| {
+            let p = p.to_string_lossy().replace('\\', "/");
+            !allowed.iter().any(|a| in_scope(&p, a))
+        })
+        .collect();
+    let files: Vec<_> = paths.iter().map(|p| project_root.join(p)).collect();
+    let blobs = git::hash_objects(&files.iter().map(|f| f.as_path()).collect::<Vec<_>>())?;
+    Ok(paths
+        .iter()
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .zip(blobs)
+        .collect())
+}
+
+fn in_scope(path: &str, allowed: &str) -> bool {
+    let allowed = allowed.trim_start_matches("./");
+    if allowed.ends_with('/') {
+        path.starts_with(allowed)
+    } else {
+        path == allowed || path.starts_with(&format!("{}/", allowed))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ViolationStatus {
+    /// Waiting for the next review gate.
+    #[default]
+    Pending,
+    Reverted,
+    /// Kept by the reviewer (or with gates disabled).
+    Accepted,
+}
+
+/// Out-of-scope writes of one agent invocation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    pub label: String,
+    pub phase: String,
+    pub pass: u32,
+    /// HEAD before the agent ran.
+    #[serde(default)]
+    pub base: String,
+    pub paths: Vec<String>,
+    /// Blob id of each path before the agent ran, empty if it did not exist.
+    /// Paths without an entry cannot be reverted.
+    #[serde(default)]
+    pub before: BTreeMap<String, String>,
+    /// Blob id of what the agent left at each path, empty if it deleted it.
+    #[serde(default)]
+    pub written: BTreeMap<String, String>,
+    #[serde(default)]
+    pub status: ViolationStatus,
+}
+
+impl Violation {
+    /// Record `paths` as the out-of-scope writes of one invocation, with their
+    /// content before it (from `base`, or `backup` under `lisa_root`) and now.
+    #[allow(clippy::too_many_arguments)]
+    pub fn capture(
+        label: &str,
+        phase: &str,
+        pass: u32,
+        base: Option<String>,
+        paths: Vec<String>,
+        backup: &BTreeMap<String, String>,
+        config: &Config,
+        project_root: &Path,
+    ) -> Result<Self> {
+        let lisa = format!("{}/", config.paths.lisa_root.trim_end_matches('/'));
+        let mut before = BTreeMap::new();
+        let mut written = BTreeMap::new();
+        for path in &paths {
+            let blob = if path.starts_with(&lisa) {
+                Some(backup.get(path).cloned().unwrap_or_default())
+            } else {
+                // Without a commit there is no earlier version to go back to
+                base.as_ref()
+                    .map(|base| git::rev_parse(&format!("{}:{}", base, path)).unwrap_or_default())
+            };
+            if let Some(blob) = blob {
+                before.insert(path.clone(), blob);
+            }
+            written.insert(path.clone(), blob_of(&project_root.join(path))?);
+        }
+        Ok(Self {
+            label: label.to_string(),
+            phase: phase.to_string(),
+            pass,
+            base: base.unwrap_or_default(),
+            paths,
+            before,
+            written,
+            status: ViolationStatus::Pending,
+        })
+    }
+}
+
+/// Blob id of `file`'s content, empty if it does not exist.
+fn blob_of(file: &Path) -> Result<String> {
+    if !file.exists() {
+        return Ok(String::new());
+    }
+    Ok(git::hash_objects(&[file])?.pop().unwrap_or_default())
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ViolationLog {
+    #[serde(default)]
+    violations: Vec<Violation>,
+}
+
+fn log_path(lisa_root: &Path) -> std::path::PathBuf {
+    lisa_root.join("write-scope.toml")
+}
+
+fn load(lisa_root: &Path) -> Result<ViolationLog> {
+    let path = log_path(lisa_root);
+    if !path.exists() {
+        return Ok(ViolationLog::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn save(lisa_root: &Path, log: &ViolationLog) -> Result<()> {
+    let path = log_path(lisa_root);
+    std::fs::write(&path, toml::to_string_pretty(log)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn record(lisa_root: &Path, violation: Violation) -> Result<()> {
+    let mut log = load(lisa_root)?;
+    log.violations.push(violation);
+    save(lisa_root, &log)
+}
+
+/// Violations not yet reverted or accepted.
+pub fn pending(lisa_root: &Path) -> Result<Vec<Violation>> {
+    Ok(load(lisa_root)?
+        .violations
+        .into_iter()
+        .filter(|v| v.status == ViolationStatus::Pending)
+        .collect())
+}
+
+pub fn accept_pending(lisa_root: &Path) -> Result<()> {
+    set_pending_status(lisa_root, ViolationStatus::Accepted)
+}
+
+fn set_pending_status(lisa_root: &Path, status: ViolationStatus) -> Result<()> {
+    let mut log = load(lisa_root)?;
+    let mut changed = false;
+    for v in log
+        .violations
+        .iter_mut()
+        .filter(|v| v.status == ViolationStatus::Pending)
+    {
+        v.status = status;
+        changed = true;
+    }
+    if changed {
+        save(lisa_root, &log)?;
+    }
+    Ok(())
+}
+
+/// Mark `paths` as already reverted by lisa (e.g. bounding tests restored by
+/// the tamper guard) so the review gate does not offer them again.
+pub fn resolve_reverted(lisa_root: &Path, paths: &[String]) -> Result<()> {
+    let mut log = load(lisa_root)?;
+    let mut changed = false;
+    for v in log
+        .violations
+        .iter_mut()
+        .filter(|v| v.status == ViolationStatus::Pending)
+    {
+        if v.paths.iter().all(|p| paths.contains(p)) {
+            v.status = ViolationStatus::Reverted;
+            changed = true;
+        } else if v.paths.iter().any(|p| paths.contains(p)) {
+            v.paths.retain(|p| !paths.contains(p));
+            changed = true;
+        }
+    }
+    if changed {
+        save(lisa_root, &log)?;
+    }
+    Ok(())
+}
+
+/// Undo one invocation's out-of-scope writes, path by path. A file the agent
+/// created is removed, others get their previous content back. When a file
+/// changed again after the agent wrote it, only the agent's change is taken
+/// out of it. Returns the paths that could not be reverted.
+pub fn revert(violation: &Violation, project_root: &Path) -> Result<Vec<String>> {
+    let mut kept = Vec::new();
+    for path in &violation.paths {
+        let reverted = match (violation.before.get(path), violation.written.get(path)) {
+            (Some(before), Some(written)) => {
+                revert_file(&project_root.join(path), before, written)?
+            }
+            _ => false,
+        };
+        if !reverted {
+            kept.push(path.clone());
+        }
+    }
+    Ok(kept)
+}
+
+/// Undo the change of `file` from blob `before` to blob `written` (empty for
+/// a missing file). Returns false when it conflicts with later changes.
+fn revert_file(file: &Path, before: &str, written: &str) -> Result<bool> {
+    let current = blob_of(file)?;
+    let content = if current == written {
+        (!before.is_empty())
+            .then(|| git::read_blob(before))
+            .transpose()?
+    } else if before == written {
+        return Ok(true);
+    } else if current.is_empty() || before.is_empty() || written.is_empty() {
+        return Ok(false);
+    } else {
+        match git::merge_blobs(&current, written, before)? {
+            Some(merged) => Some(merged),
+            None => return Ok(false),
+        }
+    };
+    match content {
+        Some(content) => {
+            if let Some(parent) = file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(file, content)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+        }
+        None if file.exists() => std::fs::remove_file(file)
+            .with_context(|| format!("Failed to remove {}", file.display()))?,
+        None => {}
+    }
+    Ok(true)
+}
+
+/// Revert every pending violation and commit the result.
+pub fn revert_pending(config: &Config, project_root: &Path) -> Result<()> {
+    let lisa_root = config.lisa_root(project_root);
+    for violation in pending(&lisa_root)? {
+        let kept = revert(&violation, project_root)?;
+        if !kept.is_empty() {
+            terminal::log_warn(&format!(
+                "Not reverted (changed again since, or no earlier version): {}",
+                kept.join(", ")
+            ));
+        }
+    }
+    set_pending_status(&lisa_root, ViolationStatus::Reverted)?;
+    git::commit_all("revert out-of-scope agent writes", config)?;
+    terminal::log_success("Out-of-scope writes reverted.");
+    Ok(())
+}
+
+/// Report an invocation's out-of-scope writes, reverting them right away with
+/// `auto_revert` and otherwise leaving them for the next pass review gate.
+pub fn flag(config: &Config, project_root: &Path, mut violation: Violation) -> Result<()> {
+    terminal::log_warn(&format!(
+        "{} wrote outside the {} phase's scope:\n{}",
+        violation.label,
+        violation.phase,
+        violation
+            .paths
+            .iter()
+            .map(|p| format!("  - {}", p))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ));
+    if config.write_scope.auto_revert {
+        let kept = revert(&violation, project_root)?;
+        violation.status = ViolationStatus::Reverted;
+        if kept.is_empty() {
+            terminal::log_info("Reverted (write_scope.auto_revert = true).");
+        } else {
+            terminal::log_warn(&format!(
+                "Reverted, except (changed again since, or no earlier version): {}",
+                kept.join(", ")
+            ));
+        }
+    } else {
+        terminal::log_info("Flagged for the next review gate.");
+    }
+    record(&config.lisa_root(project_root), violation)
+}
+
+/// Print pending violations. Returns false when there are none.
+pub fn display_pending(lisa_root: &Path) -> bool {
+    let pending = pending(lisa_root).unwrap_or_default();
+    if pending.is_empty() {
+        return false;
+    }
+    terminal::print_colored("  Out-of-scope writes:\n", crossterm::style::Color::Red);
+    for v in &pending {
+        println!("    {} (pass {}):", v.label, v.pass);
+        for path in &v.paths {
+            println!("      - {}", path);
+        }
+    }
+    println!();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn config() -> Config {
+        toml::from_str(
+            r#"
+[project]
+name = "t"
+
+[paths]
+source = ["src"]
+tests_bounds = "tests/bounds"
+tests_software = "tests/software"
+tests_integration = "tests/integration"
+
+[write_scope]
+allow = ["build/"]
+"#,
+        )
+        .unwrap()
+    }
+
+    fn diff(changed: &[&str], deleted: &[&str]) -> SnapshotDiff {
+        SnapshotDiff {
+            changed: changed.iter().map(PathBuf::from).collect(),
+            deleted: deleted.iter().map(PathBuf::from).collect(),
+        }
+    }
+
+    #[test]
+    fn test_build_editing_bounding_test_is_out_of_scope() {
+        let allowed = allowed_paths("build", 2, &config()).unwrap();
+        let changes = diff(
+            &[
+                "src/model.py",
+                "tests/software/test_model.py",
+                "tests/bounds/phenomenon/test_drag.py",
+                ".lisa/methodology/plan.md",
+                ".lisa/methodology/methodology.md",
+                ".lisa/spiral/pass-2/execution-report.md",
+                "build/out.o",
+                "src-old/x.py",
+            ],
+            &["tests/bounds/system/test_total.py"],
+        );
+        assert_eq!(
+            out_of_scope(&changes, &allowed),
+            vec![
+                ".lisa/methodology/methodology.md",
+                "src-old/x.py",
+                "tests/bounds/phenomenon/test_drag.py",
+                "tests/bounds/system/test_total.py",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_phase_scopes() {
+        let config = config();
+        let bounds = allowed_paths("bounds", 1, &config).unwrap();
+        assert_eq!(
+            out_of_scope(
+                &diff(
+                    &[
+                        "tests/bounds/test_a.py",
+                        ".lisa/spiral/pass-1/plots/a.png",
+                        "src/model.py"
+                    ],
+                    &[]
+                ),
+                &bounds
+            ),
+            vec!["src/model.py"]
+        );
+        let audit = allowed_paths("audit", 3, &config).unwrap();
+        assert_eq!(
+            out_of_scope(
+                &diff(
+                    &[
+                        ".lisa/spiral/pass-3/review-package.md",
+                        ".lisa/spiral/pass-2/review-package.md"
+                    ],
+                    &[]
+                ),
+                &audit
+            ),
+            vec![".lisa/spiral/pass-2/review-package.md"]
+        );
+        assert!(allowed_paths("research", 0, &config).is_none());
+    }
+
+    #[test]
+    fn test_revert_keeps_later_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::process::Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(root)
+            .status()
+            .unwrap();
+        let file = root.join("notes.md");
+        crate::process::in_dir(root, || {
+            std::fs::write(&file, "one\ntwo\nthree\nfour\n").unwrap();
+            let before = blob_of(&file).unwrap();
+            std::fs::write(&file, "one\nTWO\nthree\nfour\n").unwrap();
+            let written = blob_of(&file).unwrap();
+            let created = root.join("new.md");
+            std::fs::write(&created, "new\n").unwrap();
+            let violation = Violation {
+                label: "Build: task 1 iter 1".to_string(),
+                phase: "build".to_string(),
+                pass: 1,
+                base: String::new(),
+                paths: vec![
+                    "new.md".to_string(),
+                    "notes.md".to_string(),
+                    "unknown.md".to_string(),
+                ],
+                before: BTreeMap::from([
+                    ("notes.md".to_string(), before),
+                    ("new.md".to_string(), String::new()),
+                ]),
+                written: BTreeMap::from([
+                    ("notes.md".to_string(), written),
+                    ("new.md".to_string(), blob_of(&created).unwrap()),
+                ]),
+                status: ViolationStatus::Pending,
+            };
+            // Edited again after the agent: only the agent's line is undone
+            std::fs::write(&file, "one\nTWO\nthree\nFOUR\n").unwrap();
+
+            let kept = revert(&violation, root).unwrap();
+            assert_eq!(kept, vec!["unknown.md"]);
+            assert_eq!(
+                std::fs::read_to_string(&file).unwrap(),
+                "one\ntwo\nthree\nFOUR\n"
+            );
+            assert!(!created.exists());
+        });
+    }
+
+    #[test]
+    fn test_pending_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let lisa_root = dir.path();
+        let violation = Violation {
+            label: "Build: task 1 iter 1".to_string(),
+            phase: "build".to_string(),
+            pass: 1,
+            base: "abc".to_string(),
+            paths: vec!["tests/bounds/test_a.py".to_string()],
+            before: BTreeMap::new(),
+            written: BTreeMap::new(),
+            status: ViolationStatus::Pending,
+        };
+        record(lisa_root, violation.clone()).unwrap();
+        record(lisa_root, violation).unwrap();
+        assert_eq!(pending(lisa_root).unwrap().len(), 2);
+
+        accept_pending(lisa_root).unwrap();
+        assert!(pending(lisa_root).unwrap().is_empty());
+        let content = std::fs::read_to_string(lisa_root.join("write-scope.toml")).unwrap();
+        assert_eq!(content.matches("status = \"accepted\"").count(), 2);
+    }
+}
@@ -0,0 +1,1402 @@
+//! End-to-end runs of the `lisa` binary against recorded agent invocations.
+
+use std::path::Path;
+use std::process::Command;
+
+fn git(root: &Path, args: &[&str]) -> String {
+    let out = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "git {:?} failed", args);
+    String::from_utf8_lossy(&out.stdout).to_string()
+}
+
+/// Write a recording in the layout `lisa run --record` produces.
+fn record(dir: &Path, n: u32, label: &str, effects: &[(&str, &str)]) {
+    let rec = dir.join(format!("{:04}-rec", n));
+    std::fs::create_dir_all(rec.join("effects")).unwrap();
+    std::fs::write(rec.join("input.md"), "").unwrap();
+    std::fs::write(
+        rec.join("stream.jsonl"),
+        format!(
+            "{}\n",
+            serde_json::json!({
+                "type": "result",
+                "result": format!("{} done", label),
+                "total_cost_usd": 0.01,
+                "usage": {"input_tokens": 100, "output_tokens": 10},
+            })
+        ),
+    )
+    .unwrap();
+    let mut changed = Vec::new();
+    for (path, content) in effects {
+        let dest = rec.join("effects").join(path);
+        std::fs::create_dir_all(dest.parent().unwrap()).unwrap();
+        std::fs::write(dest, content).unwrap();
+        changed.push(format!("{:?}", path));
+    }
+    std::fs::write(
+        rec.join("meta.toml"),
+        format!(
+            "label = {:?}\nbackend = \"claude\"\nmodel = \"opus\"\ntimestamp = \"\"\nexit_code = 0\nchanged = [{}]\n",
+            label,
+            changed.join(", ")
+        ),
+    )
+    .unwrap();
+}
+
+const PLAN_TODO: &str = "# Implementation Plan\n\n## Tasks\n\n\
+### Task 1: Core model\n\
+- **Status:** TODO\n\
+- **Pass:** 1\n\
+- **Methodology:** 2.1\n\
+- **Dependencies:** None\n";
+
+/// A git repo with lisa.toml, ASSIGNMENT.md and the configured source/test dirs.
+fn setup_project() -> tempfile::TempDir {
+    let project = tempfile::tempdir().unwrap();
+    let root = project.path();
+    git(root, &["init", "-q"]);
+    git(root, &["config", "user.name", "Test"]);
+    git(root, &["config", "user.email", "test@example.com"]);
+
+    std::fs::write(root.join("lisa.toml"), LISA_TOML).unwrap();
+    std::fs::write(
+        root.join("ASSIGNMENT.md"),
+        "# Assignment\n\nModel a thing.\n",
+    )
+    .unwrap();
+    std::fs::write(root.join(".gitignore"), ".lisa/\nrecordings*/\n").unwrap();
+    for dir in ["src", "tests/bounds", "tests/software", "tests/integration"] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+        std::fs::write(root.join(dir).join(".gitkeep"), "").unwrap();
+    }
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "init"]);
+    project
+}
+
+/// Scope recordings: research, validation design, and planning of a single task.
+fn record_scope(recordings: &Path) {
+    record(
+        recordings,
+        1,
+        "Research",
+        &[(
+            ".lisa/methodology/methodology.md",
+            "# Methodology\n\n## 2.1 Core model\n",
+        )],
+    );
+    record(recordings, 2, "Validation Design", &[]);
+    record(
+        recordings,
+        3,
+        "Planning",
+        &[
+            (".lisa/methodology/plan.md", PLAN_TODO),
+            (".lisa/spiral/pass-0/PASS_COMPLETE.md", "done\n"),
+        ],
+    );
+    record(recordings, 4, "Refine methodology: pass 1", &[]);
+    record(
+        recordings,
+        5,
+        "Refine plan: pass 1",
+        &[(
+            ".lisa/spiral/pass-1/refine-summary.md",
+            "# Refine Summary — Pass 1\n\nOne task.\n",
+        )],
+    );
+}
+
+/// Build of task 1 followed by the pass 1 audit.
+fn record_build_and_audit(recordings: &Path, first: u32) {
+    record(
+        recordings,
+        first,
+        "Build: task 1 iter 1",
+        &[
+            (
+                ".lisa/methodology/plan.md",
+                &PLAN_TODO.replace("TODO", "DONE"),
+            ),
+            ("src/model.py", "def model():\n    return 1\n"),
+        ],
+    );
+    record(
+        recordings,
+        first + 1,
+        "Audit: pass 1",
+        &[(".lisa/spiral/pass-1/review-package.md", "# Review\n")],
+    );
+}
+
+fn lisa(root: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_lisa"))
+        .args(args)
+        .current_dir(root)
+        .output()
+        .unwrap()
+}
+
+fn assert_success(out: &std::process::Output) {
+    assert!(
+        out.status.success(),
+        "lisa failed:\n{}\n{}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+#[test]
+fn test_replayed_run_completes_scope_and_first_pass() {
+    let project = setup_project();
+    let root = project.path();
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record_build_and_audit(&recordings, 6);
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+
+    let state = std::fs::read_to_string(root.join(".lisa/state.toml")).unwrap();
+    assert!(state.contains("PassReview"), "unexpected state: {}", state);
+    assert!(root.join("src/model.py").exists());
+    assert!(git(root, &["tag"]).contains("lisa/pass-1"));
+    assert!(git(root, &["log", "--format=%s"]).contains("build: pass 1 task 1"));
+
+    let usage = std::fs::read_to_string(root.join(".lisa/usage.toml")).unwrap();
+    assert_eq!(usage.matches("[[invocations]]").count(), 7);
+    assert!(usage.contains(r#"transcript = "spiral/pass-1/transcripts/build-1-1.md""#));
+    let transcripts = root.join(".lisa/spiral/pass-1/transcripts");
+    assert!(transcripts.join("build-1-1.jsonl").exists());
+    let md = std::fs::read_to_string(transcripts.join("build-1-1.md")).unwrap();
+    assert!(md.contains("Build: task 1 iter 1 done"));
+
+    let summary =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/refine-summary.md")).unwrap();
+    assert!(summary.starts_with("# Refine Summary — Pass 1\n\nOne task.\n"));
+    assert!(summary.contains("## Task Graph\n\n```mermaid\nflowchart LR\n"));
+    assert!(summary.contains("task1[\"1: Core model<br/>TODO\"]"));
+}
+
+#[test]
+fn test_invalid_plan_goes_back_to_planning_agent() {
+    let project = setup_project();
+    let root = project.path();
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    // The first plan depends on a task that doesn't exist; the revision fixes it
+    record(
+        &recordings,
+        3,
+        "Planning",
+        &[(
+            ".lisa/methodology/plan.md",
+            &PLAN_TODO.replace("**Dependencies:** None", "**Dependencies:** Task 9"),
+        )],
+    );
+    record(
+        &recordings,
+        6,
+        "Planning",
+        &[
+            (".lisa/methodology/plan.md", PLAN_TODO),
+            (".lisa/spiral/pass-0/PASS_COMPLETE.md", "done\n"),
+        ],
+    );
+    record_build_and_audit(&recordings, 7);
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Task 1 depends on Task 9, which does not exist"));
+    assert!(stdout.contains("Sending the plan back for revision (1/2)"));
+    assert!(!stdout.contains("revision (2/2)"));
+
+    let usage = std::fs::read_to_string(root.join(".lisa/usage.toml")).unwrap();
+    assert_eq!(usage.matches(r#"phase = "planning""#).count(), 2);
+    assert!(git(root, &["tag"]).contains("lisa/pass-1"));
+}
+
+#[test]
+fn test_resume_after_failed_build() {
+    let project = setup_project();
+    let root = project.path();
+
+    // First run: the build agent crashes
+    let first = root.join("recordings");
+    record_scope(&first);
+    record(&first, 6, "Build: task 1 iter 1", &[]);
+    let meta = first.join("0006-rec/meta.toml");
+    let content = std::fs::read_to_string(&meta).unwrap();
+    std::fs::write(&meta, content.replace("exit_code = 0", "exit_code = 1")).unwrap();
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert!(!out.status.success());
+    let state = std::fs::read_to_string(root.join(".lisa/state.toml")).unwrap();
+    assert!(state.contains("Build"), "unexpected state: {}", state);
+    assert!(root.join(".lisa/last-error.md").exists());
+
+    // Resume picks up at the build phase and finishes the pass
+    let second = root.join("recordings-resume");
+    record_build_and_audit(&second, 1);
+    let out = lisa(
+        root,
+        &["resume", "--no-pause", "--replay", "recordings-resume"],
+    );
+    assert_success(&out);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("Previous failure context"));
+    assert!(root.join("src/model.py").exists());
+    assert!(git(root, &["tag"]).contains("lisa/pass-1"));
+}
+
+#[test]
+fn test_sandboxed_bounds_only_returns_bounding_tests() {
+    let project = setup_project();
+    let root = project.path();
+    let config = format!(
+        "{}\n[bounds]\nsandbox = true\non_source_access = \"fail\"\n",
+        LISA_TOML
+    );
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["commit", "-q", "-am", "sandbox bounds"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    // Re-record planning with a task that needs bounds
+    let plan = format!("{}- **Bounding Checks:** L1 drag coefficient\n", PLAN_TODO);
+    record(
+        &recordings,
+        3,
+        "Planning",
+        &[
+            (".lisa/methodology/plan.md", &plan),
+            (".lisa/spiral/pass-0/PASS_COMPLETE.md", "done\n"),
+        ],
+    );
+    record(
+        &recordings,
+        6,
+        "Bounds: task 1",
+        &[
+            (
+                "tests/bounds/phenomenon/test_drag.py",
+                "def test_drag(): pass\n",
+            ),
+            (".lisa/spiral/pass-1/plots/REVIEW.md", "# Plots\n"),
+            ("src/leak.py", "written inside the sandbox\n"),
+        ],
+    );
+    record_build_and_audit(&recordings, 7);
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+
+    assert!(root.join("tests/bounds/phenomenon/test_drag.py").exists());
+    assert!(!root.join(".lisa/spiral/pass-1/plots/REVIEW.md").exists());
+    assert!(!root.join("src/leak.py").exists());
+    let committed = git(root, &["show", "--name-only", "--format=", ":/bounds:"]);
+    assert!(
+        committed.contains("tests/bounds/phenomenon/test_drag.py"),
+        "bounds commit: {}",
+        committed
+    );
+
+    let log =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/bounds-independence.toml")).unwrap();
+    assert!(log.contains("outcome = \"verified\""));
+    let review =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/review-package.md")).unwrap();
+    assert!(review.contains("**Independence verified:**"));
+    assert!(git(root, &["worktree", "list"]).lines().count() == 1);
+}
+
+#[test]
+fn test_build_editing_bounding_test_is_caught() {
+    let project = setup_project();
+    let root = project.path();
+    std::fs::write(
+        root.join("tests/bounds/test_limits.py"),
+        "assert 1 <= x <= 2\n",
+    )
+    .unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "bounding test"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[
+            (
+                ".lisa/methodology/plan.md",
+                &PLAN_TODO.replace("TODO", "DONE"),
+            ),
+            ("src/model.py", "def model():\n    return 1\n"),
+            ("tests/bounds/test_limits.py", "assert 0 <= x <= 100\n"),
+            ("tests/bounds/test_new.py", "assert True\n"),
+        ],
+    );
+    record(
+        &recordings,
+        7,
+        "Audit: pass 1",
+        &[(".lisa/spiral/pass-1/review-package.md", "# Review\n")],
+    );
+
+    // Flagged by the write scope, then restored by the bounding-test guard
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("tests/bounds/test_limits.py"), "{}", stdout);
+    let log = std::fs::read_to_string(root.join(".lisa/write-scope.toml")).unwrap();
+    assert!(log.contains("phase = \"build\""));
+    assert!(log.contains("\"tests/bounds/test_new.py\""));
+    assert!(log.contains("status = \"reverted\""));
+    assert!(!log.contains("src/model.py"));
+}
+
+#[test]
+fn test_build_tampering_is_restored_and_reported() {
+    let project = setup_project();
+    let root = project.path();
+    std::fs::write(
+        root.join("tests/bounds/test_limits.py"),
+        "assert 1 <= x <= 2\n",
+    )
+    .unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "bounding test"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[
+            ("src/model.py", "def model():\n    return 1\n"),
+            ("tests/bounds/test_limits.py", "assert 0 <= x <= 100\n"),
+        ],
+    );
+    record(
+        &recordings,
+        7,
+        "Build: task 1 iter 2",
+        &[
+            (
+                ".lisa/methodology/plan.md",
+                &PLAN_TODO.replace("TODO", "DONE"),
+            ),
+            ("src/model.py", "def model():\n    return 1.5\n"),
+        ],
+    );
+    record(
+        &recordings,
+        8,
+        "Audit: pass 1",
+        &[(".lisa/spiral/pass-1/review-package.md", "# Review\n")],
+    );
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+    assert_eq!(
+        std::fs::read_to_string(root.join("tests/bounds/test_limits.py")).unwrap(),
+        "assert 1 <= x <= 2\n"
+    );
+    let diff =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/bounds-tamper/task-1-iter-1.diff"))
+            .unwrap();
+    assert!(diff.contains("-assert 1 <= x <= 2\n+assert 0 <= x <= 100\n"));
+
+    // The next iteration is told what was undone
+    let transcript =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/transcripts/build-1-2.md")).unwrap();
+    assert!(transcript.contains("Bounding Test Changes Reverted"));
+    assert!(transcript.contains("+assert 0 <= x <= 100"));
+    assert!(
+        git(root, &["log", "-p", "--", "tests/bounds/test_limits.py"])
+            .lines()
+            .all(|l| !l.contains("0 <= x <= 100"))
+    );
+}
+
+#[test]
+fn test_failed_build_out_of_scope_writes_are_flagged() {
+    let project = setup_project();
+    let root = project.path();
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[(".lisa/methodology/methodology.md", "# Rewritten\n")],
+    );
+    let meta = recordings.join("0006-rec/meta.toml");
+    let content = std::fs::read_to_string(&meta).unwrap();
+    std::fs::write(&meta, content.replace("exit_code = 0", "exit_code = 1")).unwrap();
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert!(!out.status.success());
+    let log = std::fs::read_to_string(root.join(".lisa/write-scope.toml")).unwrap();
+    assert!(log.contains("Build: task 1 iter 1"), "{}", log);
+    assert!(log.contains("methodology/methodology.md"), "{}", log);
+    assert!(log.contains("status = \"pending\""), "{}", log);
+}
+
+#[test]
+fn test_failed_build_tampering_is_restored() {
+    let project = setup_project();
+    let root = project.path();
+    std::fs::write(
+        root.join("lisa.toml"),
+        format!("{}\n[git]\nauto_commit = false\n", LISA_TOML),
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("tests/bounds/test_limits.py"),
+        "assert 1 <= x <= 2\n",
+    )
+    .unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "bounding test"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[("tests/bounds/test_limits.py", "assert 0 <= x <= 100\n")],
+    );
+    let meta = recordings.join("0006-rec/meta.toml");
+    let content = std::fs::read_to_string(&meta).unwrap();
+    std::fs::write(&meta, content.replace("exit_code = 0", "exit_code = 1")).unwrap();
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert!(!out.status.success());
+    assert_eq!(
+        std::fs::read_to_string(root.join("tests/bounds/test_limits.py")).unwrap(),
+        "assert 1 <= x <= 2\n"
+    );
+    assert!(root
+        .join(".lisa/spiral/pass-1/bounds-tamper")
+        .read_dir()
+        .unwrap()
+        .next()
+        .is_some());
+}
+
+#[test]
+fn test_auto_revert_restores_bounding_test() {
+    let project = setup_project();
+    let root = project.path();
+    std::fs::write(
+        root.join("tests/bounds/test_limits.py"),
+        "assert 1 <= x <= 2\n",
+    )
+    .unwrap();
+    let config = format!("{}\n[write_scope]\nauto_revert = true\n", LISA_TOML);
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "bounding test"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[
+            (
+                ".lisa/methodology/plan.md",
+                &PLAN_TODO.replace("TODO", "DONE"),
+            ),
+            ("src/model.py", "def model():\n    return 1\n"),
+            ("tests/bounds/test_limits.py", "assert 0 <= x <= 100\n"),
+            ("tests/bounds/test_new.py", "assert True\n"),
+            (".lisa/methodology/methodology.md", "# Rewritten\n"),
+        ],
+    );
+    record(
+        &recordings,
+        7,
+        "Audit: pass 1",
+        &[(".lisa/spiral/pass-1/review-package.md", "# Review\n")],
+    );
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+    assert_eq!(
+        std::fs::read_to_string(root.join("tests/bounds/test_limits.py")).unwrap(),
+        "assert 1 <= x <= 2\n"
+    );
+    assert!(!root.join("tests/bounds/test_new.py").exists());
+    assert!(root.join("src/model.py").exists());
+    // Unversioned process artifacts are reverted too
+    assert_eq!(
+        std::fs::read_to_string(root.join(".lisa/methodology/methodology.md")).unwrap(),
+        "# Methodology\n\n## 2.1 Core model\n"
+    );
+    let log = std::fs::read_to_string(root.join(".lisa/write-scope.toml")).unwrap();
+    assert!(log.contains("status = \"reverted\""));
+    // The tampered test never reached history
+    assert!(
+        git(root, &["log", "-p", "--", "tests/bounds/test_limits.py"])
+            .lines()
+            .all(|l| !l.contains("0 <= x <= 100"))
+    );
+}
+
+#[test]
+fn test_done_task_is_reopened_when_tests_fail() {
+    let project = setup_project();
+    let root = project.path();
+    let config = format!(
+        "{}\n[commands]\ntest_bounds = \"grep -q 1.5 src/model.py\"\n",
+        LISA_TOML
+    );
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "test command"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    let done = PLAN_TODO.replace("TODO", "DONE");
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[
+            (".lisa/methodology/plan.md", &done),
+            ("src/model.py", "def model():\n    return 1\n"),
+        ],
+    );
+    record(
+        &recordings,
+        7,
+        "Build: task 1 iter 2",
+        &[
+            (".lisa/methodology/plan.md", &done),
+            ("src/model.py", "def model():\n    return 1.5\n"),
+        ],
+    );
+    record(
+        &recordings,
+        8,
+        "Audit: pass 1",
+        &[(".lisa/spiral/pass-1/review-package.md", "# Review\n")],
+    );
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("reopened as IN_PROGRESS"), "{}", stdout);
+    assert!(stdout.contains("Task 1 verified."), "{}", stdout);
+
+    let transcript =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/transcripts/build-1-2.md")).unwrap();
+    assert!(transcript.contains("## Verification Failed"));
+    let plan = std::fs::read_to_string(root.join(".lisa/methodology/plan.md")).unwrap();
+    assert!(plan.contains("**Status:** DONE"));
+}
+
+#[test]
+fn test_status_reports_measured_test_results() {
+    let project = setup_project();
+    let root = project.path();
+    std::fs::create_dir_all(root.join("fixtures")).unwrap();
+    std::fs::write(
+        root.join("fixtures/bounds.xml"),
+        r#"<testsuite>
+<testcase classname="bounds" name="test_lower"/>
+<testcase classname="bounds" name="test_upper"><failure message="too big"/></testcase>
+</testsuite>"#,
+    )
+    .unwrap();
+    let config = format!(
+        "{}\n[commands]\n\
+         test_bounds = \"mkdir -p reports && cp fixtures/bounds.xml reports/\"\n\
+         [commands.results]\n\
+         bounds = {{ format = \"junit\", report = \"reports/bounds.xml\" }}\n",
+        LISA_TOML
+    );
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "results config"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[
+            (
+                ".lisa/methodology/plan.md",
+                &PLAN_TODO.replace("TODO", "DONE"),
+            ),
+            ("src/model.py", "def model():\n    return 1\n"),
+        ],
+    );
+    // The agent claims everything passed
+    record(
+        &recordings,
+        7,
+        "Audit: pass 1",
+        &[(
+            ".lisa/spiral/pass-1/review-package.md",
+            "# Review\n\nBounds: 2/2\n",
+        )],
+    );
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+    let results =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/test-results.toml")).unwrap();
+    assert!(results.contains("passed = 1"), "{}", results);
+    assert!(results.contains("\"bounds::test_upper\""), "{}", results);
+
+    let out = lisa(root, &["status"]);
+    assert_success(&out);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let row = stdout
+        .lines()
+        .find(|l| l.trim_start().starts_with("1 "))
+        .unwrap_or_else(|| panic!("no pass row in status:\n{}", stdout));
+    assert!(row.contains("1/2"), "{}", row);
+}
+
+#[test]
+fn test_setup_build_and_lint_commands_run() {
+    let project = setup_project();
+    let root = project.path();
+    let config = format!(
+        "{}\n[commands]\n\
+         setup = \"touch .lisa/setup-ran\"\n\
+         build = \"grep -q 'def model' src/model.py\"\n\
+         lint = \"! grep -n TODO src/model.py\"\n",
+        LISA_TOML
+    );
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "commands"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    record(
+        &recordings,
+        6,
+        "Build: task 1 iter 1",
+        &[("src/model.py", "def model():\n    return 1  # TODO\n")],
+    );
+    record(
+        &recordings,
+        7,
+        "Build: task 1 iter 2",
+        &[
+            (
+                ".lisa/methodology/plan.md",
+                &PLAN_TODO.replace("TODO", "DONE"),
+            ),
+            ("src/model.py", "def model():\n    return 1\n"),
+        ],
+    );
+    record(
+        &recordings,
+        8,
+        "Audit: pass 1",
+        &[(".lisa/spiral/pass-1/review-package.md", "# Review\n")],
+    );
+
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert_success(&out);
+    assert!(root.join(".lisa/setup-ran").exists());
+
+    let checks = std::fs::read_to_string(root.join(".lisa/spiral/pass-1/checks.toml")).unwrap();
+    let lint: Vec<&str> = checks.lines().filter(|l| l.starts_with("lint")).collect();
+    assert_eq!(
+        lint,
+        vec!["lint = \"fail\"", "lint = \"pass\""],
+        "{}",
+        checks
+    );
+    assert_eq!(checks.matches("build = \"pass\"").count(), 2);
+
+    let transcript =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/transcripts/build-1-2.md")).unwrap();
+    assert!(transcript.contains("### Lint failed"));
+    assert!(transcript.contains("2:    return 1  # TODO"));
+}
+
+#[test]
+fn test_hanging_test_command_is_reported_as_timeout() {
+    let project = setup_project();
+    let root = project.path();
+    let config = LISA_TOML.replace(
+        "max_agent_retries = 0",
+        "max_agent_retries = 0\ncommand_timeout_secs = 1",
+    ) + "\n[commands]\ntest_bounds = \"echo solving; sleep 60\"\n";
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "hanging test"]);
+
+    let recordings = root.join("recordings");
+    record_scope(&recordings);
+    let done = PLAN_TODO.replace("TODO", "DONE");
+    for (n, iter) in [(6, 1), (7, 2)] {
+        record(
+            &recordings,
+            n,
+            &format!("Build: task 1 iter {}", iter),
+            &[(".lisa/methodology/plan.md", &done)],
+        );
+    }
+
+    let started = std::time::Instant::now();
+    let out = lisa(
+        root,
+        &[
+            "run",
+            "--no-pause",
+            "--max-passes",
+            "1",
+            "--replay",
+            "recordings",
+        ],
+    );
+    assert!(started.elapsed() < std::time::Duration::from_secs(30));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("bounds tests hung after 1s"), "{}", stdout);
+    let transcript =
+        std::fs::read_to_string(root.join(".lisa/spiral/pass-1/transcripts/build-1-2.md")).unwrap();
+    assert!(transcript.contains("TIMED OUT: hung after 1s"));
+    assert!(transcript.contains("solving"));
+}
+
+#[test]
+fn test_ctrl_c_stops_agent_and_leaves_resumable_state() {
+    let project = setup_project();
+    let root = project.path();
+    // An agent that starts a long-running child and never answers
+    let config = LISA_TOML.to_string()
+        + r#"
+[models]
+scope = "hang:model"
+
+[agent.backends.hang]
+command = "bash"
+args = ["-c", "sleep 60 & echo $! > solver.pid; wait"]
+model_flag = ""
+"#;
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "hanging agent"]);
+    // Staged by the user before running lisa
+    std::fs::write(root.join("NOTES.md"), "draft\n").unwrap();
+    git(root, &["add", "NOTES.md"]);
+
+    let child = Command::new(env!("CARGO_BIN_EXE_lisa"))
+        .args(["run", "--no-pause"])
+        .current_dir(root)
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let pid_file = root.join("solver.pid");
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(20);
+    while !pid_file.exists() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    let solver = std::fs::read_to_string(&pid_file).unwrap();
+
+    Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let out = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(130), "{}", stdout);
+    assert!(
+        stdout.contains("Run `lisa resume` to continue"),
+        "{}",
+        stdout
+    );
+
+    // A zombie waiting for init to reap it is not alive
+    let solver_alive = std::fs::read_to_string(format!("/proc/{}/stat", solver.trim()))
+        .is_ok_and(|stat| !stat.contains(") Z "));
+    assert!(!solver_alive, "the agent's child process survived Ctrl-C");
+
+    let error = std::fs::read_to_string(root.join(".lisa/last-error.md")).unwrap();
+    assert!(error.contains("INTERRUPTED (Ctrl-C)"), "{}", error);
+    assert!(error.contains("## Interrupted"), "{}", error);
+    let state = std::fs::read_to_string(root.join(".lisa/state.toml")).unwrap();
+    assert!(state.contains("ScopeResearch"), "{}", state);
+    assert!(git(root, &["diff", "--cached", "--name-only"]).contains("NOTES.md"));
+}
+
+#[test]
+fn test_wall_clock_limit_retries_narrower_then_stops() {
+    let project = setup_project();
+    let root = project.path();
+    // An agent that keeps talking and never finishes
+    let config = LISA_TOML.to_string()
+        + r#"
+[limits.phase.research]
+max_wall_secs = 1
+
+[models]
+scope = "chatty:model"
+
+[agent.backends.chatty]
+command = "bash"
+args = ["-c", "while :; do echo '{\"type\":\"system\"}'; sleep 0.2; done"]
+model_flag = ""
+"#;
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "chatty agent"]);
+
+    let out = lisa(root, &["run", "--no-pause"]);
+    assert!(!out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("reached its wall-clock limit of 1s"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("narrower scope"), "{}", stdout);
+    assert!(
+        stdout.contains("narrower retry also stopped, defaulting to STOP"),
+        "{}",
+        stdout
+    );
+
+    // The retry was told why, and both runs count toward the budget
+    let transcripts = root.join(".lisa/spiral/pass-0/transcripts");
+    let retry = std::fs::read_dir(&transcripts)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| std::fs::read_to_string(e.path()).unwrap())
+        .any(|t| t.contains("## Invocation Limit"));
+    assert!(retry, "no transcript of the narrower retry");
+    let ledger = std::fs::read_to_string(root.join(".lisa/usage.toml")).unwrap();
+    assert_eq!(
+        ledger.matches("phase = \"research\"").count(),
+        2,
+        "{}",
+        ledger
+    );
+}
+
+#[test]
+fn test_resume_continues_interrupted_agent_session() {
+    let project = setup_project();
+    let root = project.path();
+    // Fails after reporting its session; when asked to continue it, notes the
+    // session id and the prompt it got, then finishes
+    let config = LISA_TOML.to_string()
+        + r#"
+[models]
+scope = "flaky:model"
+
+[agent.backends.flaky]
+command = "bash"
+args = ["-c", """
+if [ "$0" = "--resume" ]; then
+  echo "$1" > resumed-session.txt; cat > resumed-input.txt
+  echo '{"type":"result","subtype":"success","is_error":false,"result":"done","session_id":"s-1"}'
+else
+  cat > /dev/null; echo '{"type":"system","subtype":"init","session_id":"s-1"}'; exit 1
+fi
+"""]
+model_flag = ""
+resume_flag = "--resume"
+"#;
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "flaky agent"]);
+
+    let out = lisa(root, &["run", "--no-pause"]);
+    assert!(!out.status.success());
+    let state = std::fs::read_to_string(root.join(".lisa/state.toml")).unwrap();
+    assert!(state.contains("[session]"), "{}", state);
+    assert!(state.contains("id = \"s-1\""), "{}", state);
+    assert!(!root.join("resumed-session.txt").exists());
+
+    let out = lisa(root, &["resume", "--no-pause"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("continuing the interrupted agent session"),
+        "{}",
+        stdout
+    );
+    let resumed = std::fs::read_to_string(root.join("resumed-session.txt")).unwrap();
+    assert_eq!(resumed.trim(), "s-1");
+    let input = std::fs::read_to_string(root.join("resumed-input.txt")).unwrap();
+    assert!(input.starts_with("You were interrupted"), "{}", input);
+}
+
+#[test]
+fn test_unavailable_model_falls_back_to_next_in_chain() {
+    let project = setup_project();
+    let root = project.path();
+    // Rejects model "missing"; answers once on any other model, then fails
+    let config = LISA_TOML.to_string()
+        + r#"
+[models]
+scope = ["fake:missing", "fake:backup"]
+
+[agent.backends.fake]
+command = "bash"
+args = ["-c", """
+cat > /dev/null
+if [ "$1" = "missing" ]; then
+  echo 'API Error: 404 {"type":"error","error":{"type":"not_found_error","message":"model: missing"}}' >&2; exit 1
+fi
+[ -e ran-on.txt ] && exit 1
+echo "$1" > ran-on.txt
+echo '{"type":"result","subtype":"success","is_error":false,"result":"done"}'
+"""]
+"#;
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "fallback chain"]);
+
+    let out = lisa(root, &["run", "--no-pause"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains(
+            "Model 'fake:missing' is unavailable or rate-limited — falling back to 'fake:backup'"
+        ),
+        "{}",
+        stdout
+    );
+    let ran_on = std::fs::read_to_string(root.join("ran-on.txt")).unwrap();
+    assert_eq!(ran_on.trim(), "backup");
+
+    // The ledger records the model that ran, and the one it replaced
+    let ledger = std::fs::read_to_string(root.join(".lisa/usage.toml")).unwrap();
+    assert!(ledger.contains("model = \"fake:backup\""), "{}", ledger);
+    assert!(
+        ledger.contains("fallback_from = \"fake:missing\""),
+        "{}",
+        ledger
+    );
+}
+
+#[test]
+fn test_budget_warning_downgrades_listed_phases() {
+    let project = setup_project();
+    let root = project.path();
+    // Records the model it runs on, once
+    let config = LISA_TOML.replace(
+        "max_agent_retries = 0",
+        "max_agent_retries = 0\nbudget_usd = 1.0",
+    ) + r#"
+[limits.downgrade]
+research = "fake:cheap"
+
+[models]
+scope = "fake:pricey"
+
+[agent.backends.fake]
+command = "bash"
+args = ["-c", """
+cat > /dev/null
+[ -e ran-on.txt ] && exit 1
+echo "$1" > ran-on.txt
+echo '{"type":"result","subtype":"success","is_error":false,"result":"done"}'
+"""]
+"#;
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "budget downgrade"]);
+    // 90% of the budget is already spent
+    std::fs::create_dir_all(root.join(".lisa")).unwrap();
+    std::fs::write(
+        root.join(".lisa/usage.toml"),
+        r#"[[invocations]]
+phase = "scope"
+pass = 0
+model = "opus"
+input_tokens = 0
+output_tokens = 0
+cache_creation_input_tokens = 0
+cache_read_input_tokens = 0
+cost_usd = 0.9
+elapsed_secs = 1
+timestamp = "2026-01-01T00:00:00+00:00"
+"#,
+    )
+    .unwrap();
+
+    let out = lisa(root, &["run", "--no-pause"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("running 'Research' on 'fake:cheap' instead of 'fake:pricey'"),
+        "{}",
+        stdout
+    );
+    let ran_on = std::fs::read_to_string(root.join("ran-on.txt")).unwrap();
+    assert_eq!(ran_on.trim(), "cheap");
+
+    let out = lisa(root, &["status"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("research: fake:pricey → fake:cheap (1 invocations)"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_ready_tasks_build_in_parallel_lanes_and_merge_back() {
+    let project = setup_project();
+    let root = project.path();
+    // Implements the assigned task in its own file plus a file both tasks
+    // write differently, and marks the task done
+    let config = LISA_TOML.replace(
+        "max_agent_retries = 0",
+        "max_agent_retries = 0\nmax_parallel_tasks = 2",
+    ) + r#"
+[models]
+build = "fake:model"
+audit = "fake:model"
+
+[agent.backends.fake]
+command = "bash"
+args = ["-c", """
+task=$(sed -n 's/^Assigned task number: //p' | head -n 1)
+if [ -n "$task" ]; then
+  echo "x = $task" > src/task$task.py
+  echo "$task" > src/shared.py
+  sed -i "/### Task $task:/,/Status/ s/IN_PROGRESS/DONE/" .lisa/methodology/plan.md
+fi
+echo '{"type":"result","subtype":"success","is_error":false,"result":"done"}'
+"""]
+"#;
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "parallel lanes"]);
+    let plan = "# Implementation Plan\n\n## Tasks\n\n\
+                ### Task 1: Drag\n- **Status:** TODO\n- **Pass:** 1\n- **Dependencies:** None\n\n\
+                ### Task 2: Lift\n- **Status:** TODO\n- **Pass:** 1\n- **Dependencies:** None\n";
+    std::fs::create_dir_all(root.join(".lisa/methodology")).unwrap();
+    std::fs::write(root.join(".lisa/methodology/plan.md"), plan).unwrap();
+    std::fs::write(
+        root.join(".lisa/state.toml"),
+        "state = \"RefineComplete\"\npass = 1\n",
+    )
+    .unwrap();
+
+    let out = lisa(root, &["resume", "--no-pause"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        stdout.contains("Building tasks 1, 2 in parallel worktrees."),
+        "{}",
+        stdout
+    );
+    // Task 1 merges first; task 2 clashes on shared.py, is kept on a branch and redone
+    assert!(
+        stdout.contains("Task 2 conflicts with work merged before it"),
+        "{}",
+        stdout
+    );
+    assert!(git(root, &["log", "--format=%s"]).contains("Merge lane: pass 1 task 1"));
+    assert!(git(root, &["branch", "--list", "lisa/pass-1-task-2"]).contains("lisa/pass-1-task-2"));
+    assert_eq!(git(root, &["show", "HEAD:src/shared.py"]).trim(), "2");
+    assert!(root.join("src/task1.py").exists());
+    assert!(root.join("src/task2.py").exists());
+    let plan = std::fs::read_to_string(root.join(".lisa/methodology/plan.md")).unwrap();
+    assert_eq!(plan.matches("**Status:** DONE").count(), 2, "{}", plan);
+
+    // Both lanes' invocations reach the ledger, and their worktrees are gone
+    let ledger = std::fs::read_to_string(root.join(".lisa/usage.toml")).unwrap();
+    assert!(
+        ledger.matches("phase = \"build\"").count() >= 3,
+        "{}",
+        ledger
+    );
+    assert_eq!(git(root, &["worktree", "list"]).lines().count(), 1);
+}
+
+#[test]
+fn test_failed_lane_keeps_its_worktree_and_commits() {
+    let project = setup_project();
+    let root = project.path();
+    // Task 2 commits part of its work, leaves more uncommitted and fails
+    let config = LISA_TOML.replace(
+        "max_agent_retries = 0",
+        "max_agent_retries = 0\nmax_parallel_tasks = 2",
+    ) + r#"
+[models]
+build = "fake:model"
+
+[agent.backends.fake]
+command = "bash"
+args = ["-c", """
+task=$(sed -n 's/^Assigned task number: //p' | head -n 1)
+echo "x = $task" > src/task$task.py
+echo "$task" > notes$task.txt
+if [ "$task" = 2 ]; then
+  git add src/task2.py && git commit -q -m "task 2 part one"
+  echo "wip" > src/wip.py
+  exit 1
+fi
+sed -i "/### Task $task:/,/Status/ s/IN_PROGRESS/DONE/" .lisa/methodology/plan.md
+echo '{"type":"result","subtype":"success","is_error":false,"result":"done"}'
+"""]
+"#;
+    std::fs::write(root.join("lisa.toml"), config).unwrap();
+    git(root, &["add", "-A"]);
+    git(root, &["commit", "-q", "-m", "parallel lanes"]);
+    let plan = "# Implementation Plan\n\n## Tasks\n\n\
+                ### Task 1: Drag\n- **Status:** TODO\n- **Pass:** 1\n- **Dependencies:** None\n\n\
+                ### Task 2: Lift\n- **Status:** TODO\n- **Pass:** 1\n- **Dependencies:** None\n";
+    std::fs::create_dir_all(root.join(".lisa/methodology")).unwrap();
+    std::fs::write(root.join(".lisa/methodology/plan.md"), plan).unwrap();
+    std::fs::write(
+        root.join(".lisa/state.toml"),
+        "state = \"RefineComplete\"\npass = 1\n",
+    )
+    .unwrap();
+
+    let out = lisa(root, &["resume", "--no-pause"]);
+    assert!(!out.status.success());
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(git(root, &["log", "--format=%s"]).contains("Merge lane: pass 1 task 1"));
+    let state = std::fs::read_to_string(root.join(".lisa/state.toml")).unwrap();
+    assert!(state.contains("lanes = ["), "{}", state);
+    // Both lanes' out-of-scope writes reach the project's log
+    let log = std::fs::read_to_string(root.join(".lisa/write-scope.toml")).unwrap();
+    assert!(log.contains("\"notes1.txt\""), "{}", log);
+    assert!(log.contains("\"notes2.txt\""), "{}", log);
+
+    // The failed lane's commit is on a branch and its worktree, with the
+    // uncommitted edit, is still there
+    assert_eq!(
+        git(root, &["log", "-1", "--format=%s", "lisa/pass-1-task-2"]).trim(),
+        "task 2 part one"
+    );
+    let worktrees = git(root, &["worktree", "list", "--porcelain"]);
+    let kept: Vec<&str> = worktrees
+        .lines()
+        .filter_map(|l| l.strip_prefix("worktree "))
+        .skip(1)
+        .collect();
+    assert_eq!(kept.len(), 1, "{}", worktrees);
+    assert!(
+        stdout.contains(&format!("Worktree of task 2 kept at {}", kept[0])),
+        "{}",
+        stdout
+    );
+    assert!(Path::new(kept[0]).join("src/wip.py").exists());
+    git(root, &["worktree", "remove", "--force", kept[0]]);
+}
+
+#[test]
+fn test_tasks_command_edits_plan() {
+    let project = setup_project();
+    let root = project.path();
+    let plan = "# Implementation Plan\n\nKeep this note.\n\n## Tasks\n\n\
+                ### Task 1: Drag\n- **Status:** DONE\n- **Pass:** 1\n- **Dependencies:** None\n\n\
+                ### Task 2: Lift\n- **Status:** BLOCKED\n- **Pass:** 1\n- **Dependencies:** Task 1\n\n\
+                ### Task 3: Moment\n- **Status:** TODO\n- **Pass:** 2\n- **Dependencies:** Task 2\n";
+    std::fs::create_dir_all(root.join(".lisa/methodology")).unwrap();
+    std::fs::write(root.join(".lisa/methodology/plan.md"), plan).unwrap();
+
+    let out = lisa(root, &["tasks", "list", "--pass", "1", "--json"]);
+    assert_success(&out);
+    let listed: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    let statuses: Vec<&str> = listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, ["DONE", "BLOCKED"]);
+
+    // Nothing is ready while Task 2 is blocked
+    let out = lisa(root, &["tasks", "next", "--pass", "1", "--json"]);
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "null");
+    assert_success(&lisa(root, &["tasks", "unblock", "2"]));
+    let out = lisa(root, &["tasks", "next", "--pass", "1", "--json"]);
+    let next: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(next["number"], 2);
+
+    // Edits that would break the plan are refused
+    let out = lisa(root, &["tasks", "add-dep", "2", "3"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("would create a cycle"));
+    assert!(!lisa(root, &["tasks", "set-status", "3", "SOON"])
+        .status
+        .success());
+    assert!(!lisa(root, &["tasks", "block", "9"]).status.success());
+
+    let out = lisa(root, &["tasks", "graph", "--format", "dot"]);
+    assert_success(&out);
+    let dot = String::from_utf8_lossy(&out.stdout);
+    assert!(dot.contains("subgraph cluster_pass_2"));
+    assert!(dot.contains("task2 -> task3 [color=\"#d32f2f\", penwidth=2];"));
+    let out = lisa(root, &["tasks", "graph"]);
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("flowchart LR\n"));
+
+    assert_success(&lisa(root, &["tasks", "rm-dep", "3", "2"]));
+    assert_success(&lisa(root, &["tasks", "set-status", "3", "in_progress"]));
+    let edited = std::fs::read_to_string(root.join(".lisa/methodology/plan.md")).unwrap();
+    assert_eq!(
+        edited,
+        plan.replace("BLOCKED", "TODO").replace(
+            "- **Status:** TODO\n- **Pass:** 2\n- **Dependencies:** Task 2",
+            "- **Status:** IN_PROGRESS\n- **Pass:** 2\n- **Dependencies:** None"
+        )
+    );
+}
+
+const LISA_TOML: &str = r#"[project]
+name = "replay-test"
+
+[limits]
+max_spiral_passes = 1
+max_agent_retries = 0
+
+[review]
+pause = false
+
+[paths]
+lisa_root = ".lisa"
+source = ["src"]
+tests_bounds = "tests/bounds"
+tests_software = "tests/software"
+tests_integration = "tests/integration"
+"#;